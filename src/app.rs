@@ -1,10 +1,17 @@
 mod cfg;
 mod cli;
+mod clipboard;
 mod consts;
 mod context;
 mod crypto;
 mod entry;
 mod errors;
+mod failed_unlock;
+mod fuzzy;
+mod generator;
+mod listing;
+mod main_pwd_input;
+mod secret;
 mod storage;
 mod tui;
 
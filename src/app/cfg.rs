@@ -1,4 +1,10 @@
-use crate::app::consts::{APP_NAME, CONF_FILE_NAME, DATA_FILE_NAME, ENV_CONF_PATH_KEY, ENV_DEFAULT_DATA_FILE_PATH_KEY};
+use crate::app::clipboard::ClipboardBackend;
+use crate::app::consts::{
+    APP_NAME, CONF_FILE_NAME, DATA_FILE_NAME, DEFAULT_HISTORY_KEEP, DEFAULT_TRASH_PURGE_DAYS,
+    ENV_CONF_PATH_KEY, ENV_DEFAULT_DATA_FILE_PATH_KEY,
+};
+use crate::app::crypto::envelope::CipherAlg;
+use crate::app::crypto::kdf::KdfParams;
 use crate::app::storage::{Storage, kv_cfg::BitCfg};
 use serde::Deserialize;
 use std::env;
@@ -10,6 +16,10 @@ use std::path::{Path, PathBuf};
 pub struct Cfg {
     /// pnt data file - 运行时或可被参数替换，否则从配置文件或env找路径
     pub load_data: PathBuf,
+    /// 密钥文件 - 运行时或可被参数替换，否则从配置文件找，仅 data file 要求密钥文件时使用
+    pub keyfile: Option<PathBuf>,
+    /// 剪贴板后端 - 从配置文件找，未配置则自动探测
+    pub clipboard: ClipboardBackend,
     /// 内部配置，从 data file 中读取
     pub inner_cfg: InnerCfg,
 }
@@ -22,6 +32,29 @@ pub struct InnerCfg {
     pub immediate_lock_screen: bool,
     pub auto_relock_idle_sec: Option<u32>,
     pub auto_close_idle_sec: Option<u32>,
+    /// TUI 复制到剪贴板的内容在多少秒后清除（若剪贴板仍为该内容）
+    pub clipboard_clear_sec: Option<u32>,
+    /// 详情及编辑页面打开时即显示密码明文，否则掩码
+    pub reveal_password: bool,
+    /// 显示的密码在多少秒后重新掩码
+    pub reveal_password_sec: Option<u32>,
+    /// 主密码校验失败次数上限（跨进程累计），达到后锁定或擦除 data file
+    pub max_failed_unlock: Option<u32>,
+    /// 失败次数达到上限时擦除 data file，否则仅锁定（可由 `pnt recover` 解除）
+    pub wipe_on_max_failed_unlock: bool,
+    /// 每个条目保留的历史版本个数，0 即更新条目时不保留历史版本
+    pub history_keep: u32,
+    /// 回收站中的条目在多少天后永久删除，0 即不自动删除
+    pub trash_purge_days: u32,
+    /// 全加密模式，about 及 notes 亦加密，
+    /// 该值反映 data file 状态，切换需重新加密所有条目，遂不由 [`InnerCfg::save_to_data`] 存储
+    pub full_encrypt: bool,
+    /// 新写入密文使用的加密算法，
+    /// 该值反映 data file 状态，切换时重新加密所有条目，遂不由 [`InnerCfg::save_to_data`] 存储
+    pub cipher: CipherAlg,
+    /// 主密码 KDF 参数，
+    /// 该值与主密码 hash 绑定，变更需 re-key，遂不由 [`InnerCfg::save_to_data`] 存储
+    pub kdf: KdfParams,
 }
 
 /// Inner 配置 的 默认配置，data file 中没有的，使用默认配置
@@ -33,6 +66,16 @@ impl Default for InnerCfg {
             immediate_lock_screen: false,
             auto_relock_idle_sec: Some(60), // 1 min
             auto_close_idle_sec: Some(90), // 1.5 min
+            clipboard_clear_sec: None,
+            reveal_password: false,
+            reveal_password_sec: None,
+            max_failed_unlock: None,
+            wipe_on_max_failed_unlock: false,
+            history_keep: DEFAULT_HISTORY_KEEP,
+            trash_purge_days: DEFAULT_TRASH_PURGE_DAYS,
+            full_encrypt: false,
+            cipher: CipherAlg::default(),
+            kdf: KdfParams::default(),
         }
     }
 }
@@ -46,6 +89,26 @@ impl InnerCfg {
     pub const AUTO_RELOCK_IDLE_SEC: &'static str = "auto-relock-idle-sec";
     /// 配置名常量
     pub const AUTO_CLOSE_IDLE_SEC: &'static str = "auto-close-idle-sec";
+    /// 配置名常量
+    pub const CLIPBOARD_CLEAR_SEC: &'static str = "clipboard-clear-sec";
+    /// 配置名常量
+    pub const REVEAL_PASSWORD: &'static str = "reveal-password";
+    /// 配置名常量
+    pub const REVEAL_PASSWORD_SEC: &'static str = "reveal-password-sec";
+    /// 配置名常量
+    pub const MAX_FAILED_UNLOCK: &'static str = "max-failed-unlock";
+    /// 配置名常量
+    pub const WIPE_ON_MAX_FAILED_UNLOCK: &'static str = "wipe-on-max-failed-unlock";
+    /// 配置名常量
+    pub const HISTORY_KEEP: &'static str = "history-keep";
+    /// 配置名常量
+    pub const TRASH_PURGE_DAYS: &'static str = "trash-purge-days";
+    /// 配置名常量
+    pub const FULL_ENCRYPT: &'static str = "full-encrypt";
+    /// 配置名常量
+    pub const CIPHER: &'static str = "cipher";
+    /// 配置名常量
+    pub const KDF: &'static str = "kdf";
 
     /// 将配置文件的 inner_cfg 覆盖
     pub fn overwrite_default(&mut self, storage: &Storage) -> anyhow::Result<()> {
@@ -53,9 +116,21 @@ impl InnerCfg {
         if let Some(bf) = bf_or {
             self.verify_on_launch = bf.contains(BitCfg::VERIFY_ON_LAUNCH);
             self.immediate_lock_screen = bf.contains(BitCfg::IMMEDIATE_LOCK_SCREEN);
+            self.wipe_on_max_failed_unlock = bf.contains(BitCfg::WIPE_ON_MAX_FAILED_UNLOCK);
+            self.reveal_password = bf.contains(BitCfg::REVEAL_PASSWORD);
         }
         self.auto_relock_idle_sec = storage.query_cfg_auto_relock_idle_sec()?;
         self.auto_close_idle_sec = storage.query_cfg_auto_close_idle_sec()?;
+        self.clipboard_clear_sec = storage.query_cfg_clipboard_clear_sec()?;
+        self.reveal_password_sec = storage.query_cfg_reveal_password_sec()?;
+        self.max_failed_unlock = storage.query_cfg_max_failed_unlock()?;
+        self.history_keep = storage.query_cfg_history_keep()?.unwrap_or(DEFAULT_HISTORY_KEEP);
+        self.trash_purge_days = storage
+            .query_cfg_trash_purge_days()?
+            .unwrap_or(DEFAULT_TRASH_PURGE_DAYS);
+        self.full_encrypt = storage.query_full_encrypt()?;
+        self.cipher = storage.query_cipher_alg()?;
+        self.kdf = storage.query_kdf_params()?;
         Ok(())
     }
 
@@ -70,10 +145,21 @@ impl InnerCfg {
         if self.immediate_lock_screen {
             bf.insert(BitCfg::IMMEDIATE_LOCK_SCREEN);
         }
+        if self.wipe_on_max_failed_unlock {
+            bf.insert(BitCfg::WIPE_ON_MAX_FAILED_UNLOCK);
+        }
+        if self.reveal_password {
+            bf.insert(BitCfg::REVEAL_PASSWORD);
+        }
         // store
         storage.store_cfg_bit_flags(bf);
         storage.store_cfg_auto_re_lock_idle_sec(self.auto_relock_idle_sec.unwrap_or(0));
         storage.store_cfg_auto_close_idle_sec(self.auto_close_idle_sec.unwrap_or(0));
+        storage.store_cfg_clipboard_clear_sec(self.clipboard_clear_sec.unwrap_or(0));
+        storage.store_cfg_reveal_password_sec(self.reveal_password_sec.unwrap_or(0));
+        storage.store_cfg_max_failed_unlock(self.max_failed_unlock.unwrap_or(0));
+        storage.store_cfg_history_keep(self.history_keep);
+        storage.store_cfg_trash_purge_days(self.trash_purge_days);
     }
 }
 
@@ -93,6 +179,36 @@ impl Display for InnerCfg {
             Self::AUTO_CLOSE_IDLE_SEC,
             self.auto_close_idle_sec.unwrap_or(0)
         )?;
+        writeln!(
+            f,
+            "{} = {}",
+            Self::CLIPBOARD_CLEAR_SEC,
+            self.clipboard_clear_sec.unwrap_or(0)
+        )?;
+        writeln!(f, "{} = {}", Self::REVEAL_PASSWORD, self.reveal_password)?;
+        writeln!(
+            f,
+            "{} = {}",
+            Self::REVEAL_PASSWORD_SEC,
+            self.reveal_password_sec.unwrap_or(0)
+        )?;
+        writeln!(
+            f,
+            "{} = {}",
+            Self::MAX_FAILED_UNLOCK,
+            self.max_failed_unlock.unwrap_or(0)
+        )?;
+        writeln!(
+            f,
+            "{} = {}",
+            Self::WIPE_ON_MAX_FAILED_UNLOCK,
+            self.wipe_on_max_failed_unlock
+        )?;
+        writeln!(f, "{} = {}", Self::HISTORY_KEEP, self.history_keep)?;
+        writeln!(f, "{} = {}", Self::TRASH_PURGE_DAYS, self.trash_purge_days)?;
+        writeln!(f, "{} = {}", Self::FULL_ENCRYPT, self.full_encrypt)?;
+        writeln!(f, "{} = {}", Self::CIPHER, self.cipher)?;
+        writeln!(f, "{} = {}", Self::KDF, self.kdf)?;
         Ok(())
     }
 }
@@ -146,22 +262,22 @@ impl From<TomlCfg> for Cfg {
                 .default_data
                 .or_else(env_data_path) // toml 中未配置，尝试使用 env_data_path
                 .unwrap_or_else(default_data_path), // toml 未配置，env未有，尝试使用默认路径位置，即toml中配置优先级最高
+            keyfile: value.keyfile,
+            clipboard: value.clipboard.unwrap_or_default(),
             inner_cfg: InnerCfg::default(),
         }
     }
 }
 
 /// app 配置文件
-#[derive(Deserialize, Debug, Eq, PartialEq)]
+#[derive(Deserialize, Debug, Default, Eq, PartialEq)]
 pub(super) struct TomlCfg {
     /// 存储各密码的sqlite-db路径, 默认在 app data，为防止toml的 " 转义，win路径反斜杠路径应使用 ' 符号
     pub(super) default_data: Option<PathBuf>,
-}
-
-impl Default for TomlCfg {
-    fn default() -> Self {
-        Self { default_data: None }
-    }
+    /// 密钥文件路径，密钥文件应仅存于本机，遂由本机配置文件给定而非存储于 data file
+    pub(super) keyfile: Option<PathBuf>,
+    /// 剪贴板后端，剪贴板取决于本机环境（如经 SSH 时仅 OSC 52 可用），遂由本机配置文件给定
+    pub(super) clipboard: Option<ClipboardBackend>,
 }
 
 /// 从磁盘载入配置文件，若配置文件存在则载入，
@@ -1,15 +1,30 @@
 use crate::app::cfg::{InnerCfg, load_cfg};
-use crate::app::consts::{ALLOC_INVALID_MAIN_PASS_MAX, APP_NAME};
+use crate::app::clipboard::{self, ClipboardBackend};
+use crate::app::consts::{ALLOC_INVALID_MAIN_PASS_MAX, APP_NAME, CLI_CLIPBOARD_HOLD_SEC, MAIN_PWD_MIN_LEN};
 use crate::app::context::{DataFileState, PntContext};
-use crate::app::crypto::{Encrypter, MainPwdEncrypter, MainPwdVerifier};
+use crate::app::crypto::entry_secret::EntryEncryptMode;
+use crate::app::crypto::envelope::CipherAlg;
+use crate::app::crypto::kdf::KdfParams;
+use crate::app::crypto::keyfile::Keyfile;
+use crate::app::crypto::subkey::{DataKey, RecoveryKey};
+use crate::app::crypto::MainPwdEncrypter;
+use crate::app::crypto::Encrypter;
+use crate::app::entry::{InputEntry, normalize_group_path, parse_tags};
 use crate::app::errors::AppError;
+use crate::app::generator::{CharClasses, DEFAULT_PASSPHRASE_WORDS, PasswordPolicy, is_valid_policy_name};
+use crate::app::listing::{self, ListFormat, ListSort, ListedEntry};
+use crate::app::main_pwd_input::ScriptedMainPwd;
+use crate::app::failed_unlock::{FailedUnlock, secs_until};
+use crate::app::secret::SecretString;
 use crate::app::storage::Storage;
 use anyhow::anyhow;
+use chrono::{DateTime, Local};
 use clap::Args;
 use clap::{Parser, Subcommand};
 use ratatui::crossterm::style::Stylize;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// runtime cli args...
 #[derive(Parser, Debug)]
@@ -19,6 +34,15 @@ pub struct CliArgs {
     #[arg( global = true ,short='d',long= "data", value_name = "DATA_FILE", help = Self::CLI_HELP_DATA
     )]
     data: Option<PathBuf>,
+    /// 要求使用的密钥文件
+    #[arg(global = true, long = "keyfile", value_name = "KEYFILE", help = Self::CLI_HELP_KEYFILE)]
+    keyfile: Option<PathBuf>,
+    /// 从 stdin 读取主密码（非交互）
+    #[arg(global = true, long = "main-password-stdin", conflicts_with = "main_password_file", help = Self::CLI_HELP_MP_STDIN)]
+    main_password_stdin: bool,
+    /// 从文件读取主密码（非交互）
+    #[arg(global = true, long = "main-password-file", value_name = "FILE", help = Self::CLI_HELP_MP_FILE)]
+    main_password_file: Option<PathBuf>,
     /// 要通过 about 值模糊查找的 条目
     #[arg(short = 'f', long = "find", value_name = "ABOUT", help = Self::CLI_HELP_FIND)]
     find: Option<String>,
@@ -31,6 +55,17 @@ impl CliArgs {
     const CLI_HELP_DATA: &'static str = "Use the specified data file,
 if this option is not provided,
 Use the default data file (default_data)";
+    const CLI_HELP_KEYFILE: &'static str = "Use the specified keyfile as a second unlock factor,
+with `init`, the new data file will require this keyfile,
+if this option is not provided,
+Use the keyfile in the configuration file (keyfile)";
+    const CLI_HELP_MP_STDIN: &'static str = "Read the main password from stdin instead of prompting,
+one per line, `mmp` reads the new main password from the next line,
+an invalid main password fails immediately.
+Without this option or --main-password-file, `PNT_PASSWORD`
+(and `PNT_NEW_PASSWORD` for `mmp`) is used if set";
+    const CLI_HELP_MP_FILE: &'static str = "Read the main password from the file (or /dev/fd/N) instead of prompting,
+one per line, like --main-password-stdin";
     const CLI_HELP_FIND: &'static str = "Find for entries with similar 'about' values";
 }
 
@@ -44,10 +79,50 @@ enum SubCmd {
     #[command(name = "init",
     about = Self::SUB_INIT_HELP_HEAD,
     long_about = Self::SUB_INIT_HELP)]
-    Init,
+    Init(SubCmdInitArgs),
     /// Modify the main password in an interactive context
     #[command(name = "mmp")]
     ModifyMainPwd,
+    /// 子命令 以新的 KDF 参数重新派生密钥并重新包裹数据密钥
+    #[command(name = "rekey",
+    about = Self::SUB_REKEY_HELP_HEAD,
+    long_about = Self::SUB_REKEY_HELP)]
+    Rekey(SubCmdRekeyArgs),
+    /// Generate a new recovery key (the previous one no longer works)
+    #[command(name = "recovery-key")]
+    RecoveryKey,
+    /// 子命令 以恢复密钥重设遗忘的主密码
+    #[command(name = "recover",
+    about = Self::SUB_RECOVER_HELP_HEAD,
+    long_about = Self::SUB_RECOVER_HELP)]
+    Recover,
+    /// 子命令 非交互地输出或复制单个条目的字段
+    #[command(name = "get",
+    about = Self::SUB_GET_HELP_HEAD,
+    long_about = Self::SUB_GET_HELP)]
+    Get(SubCmdGetArgs),
+    /// 子命令 以可解析的格式列出条目
+    #[command(name = "list",
+    about = Self::SUB_LIST_HELP_HEAD,
+    long_about = Self::SUB_LIST_HELP)]
+    List(SubCmdListArgs),
+    /// 子命令 按策略生成密码
+    #[command(name = "gen",
+    about = Self::SUB_GEN_HELP_HEAD,
+    long_about = Self::SUB_GEN_HELP)]
+    Gen(SubCmdGenArgs),
+    /// Add an entry non-interactively
+    #[command(name = "add")]
+    Add(SubCmdAddArgs),
+    /// Edit fields of a single entry non-interactively
+    #[command(name = "edit")]
+    Edit(SubCmdEditArgs),
+    /// Move a single entry to the trash (or remove it permanently)
+    #[command(name = "rm")]
+    Remove(SubCmdRemoveArgs),
+    /// Move a single entry to a group
+    #[command(name = "mv")]
+    Move(SubCmdMoveArgs),
     /// 子命令 print 或 修改 cfg
     #[command(name = "cfg",
     about = Self::SUB_CFG_HELP_HEAD,
@@ -63,6 +138,34 @@ impl SubCmd {
 .2. The `default_data` value in the configuration file (default config file)
 .3. The value specified by the environment variable `PNT_DEFAULT_DATA_FILE`
 .4. Default path";
+    const SUB_REKEY_HELP_HEAD: &'static str =
+        "Re-derive the key with new KDF parameters";
+    const SUB_REKEY_HELP: &'static str =
+        "Re-derive the key with new KDF parameters and re-wrap the data key with it.
+\nKDF parameters not specified keep their current values (view them via `pnt cfg`),
+the main password itself is not changed.";
+    const SUB_RECOVER_HELP_HEAD: &'static str =
+        "Reset a forgotten main password with the recovery key";
+    const SUB_RECOVER_HELP: &'static str =
+        "Reset a forgotten main password with the recovery key.
+\nThe recovery key is shown by `pnt init` or `pnt recovery-key`, entries are kept.
+If the data file requires a keyfile, it is still required.";
+    const SUB_GET_HELP_HEAD: &'static str = "Print or copy a field of a single entry";
+    const SUB_GET_HELP: &'static str = "Print or copy a field of a single entry.
+\nThe entry is matched by its id, or by its 'about' (case-insensitive, partial),
+an exact 'about' wins over partial matches.
+It fails if no entry or more than one entry matches.";
+    const SUB_LIST_HELP_HEAD: &'static str = "List entries in a table, JSON or TSV";
+    const SUB_LIST_HELP: &'static str = "List entries in a table, JSON or TSV.
+\nEach entry is listed with its id, about, notes, tags, group, created_time and updated_time,
+usernames and passwords are listed only with `--secrets`, which requires the main password.
+TSV values escape '\\', tab and newline as '\\\\', '\\t' and '\\n'.";
+    const SUB_GEN_HELP_HEAD: &'static str = "Generate a password or passphrase";
+    const SUB_GEN_HELP: &'static str = "Generate a password or passphrase.
+\nThe policy options apply on top of the named policy given by --policy,
+or on top of the policy named 'default' in the data file if there is one,
+or on top of the built-in default (20 characters of all classes, at least one of each).
+Named policies are saved with --save and removed with `pnt cfg --remove-gen-policy`.";
     const SUB_CFG_HELP_HEAD: &'static str =
         "Management of configuration related to specific data files";
     const SUB_CFG_HELP: &'static str = "Management of configuration related to specific data files.
@@ -70,6 +173,353 @@ impl SubCmd {
 it will print the current state of all configurations.";
 }
 
+#[derive(Args, Debug)]
+struct SubCmdInitArgs {
+    /// Full encryption mode: the 'about' and 'notes' of entries are encrypted too.
+    /// Listing and finding entries then requires the main password
+    #[arg(long = InnerCfg::FULL_ENCRYPT)]
+    full_encrypt: bool,
+    /// Cipher used to encrypt entries (aes-256-gcm, xchacha20-poly1305)
+    #[arg(long = InnerCfg::CIPHER, value_name = "ALGORITHM", value_parser = parse_cipher_alg, default_value_t = CipherAlg::default())]
+    cipher: CipherAlg,
+    #[command(flatten)]
+    kdf: KdfArgs,
+}
+
+#[derive(Args, Debug)]
+struct SubCmdGetArgs {
+    /// Entry id or 'about', a number is matched as an entry id first
+    #[arg(value_name = "ABOUT_OR_ID")]
+    query: String,
+    /// Field to print or copy: username, password, notes or the name of a custom field
+    #[arg(long = "field", value_name = "FIELD", default_value = "password")]
+    field: String,
+    /// Copy the field to the clipboard instead of printing it
+    #[arg(long = "copy")]
+    copy: bool,
+}
+
+#[derive(Args, Debug)]
+struct SubCmdListArgs {
+    /// Only list entries with similar 'about' values
+    #[arg(value_name = "ABOUT")]
+    filter: Option<String>,
+    /// Only list entries with the tag, repeat to require several tags
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+    /// Only list entries in the group or its subgroups, '/' lists entries in no group
+    #[arg(long = "group", value_name = "GROUP")]
+    group: Option<String>,
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = ListFormat::default())]
+    format: ListFormat,
+    /// Sort order
+    #[arg(long = "sort", value_enum, default_value_t = ListSort::default())]
+    sort: ListSort,
+    /// Also list usernames and passwords (requires the main password)
+    #[arg(long = "secrets")]
+    secrets: bool,
+}
+
+#[derive(Args, Debug)]
+struct SubCmdGenArgs {
+    /// Use the named policy saved in the data file
+    #[arg(long = "policy", value_name = "NAME")]
+    policy: Option<String>,
+    #[command(flatten)]
+    policy_args: GenPolicyArgs,
+    /// Save the resulting policy in the data file under the name (requires the main password)
+    #[arg(long = "save", value_name = "NAME", value_parser = parse_gen_policy_name)]
+    save: Option<String>,
+    /// Copy the password to the clipboard instead of printing it
+    #[arg(long = "copy")]
+    copy: bool,
+}
+
+/// 密码生成策略参数，覆盖于基础策略之上
+#[derive(Args, Debug)]
+struct GenPolicyArgs {
+    /// Password length
+    #[arg(short = 'l', long = "length", value_name = "LENGTH")]
+    length: Option<u16>,
+    /// Character classes: u (upper), l (lower), d (digit), s (symbol), e.g. 'uld'
+    #[arg(long = "classes", value_name = "CLASSES", value_parser = parse_char_classes)]
+    classes: Option<CharClasses>,
+    /// Exclude ambiguous characters (0 O 1 l I)
+    #[arg(long = "no-ambiguous")]
+    no_ambiguous: bool,
+    /// Do not guarantee at least one character of each class
+    #[arg(long = "no-require-each")]
+    no_require_each: bool,
+    /// Generate a passphrase of the given number of words instead
+    #[arg(long = "words", value_name = "COUNT",
+    conflicts_with_all = ["length", "classes", "no_ambiguous", "no_require_each"])]
+    words: Option<u16>,
+    /// Passphrase word separator (default '-')
+    #[arg(long = "separator", value_name = "SEPARATOR",
+    conflicts_with_all = ["length", "classes", "no_ambiguous", "no_require_each"])]
+    separator: Option<String>,
+}
+
+impl GenPolicyArgs {
+    /// 以给定参数覆盖基础策略，
+    /// 给定参数与基础策略的类型不同时，以该类型的默认策略为基础
+    fn overwrite(&self, base: PasswordPolicy) -> Result<PasswordPolicy, AppError> {
+        let chars_args =
+            self.length.is_some() || self.classes.is_some() || self.no_ambiguous || self.no_require_each;
+        let passphrase_args = self.words.is_some() || self.separator.is_some();
+        let mut policy = match base {
+            PasswordPolicy::Chars { .. } if passphrase_args => PasswordPolicy::passphrase(DEFAULT_PASSPHRASE_WORDS),
+            PasswordPolicy::Passphrase { .. } if chars_args => PasswordPolicy::default(),
+            base => base,
+        };
+        match &mut policy {
+            PasswordPolicy::Chars {
+                length,
+                classes,
+                exclude_ambiguous,
+                require_each,
+            } => {
+                *length = self.length.unwrap_or(*length);
+                *classes = self.classes.unwrap_or(*classes);
+                *exclude_ambiguous |= self.no_ambiguous;
+                *require_each &= !self.no_require_each;
+            }
+            PasswordPolicy::Passphrase { words, separator } => {
+                *words = self.words.unwrap_or(*words);
+                if let Some(sep) = &self.separator {
+                    separator.clone_from(sep);
+                }
+            }
+        }
+        policy.validate()?;
+        Ok(policy)
+    }
+}
+
+#[derive(Args, Debug)]
+struct SubCmdAddArgs {
+    /// Entry 'about'
+    #[arg(long = "about")]
+    about: String,
+    /// Entry username
+    #[arg(long = "username")]
+    username: String,
+    /// Entry notes
+    #[arg(long = "notes", default_value = "")]
+    notes: String,
+    /// Entry tags, separated by spaces or commas
+    #[arg(long = "tags", default_value = "")]
+    tags: String,
+    /// Entry group path, e.g. 'work/aws'
+    #[arg(long = "group", default_value = "")]
+    group: String,
+    #[command(flatten)]
+    fields: EntryFieldArgs,
+    /// Without a password source, the password is prompted for
+    #[command(flatten)]
+    password: EntryPasswordArgs,
+}
+
+#[derive(Args, Debug)]
+struct SubCmdEditArgs {
+    /// Entry id or 'about', a number is matched as an entry id first
+    #[arg(value_name = "ABOUT_OR_ID")]
+    query: String,
+    /// New entry 'about'
+    #[arg(long = "about")]
+    about: Option<String>,
+    /// New entry username
+    #[arg(long = "username")]
+    username: Option<String>,
+    /// New entry notes
+    #[arg(long = "notes")]
+    notes: Option<String>,
+    /// New entry tags replacing the current ones, separated by spaces or commas
+    #[arg(long = "tags")]
+    tags: Option<String>,
+    /// New entry group path, '/' moves the entry out of all groups
+    #[arg(long = "group")]
+    group: Option<String>,
+    #[command(flatten)]
+    fields: EntryFieldArgs,
+    /// Remove the custom field, repeat to remove several
+    #[arg(long = "remove-field", value_name = "NAME")]
+    remove_fields: Vec<String>,
+    /// Without a password source, the password is kept
+    #[command(flatten)]
+    password: EntryPasswordArgs,
+}
+
+#[derive(Args, Debug)]
+struct SubCmdRemoveArgs {
+    /// Entry id or 'about', a number is matched as an entry id first
+    #[arg(value_name = "ABOUT_OR_ID")]
+    query: String,
+    /// Remove without confirmation
+    #[arg(short = 'y', long = "yes")]
+    yes: bool,
+    /// Remove the entry permanently instead of moving it to the trash
+    #[arg(long = "purge")]
+    purge: bool,
+}
+
+#[derive(Args, Debug)]
+struct SubCmdMoveArgs {
+    /// Entry id or 'about', a number is matched as an entry id first
+    #[arg(value_name = "ABOUT_OR_ID")]
+    query: String,
+    /// Group path, e.g. 'work/aws', '/' moves the entry out of all groups
+    #[arg(value_name = "GROUP")]
+    group: String,
+}
+
+/// 条目密码来源
+#[derive(Args, Debug)]
+struct EntryPasswordArgs {
+    /// Read the entry password from the first line of stdin
+    #[arg(long = "password-stdin", conflicts_with = "generate")]
+    password_stdin: bool,
+    /// Generate a random entry password, with the password policy named 'default'
+    /// in the data file (or the built-in default), of the given length (--generate=24)
+    /// or by the named policy (--generate=NAME)
+    #[arg(long = "generate", value_name = "LENGTH|POLICY", num_args = 0..=1, require_equals = true)]
+    generate: Option<Option<String>>,
+}
+
+impl EntryPasswordArgs {
+    /// 按参数读取或生成条目密码，未给定来源则 None
+    fn read(&self, mp_input: &Option<ScriptedMainPwd>, context: &PntContext) -> anyhow::Result<Option<SecretString>> {
+        if self.password_stdin && mp_input.as_ref().is_some_and(ScriptedMainPwd::is_stdin) {
+            Err(anyhow!("--password-stdin cannot be used with --main-password-stdin"))
+        } else if self.password_stdin {
+            let mut line = SecretString::default();
+            std::io::stdin().read_line(&mut line)?;
+            let len = line.trim_end_matches(['\r', '\n']).len();
+            line.truncate(len);
+            Ok(Some(line))
+        } else {
+            let policy = match self.generate.as_ref() {
+                None => return Ok(None),
+                Some(None) => context.gen_policy(None)?,
+                Some(Some(v)) => match v.parse::<u16>() {
+                    Ok(len) => context.gen_policy(None)?.with_length(len),
+                    Err(_) => context.gen_policy(Some(v))?,
+                },
+            };
+            Ok(Some(policy.generate()?))
+        }
+    }
+}
+
+/// 条目自定义字段参数
+#[derive(Args, Debug)]
+struct EntryFieldArgs {
+    /// Set a custom field (replacing one with the same name), repeat to set several
+    #[arg(long = "field", value_name = "NAME=VALUE", value_parser = parse_field_assignment)]
+    fields: Vec<(String, String)>,
+    /// Set a secret custom field (always encrypted, masked in the TUI), repeat to set several.
+    /// Without '=VALUE' the value is prompted for, values given on the command line
+    /// may be visible to other local users
+    #[arg(long = "secret-field", value_name = "NAME[=VALUE]", value_parser = parse_secret_field_assignment)]
+    secret_fields: Vec<(String, Option<SecretString>)>,
+}
+
+impl EntryFieldArgs {
+    /// 设定条目的自定义字段，已有的同名字段被替换，未给定值的秘密字段即提示输入
+    fn apply(&self, entry: &mut InputEntry) -> anyhow::Result<()> {
+        for (name, value) in &self.fields {
+            entry.set_field(name, value, false);
+        }
+        for (name, value) in &self.secret_fields {
+            match value {
+                Some(value) => entry.set_field(name, value, true),
+                None => entry.set_field(name, &read_secret_by_prompt(&format!("Secret field '{name}'"))?, true),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args, Debug)]
+struct SubCmdRekeyArgs {
+    #[command(flatten)]
+    kdf: KdfArgs,
+}
+
+/// 主密码 KDF 参数，未给定的沿用基准值（init 时为默认值，rekey 时为当前值）
+#[derive(Args, Debug)]
+struct KdfArgs {
+    /// Argon2 variant used to derive the key from the main password (argon2id, argon2i, argon2d)
+    #[arg(long = "kdf-alg", value_name = "ALGORITHM", value_parser = parse_kdf_algorithm)]
+    algorithm: Option<argon2::Algorithm>,
+    /// Argon2 memory cost in KiB
+    #[arg(long = "kdf-m-cost", value_name = "KiB")]
+    m_cost: Option<u32>,
+    /// Argon2 number of iterations
+    #[arg(long = "kdf-t-cost", value_name = "ITERATIONS")]
+    t_cost: Option<u32>,
+    /// Argon2 degree of parallelism
+    #[arg(long = "kdf-p-cost", value_name = "LANES")]
+    p_cost: Option<u32>,
+}
+
+impl KdfArgs {
+    /// 以给定的参数覆盖基准值，返回校验过的 KDF 参数
+    fn overwrite(&self, base: KdfParams) -> anyhow::Result<KdfParams> {
+        let kdf = KdfParams {
+            algorithm: self.algorithm.unwrap_or(base.algorithm),
+            m_cost: self.m_cost.unwrap_or(base.m_cost),
+            t_cost: self.t_cost.unwrap_or(base.t_cost),
+            p_cost: self.p_cost.unwrap_or(base.p_cost),
+        };
+        kdf.validate()?;
+        Ok(kdf)
+    }
+}
+
+fn parse_char_classes(s: &str) -> Result<CharClasses, String> {
+    s.parse().map_err(|e: AppError| e.to_string())
+}
+
+fn parse_gen_policy_name(s: &str) -> Result<String, String> {
+    if is_valid_policy_name(s) {
+        Ok(s.to_owned())
+    } else {
+        Err("expected 1 to 32 lowercase letters, digits, '-' or '_'".to_owned())
+    }
+}
+
+fn parse_cipher_alg(s: &str) -> Result<CipherAlg, String> {
+    s.parse().map_err(|_| {
+        let names: Vec<_> = CipherAlg::ALL.iter().map(|alg| alg.name()).collect();
+        format!("expected one of: {}", names.join(", "))
+    })
+}
+
+fn parse_field_assignment(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_owned(), value.to_owned())),
+        _ => Err("expected NAME=VALUE".to_owned()),
+    }
+}
+
+/// 解析 `NAME[=VALUE]`，未给定值即 None，值为秘密，Debug 不输出之
+fn parse_secret_field_assignment(s: &str) -> Result<(String, Option<SecretString>), String> {
+    let (name, value) = match s.split_once('=') {
+        Some((name, value)) => (name, Some(SecretString::from(value.to_owned()))),
+        None => (s, None),
+    };
+    if name.trim().is_empty() {
+        return Err("expected NAME or NAME=VALUE".to_owned());
+    }
+    Ok((name.trim().to_owned(), value))
+}
+
+fn parse_kdf_algorithm(s: &str) -> Result<argon2::Algorithm, String> {
+    argon2::Algorithm::new(s).map_err(|_| "expected one of: argon2id, argon2i, argon2d".to_owned())
+}
+
 #[derive(Args, Debug)]
 struct SubCmdCfgArgs {
     /// *configuration option*
@@ -93,6 +543,63 @@ struct SubCmdCfgArgs {
     /// Setting how many seconds of inactivity before the TUI automatically closes (set to 0 to disable)
     #[arg(long = InnerCfg::AUTO_CLOSE_IDLE_SEC, value_name = "SECONDS")]
     modify_auto_close_idle_sec: Option<u32>,
+    /// *configuration option*
+    ///
+    /// Setting how many seconds after copying in the TUI the clipboard is cleared,
+    /// if it still holds the copied content (set to 0 to disable).
+    /// The clipboard is also cleared on relock and on quit
+    #[arg(long = InnerCfg::CLIPBOARD_CLEAR_SEC, value_name = "SECONDS")]
+    modify_clipboard_clear_sec: Option<u32>,
+    /// *configuration option*
+    ///
+    /// Setting whether the details and editing screens show the entry password in clear text when opened,
+    /// otherwise it is masked until toggled with 'r' (details) or 'CTRL+R' (editing)
+    #[arg(long = InnerCfg::REVEAL_PASSWORD, value_name = "BOOLEAN")]
+    modify_reveal_password: Option<bool>,
+    /// *configuration option*
+    ///
+    /// Setting how many seconds a revealed password stays in clear text before it is masked again
+    /// (set to 0 to disable)
+    #[arg(long = InnerCfg::REVEAL_PASSWORD_SEC, value_name = "SECONDS")]
+    modify_reveal_password_sec: Option<u32>,
+    /// *configuration option*
+    ///
+    /// Setting how many failed unlock attempts in total (across runs) lock out unlocking,
+    /// which `pnt recover` lifts (set to 0 to disable)
+    #[arg(long = InnerCfg::MAX_FAILED_UNLOCK, value_name = "COUNT")]
+    modify_max_failed_unlock: Option<u32>,
+    /// *configuration option*
+    ///
+    /// Setting whether to wipe the data file instead of locking out when the failed unlock limit is reached
+    #[arg(long = InnerCfg::WIPE_ON_MAX_FAILED_UNLOCK, value_name = "BOOLEAN")]
+    modify_wipe_on_max_failed_unlock: Option<bool>,
+    /// *configuration option*
+    ///
+    /// Setting how many previous versions of each entry are kept when it is updated
+    /// (set to 0 to keep none). Lowering it removes the older versions immediately
+    #[arg(long = InnerCfg::HISTORY_KEEP, value_name = "COUNT")]
+    modify_history_keep: Option<u32>,
+    /// *configuration option*
+    ///
+    /// Setting after how many days removed entries in the trash are deleted permanently
+    /// (set to 0 to keep them until purged by hand)
+    #[arg(long = InnerCfg::TRASH_PURGE_DAYS, value_name = "DAYS")]
+    modify_trash_purge_days: Option<u32>,
+    /// *configuration option*
+    ///
+    /// Setting whether the 'about' and 'notes' of entries are encrypted too (full encryption mode).
+    /// Changing it re-encrypts all entries
+    #[arg(long = InnerCfg::FULL_ENCRYPT, value_name = "BOOLEAN")]
+    modify_full_encrypt: Option<bool>,
+    /// *configuration option*
+    ///
+    /// Setting the cipher used to encrypt entries (aes-256-gcm, xchacha20-poly1305).
+    /// Changing it re-encrypts all entries
+    #[arg(long = InnerCfg::CIPHER, value_name = "ALGORITHM", value_parser = parse_cipher_alg)]
+    modify_cipher: Option<CipherAlg>,
+    /// Remove the named password policy (saved by `pnt gen --save`)
+    #[arg(long = "remove-gen-policy", value_name = "NAME")]
+    remove_gen_policy: Option<String>,
 }
 
 impl CliArgs {
@@ -106,13 +613,30 @@ impl CliArgs {
             return Ok(None);
         }
 
+        // 非交互的主密码来源，无则交互输入
+        let mut mp_input = ScriptedMainPwd::from_args(self.main_password_stdin, self.main_password_file.as_deref());
+
         // 看看参数要求
-        if let Some(SubCmd::Init) = &self.sub_command {
+        if let Some(SubCmd::Init(args)) = &self.sub_command {
             // 显式要求 init
-            handle_pnt_data_init(self.data.clone())?;
+            handle_pnt_data_init(self.data.clone(), self.keyfile.as_deref(), args, &mut mp_input)?;
             return Ok(None);
         }
 
+        // sub-cmd: gen，未给定 data file 或其不存在时以内置默认策略生成，否则见下方
+        if let Some(SubCmd::Gen(args)) = &self.sub_command
+            && args.policy.is_none()
+            && args.save.is_none()
+        {
+            let cfg = load_cfg()?;
+            let data = self.data.as_ref().unwrap_or(&cfg.load_data);
+            if !matches!(DataFileState::look(data)?, DataFileState::Ready(_)) {
+                let policy = args.policy_args.overwrite(PasswordPolicy::default())?;
+                output_generated_password(&policy, args.copy, cfg.clipboard)?;
+                return Ok(None);
+            }
+        }
+
         // =======================================
         // CONTEXT BUILD =========================
         // =======================================
@@ -122,10 +646,16 @@ impl CliArgs {
         if let Some(data) = &self.data {
             cfg.load_data = data.clone()
         };
+        // 若有 cli 参数 --keyfile 则替换cfg中的
+        if let Some(keyfile) = &self.keyfile {
+            cfg.keyfile = Some(keyfile.clone())
+        };
         // 连接数据文件，因为为非显式init，所以任何失败情况该方法内均Err向上回报
         let conn = assert_data_file_ready(&cfg.load_data)?;
         // 已填充inner配置的cfg
         cfg.inner_cfg.overwrite_default(&conn)?;
+        // 回收站中过期的条目无需解密即可删除，遂每次运行时即清除之
+        conn.purge_expired_trash(cfg.inner_cfg.trash_purge_days)?;
 
         // pnt 上下文
         let mut context = PntContext::new_with_un_verified(cfg, conn);
@@ -141,10 +671,13 @@ impl CliArgs {
                 "Verify the current data file main password to modify main password".yellow()
             );
             // 因为要修改主密码，遂立即要求主密码
-            let context = await_verifier_main_pwd(context)?;
+            let context = await_verifier_main_pwd(context, &mut mp_input)?;
+            // 修改主密码沿用当前 KDF 参数及密钥文件
+            let kdf = context.cfg.inner_cfg.kdf;
+            let keyfile = context.keyfile()?;
 
             // 至此 原主密码已校验
-            let new_mp = setting_main_pwd_by_stdin("New main password")?;
+            let new_mp = setting_main_pwd_by_stdin("New main password", &mut mp_input)?;
 
             // 不可反驳解构 PNT CONTEXT，因为已经校验了主密码，所以 else 一定不会发生
             let PntContext {
@@ -156,17 +689,111 @@ impl CliArgs {
                 unreachable!("因上述await_verifier_main_pwd，不会执行到该分支")
             };
 
-            let new_b64_s_mph = MainPwdEncrypter::new_from_random_salt().encrypt(new_mp.clone())?;
-            println!("\nNew main password hash:\n{new_b64_s_mph}\n");
-            let new_sec_ctx =
-                MainPwdVerifier::from_b64_s_mph(&new_b64_s_mph)?.load_security_context(&new_mp)?;
+            // 条目以数据密钥加密，修改主密码仅需以新主密码重新包裹之
+            let new_record =
+                MainPwdEncrypter::new_from_random_salt(kdf, keyfile).encrypt(&new_mp, old_sec_ctx.data_key())?;
+            println!("\nNew main password hash:\n{}\n", new_record.b64_s_mph);
 
             // 当前线程卡在这，等待数据库文件内容更新返回 =====
             println!("{}", "...modify main password...\n".grey());
-            storage.update_b64_s_mph(new_b64_s_mph, old_sec_ctx, new_sec_ctx)?;
+            storage.update_main_pwd(&new_record, &kdf)?;
             println!("{}", "Successfully modify main password".green());
             // 当前线程卡在这，等待数据库文件内容更新返回 =====
 
+            return Ok(None);
+        } else if let Some(SubCmd::Rekey(args)) = &self.sub_command {
+            // 要求以新的 KDF 参数 re-key...
+            println!("Data file: '{}'", context.storage.path().unwrap());
+            // 先校验参数，以免输入主密码后才发现参数无效
+            let new_kdf = args.kdf.overwrite(context.cfg.inner_cfg.kdf)?;
+            println!(
+                "{}",
+                "Verify the current data file main password to re-key".yellow()
+            );
+            // 新密钥需由主密码明文以新参数派生，密钥文件沿用
+            let (context, mp) = await_verifier_main_pwd_with_plaintext(context, &mut mp_input)?;
+            let keyfile = context.keyfile()?;
+
+            // 不可反驳解构 PNT CONTEXT，因为已经校验了主密码，所以 else 一定不会发生
+            let PntContext {
+                storage,
+                security_context: Some(old_sec_ctx),
+                cfg,
+            } = context
+            else {
+                unreachable!("因上述await_verifier_main_pwd，不会执行到该分支")
+            };
+            println!("\n{} {} -> {}", InnerCfg::KDF, cfg.inner_cfg.kdf, new_kdf);
+
+            // 以新参数派生的主密钥重新包裹数据密钥，条目无需重新加密
+            let new_record =
+                MainPwdEncrypter::new_from_random_salt(new_kdf, keyfile).encrypt(&mp, old_sec_ctx.data_key())?;
+
+            // 当前线程卡在这，等待数据库文件内容更新返回 =====
+            println!("{}", "...re-key...\n".grey());
+            storage.update_main_pwd(&new_record, &new_kdf)?;
+            println!("{}", "Successfully re-key".green());
+            // 当前线程卡在这，等待数据库文件内容更新返回 =====
+
+            return Ok(None);
+        } else if let Some(SubCmd::RecoveryKey) = &self.sub_command {
+            // 要求生成新的恢复密钥...
+            println!("Data file: '{}'", context.storage.path().unwrap());
+            println!(
+                "{}",
+                "Verify the current data file main password to generate a new recovery key".yellow()
+            );
+            let context = await_verifier_main_pwd(context, &mut mp_input)?;
+
+            // 不可反驳解构 PNT CONTEXT，因为已经校验了主密码，所以 else 一定不会发生
+            let PntContext {
+                storage,
+                security_context: Some(sec_ctx),
+                ..
+            } = context
+            else {
+                unreachable!("因上述await_verifier_main_pwd，不会执行到该分支")
+            };
+            let recovery_key = RecoveryKey::generate();
+            let recovery_kek = recovery_key.wrap_subkey()?;
+            let recovery_wrapped_key = sec_ctx.data_key().wrap(&recovery_kek)?;
+            // 替换旧的被包裹数据密钥，旧恢复密钥即失效
+            storage.store_recovery_wrapped_data_key(&recovery_wrapped_key);
+            println!(
+                "{}",
+                "Successfully generated recovery key, the previous one no longer works".green()
+            );
+            print_recovery_key(&recovery_key);
+
+            return Ok(None);
+        } else if let Some(SubCmd::Recover) = &self.sub_command {
+            // 要求以恢复密钥重设主密码...
+            println!("Data file: '{}'", context.storage.path().unwrap());
+            let recovery_wrapped_key = context
+                .storage
+                .query_recovery_wrapped_data_key()
+                .ok_or(AppError::NoRecoveryKey)?;
+            // 新主密码沿用当前 KDF 参数及密钥文件，先读取密钥文件，以免输入恢复密钥后才发现其缺失
+            let kdf = context.cfg.inner_cfg.kdf;
+            let keyfile = context.keyfile()?;
+            println!(
+                "{}",
+                "Enter the recovery key to reset the main password".yellow()
+            );
+            let data_key = await_recovery_key(&recovery_wrapped_key)?;
+
+            // 至此 恢复密钥已校验，恢复仅交互进行
+            let new_mp = setting_main_pwd_by_stdin("New main password", &mut None)?;
+            let new_record = MainPwdEncrypter::new_from_random_salt(kdf, keyfile).encrypt(&new_mp, &data_key)?;
+
+            // 当前线程卡在这，等待数据库文件内容更新返回 =====
+            println!("{}", "...reset main password...\n".grey());
+            context.storage.update_main_pwd(&new_record, &kdf)?;
+            // 恢复密钥等同解锁，清除失败记录（亦解除锁定）
+            context.storage.store_failed_unlock(&FailedUnlock::default());
+            println!("{}", "Successfully reset main password".green());
+            // 当前线程卡在这，等待数据库文件内容更新返回 =====
+
             return Ok(None);
         } else if let Some(SubCmd::Cfg(args)) = &self.sub_command {
             // 要求修改 inner 配置
@@ -181,7 +808,7 @@ impl CliArgs {
                     .yellow()
             );
             // 因为要修改配置，遂立即要求主密码
-            let mut context = await_verifier_main_pwd(context)?;
+            let mut context = await_verifier_main_pwd(context, &mut mp_input)?;
 
             // ===========================================================
             // change inner cfg and store ================================
@@ -226,26 +853,354 @@ impl CliArgs {
                     InnerCfg::AUTO_CLOSE_IDLE_SEC
                 );
             }
+            if let Some(clipboard_clear_sec) = &args.modify_clipboard_clear_sec {
+                no_any_args = false;
+                context.cfg.inner_cfg.clipboard_clear_sec = Some(*clipboard_clear_sec);
+                context.cfg.inner_cfg.save_to_data(&mut context.storage);
+                println!(
+                    "{} '{}'",
+                    "Successfully modified configuration".green(),
+                    InnerCfg::CLIPBOARD_CLEAR_SEC
+                );
+            }
+            if let Some(reveal_password) = &args.modify_reveal_password {
+                no_any_args = false;
+                context.cfg.inner_cfg.reveal_password = *reveal_password;
+                context.cfg.inner_cfg.save_to_data(&mut context.storage);
+                println!(
+                    "{} '{}'",
+                    "Successfully modified configuration".green(),
+                    InnerCfg::REVEAL_PASSWORD
+                );
+            }
+            if let Some(reveal_password_sec) = &args.modify_reveal_password_sec {
+                no_any_args = false;
+                context.cfg.inner_cfg.reveal_password_sec = Some(*reveal_password_sec);
+                context.cfg.inner_cfg.save_to_data(&mut context.storage);
+                println!(
+                    "{} '{}'",
+                    "Successfully modified configuration".green(),
+                    InnerCfg::REVEAL_PASSWORD_SEC
+                );
+            }
+            if let Some(max_failed_unlock) = &args.modify_max_failed_unlock {
+                no_any_args = false;
+                context.cfg.inner_cfg.max_failed_unlock = Some(*max_failed_unlock);
+                context.cfg.inner_cfg.save_to_data(&mut context.storage);
+                println!(
+                    "{} '{}'",
+                    "Successfully modified configuration".green(),
+                    InnerCfg::MAX_FAILED_UNLOCK
+                );
+            }
+            if let Some(wipe_on_max_failed_unlock) = &args.modify_wipe_on_max_failed_unlock {
+                no_any_args = false;
+                context.cfg.inner_cfg.wipe_on_max_failed_unlock = *wipe_on_max_failed_unlock;
+                context.cfg.inner_cfg.save_to_data(&mut context.storage);
+                println!(
+                    "{} '{}'",
+                    "Successfully modified configuration".green(),
+                    InnerCfg::WIPE_ON_MAX_FAILED_UNLOCK
+                );
+            }
+            if let Some(history_keep) = &args.modify_history_keep {
+                no_any_args = false;
+                context.cfg.inner_cfg.history_keep = *history_keep;
+                context.cfg.inner_cfg.save_to_data(&mut context.storage);
+                context.storage.prune_entry_history(*history_keep)?;
+                println!(
+                    "{} '{}'",
+                    "Successfully modified configuration".green(),
+                    InnerCfg::HISTORY_KEEP
+                );
+            }
+            if let Some(trash_purge_days) = &args.modify_trash_purge_days {
+                no_any_args = false;
+                context.cfg.inner_cfg.trash_purge_days = *trash_purge_days;
+                context.cfg.inner_cfg.save_to_data(&mut context.storage);
+                context.storage.purge_expired_trash(*trash_purge_days)?;
+                println!(
+                    "{} '{}'",
+                    "Successfully modified configuration".green(),
+                    InnerCfg::TRASH_PURGE_DAYS
+                );
+            }
+            if args.modify_full_encrypt.is_some() || args.modify_cipher.is_some() {
+                no_any_args = false;
+                let Some(old_sec_ctx) = &context.security_context else {
+                    unreachable!("因上述await_verifier_main_pwd，不会执行到该分支")
+                };
+                // 全加密模式及加密算法均需重新加密所有条目，合并为一次
+                let old_mode = old_sec_ctx.mode();
+                let new_mode = EntryEncryptMode {
+                    full_encrypt: args.modify_full_encrypt.unwrap_or(old_mode.full_encrypt),
+                    cipher: args.modify_cipher.unwrap_or(old_mode.cipher),
+                    ..old_mode
+                };
+                if new_mode != old_mode {
+                    let new_sec_ctx = old_sec_ctx.with_mode(new_mode);
+                    // 当前线程卡在这，等待数据库文件内容更新返回 =====
+                    println!("{}", "...re-encrypt all entries...".grey());
+                    context
+                        .storage
+                        .update_entry_encrypt_mode(old_sec_ctx, &new_sec_ctx)?;
+                    context.security_context = Some(new_sec_ctx);
+                    context.cfg.inner_cfg.full_encrypt = new_mode.full_encrypt;
+                    context.cfg.inner_cfg.cipher = new_mode.cipher;
+                }
+                if args.modify_full_encrypt.is_some() {
+                    println!(
+                        "{} '{}'",
+                        "Successfully modified configuration".green(),
+                        InnerCfg::FULL_ENCRYPT
+                    );
+                }
+                if args.modify_cipher.is_some() {
+                    println!(
+                        "{} '{}'",
+                        "Successfully modified configuration".green(),
+                        InnerCfg::CIPHER
+                    );
+                }
+            }
+            if let Some(name) = &args.remove_gen_policy {
+                no_any_args = false;
+                if !context.storage.delete_gen_policy(name) {
+                    Err(AppError::GenPolicyNotFound(name.clone()))?
+                }
+                println!("{} '{}'", "Successfully removed password policy".green(), name);
+            }
             // ===========================================================
             // change inner cfg and store ================================
             // ===========================================================
 
             // 修改 cfg时务必修改 该值为 false，当该值为true，打印配置
             if no_any_args {
-                println!("cfg:\n{}", context.cfg.inner_cfg)
+                println!("cfg:\n{}", context.cfg.inner_cfg);
+                for (name, policy) in context.storage.query_gen_policies()? {
+                    println!("gen-policy.{name} = {policy}");
+                }
             }
             // 使用 OK（NONE）打断不使TUI运行
             return Ok(None);
         }
 
+        // sub-cmd: get
+        if let Some(SubCmd::Get(args)) = &self.sub_command {
+            // 条目字段输出至 stdout，遂提示输出至 stderr
+            eprintln!("Data file: '{}'", context.storage.path().unwrap());
+            let context = await_verifier_main_pwd(context, &mut mp_input)?;
+            let entry = context.find_single_entry(&args.query)?;
+            let input_entry = entry.decrypt(context.try_encrypter()?)?;
+            // 内置字段优先于同名的自定义字段
+            let value = match args.field.to_lowercase().as_str() {
+                "username" => &input_entry.username,
+                "password" => &input_entry.password,
+                "notes" => &input_entry.notes,
+                _ => match input_entry.field(&args.field) {
+                    Some(field) => &field.value,
+                    None => Err(AppError::FieldNotFound {
+                        about: input_entry.about.clone(),
+                        field: args.field.clone(),
+                    })?,
+                },
+            };
+            if args.copy {
+                let what = format!("the {} of '{}'", args.field, input_entry.about);
+                copy_to_clipboard(context.cfg.clipboard, value, &what)?;
+            } else {
+                println!("{value}");
+            }
+            return Ok(None);
+        }
+
+        // sub-cmd: gen
+        if let Some(SubCmd::Gen(args)) = &self.sub_command {
+            let policy = args
+                .policy_args
+                .overwrite(context.gen_policy(args.policy.as_deref())?)?;
+            if let Some(name) = &args.save {
+                // 修改 cfg 均要求主密码
+                eprintln!("Data file: '{}'", context.storage.path().unwrap());
+                eprintln!("{}", "Verify the current data file main password to save the password policy".yellow());
+                context = await_verifier_main_pwd(context, &mut mp_input)?;
+                context.storage.store_gen_policy(name, &policy);
+                let msg = format!("Saved password policy '{name}': {policy}");
+                eprintln!("{}", msg.green());
+            }
+            output_generated_password(&policy, args.copy, context.cfg.clipboard)?;
+            return Ok(None);
+        }
+
+        // sub-cmd: list
+        if let Some(SubCmd::List(args)) = &self.sub_command {
+            // 条目输出至 stdout，遂提示输出至 stderr
+            eprintln!("Data file: '{}'", context.storage.path().unwrap());
+            // 全加密模式下 about 为密文，列出前必须校验主密码
+            if args.secrets || context.is_need_mp_on_run() || context.is_full_encrypt() {
+                context = await_verifier_main_pwd(context, &mut mp_input)?;
+            }
+            let mut entries = match &args.filter {
+                Some(filter) => context.find_entry_by_about_like(filter)?,
+                None => context.select_all_listing_entry()?,
+            };
+            entries.retain(|e| args.tags.iter().all(|tag| e.has_tag(tag)));
+            if let Some(group) = &args.group {
+                let group = normalize_group_path(group);
+                entries.retain(|e| if group.is_empty() { e.group.is_none() } else { e.in_group(&group) });
+            }
+            args.sort.sort(&mut entries);
+            let listed = if args.secrets {
+                let encrypter = context.try_encrypter()?;
+                entries
+                    .iter()
+                    .map(|e| {
+                        // 全加密模式下 entries 中的 about 及 notes 已解密，解密须用原样的条目
+                        let raw = context.storage.select_entry_by_id(e.id).ok_or(AppError::DataCorrupted)?;
+                        Ok(ListedEntry::with_secrets(e, &raw.decrypt(encrypter)?))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?
+            } else {
+                entries.iter().map(ListedEntry::without_secrets).collect()
+            };
+            println!("{}", listing::render(&listed, args.format, args.secrets)?);
+            return Ok(None);
+        }
+
+        // sub-cmd: add
+        if let Some(SubCmd::Add(args)) = &self.sub_command {
+            // 先读取 stdin 中的条目密码，以免与主密码的输入交错
+            let password = args.password.read(&mp_input, &context)?;
+            eprintln!("Data file: '{}'", context.storage.path().unwrap());
+            let context = await_verifier_main_pwd(context, &mut mp_input)?;
+            let password = match password {
+                Some(password) => password,
+                None => read_secret_by_prompt("Entry password")?,
+            };
+            let mut input_entry = InputEntry {
+                about: args.about.clone(),
+                notes: args.notes.clone(),
+                username: args.username.clone(),
+                password: password.to_string(),
+                tags: parse_tags(&args.tags),
+                group: normalize_group_path(&args.group),
+                fields: Vec::new(),
+            };
+            args.fields.apply(&mut input_entry)?;
+            if !input_entry.validate() {
+                Err(AppError::InvalidEntry)?
+            }
+            // 密文与条目id绑定，新建条目须先分配id
+            let e_id = context.storage.next_entry_id();
+            let valid = context.try_encrypter()?.encrypt((e_id, &input_entry))?;
+            context.insert_entry(&valid, &input_entry.tags, &input_entry.group)?;
+            let msg = format!("Added entry #{} '{}'", e_id, input_entry.about);
+            println!("{}", msg.green());
+            return Ok(None);
+        }
+
+        // sub-cmd: edit
+        if let Some(SubCmd::Edit(args)) = &self.sub_command {
+            let password = args.password.read(&mp_input, &context)?;
+            eprintln!("Data file: '{}'", context.storage.path().unwrap());
+            let context = await_verifier_main_pwd(context, &mut mp_input)?;
+            let entry = context.find_single_entry(&args.query)?;
+            let mut input_entry = entry.decrypt(context.try_encrypter()?)?;
+            if let Some(about) = &args.about {
+                input_entry.about = about.clone();
+            }
+            if let Some(username) = &args.username {
+                input_entry.username = username.clone();
+            }
+            if let Some(notes) = &args.notes {
+                input_entry.notes = notes.clone();
+            }
+            if let Some(tags) = &args.tags {
+                input_entry.tags = parse_tags(tags);
+            }
+            if let Some(group) = &args.group {
+                input_entry.group = normalize_group_path(group);
+            }
+            if let Some(password) = password {
+                input_entry.password = password.to_string();
+            }
+            for name in &args.remove_fields {
+                if !input_entry.remove_field(name) {
+                    Err(AppError::FieldNotFound {
+                        about: input_entry.about.clone(),
+                        field: name.clone(),
+                    })?
+                }
+            }
+            args.fields.apply(&mut input_entry)?;
+            if !input_entry.validate() {
+                Err(AppError::InvalidEntry)?
+            }
+            let valid = context.try_encrypter()?.encrypt((entry.id, &input_entry))?;
+            context.update_entry(&valid, &input_entry.tags, &input_entry.group)?;
+            let msg = format!("Updated entry #{} '{}'", entry.id, input_entry.about);
+            println!("{}", msg.green());
+            return Ok(None);
+        }
+
+        // sub-cmd: rm
+        if let Some(SubCmd::Remove(args)) = &self.sub_command {
+            eprintln!("Data file: '{}'", context.storage.path().unwrap());
+            // 非交互给出主密码时不等待确认，stdin 可能即为主密码来源
+            if mp_input.is_some() && !args.yes {
+                Err(anyhow!("use --yes when the main password is not interactive"))?
+            }
+            // 同 add、edit，修改条目均要求主密码，全加密模式下 about 亦为密文
+            let context = await_verifier_main_pwd(context, &mut mp_input)?;
+            let entry = context.find_single_entry(&args.query)?;
+            let about = entry.decrypt_about_notes(context.try_encrypter()?)?.about;
+            if !args.yes {
+                let how = if args.purge { "permanently remove" } else { "remove" };
+                println!("{}", format!("{how} entry #{} '{}'?", entry.id, about).red());
+                println!("\nenter 'yes' to remove or press Ctrl-C to exit");
+                let mut buf = String::new();
+                std::io::stdin().read_line(&mut buf)?;
+                if buf.to_lowercase().trim() != "yes" {
+                    return Err(anyhow!("entry #{} is not removed", entry.id));
+                }
+            }
+            let msg = if args.purge {
+                context.storage.delete_entry(entry.id);
+                format!("Removed entry #{} '{}' permanently", entry.id, about)
+            } else {
+                context.storage.trash_entry(entry.id);
+                format!("Moved entry #{} '{}' to the trash", entry.id, about)
+            };
+            println!("{}", msg.green());
+            return Ok(None);
+        }
+
+        // sub-cmd: mv
+        if let Some(SubCmd::Move(args)) = &self.sub_command {
+            eprintln!("Data file: '{}'", context.storage.path().unwrap());
+            // 分组名在全加密模式下为密文，且修改条目均要求主密码
+            let context = await_verifier_main_pwd(context, &mut mp_input)?;
+            let entry = context.find_single_entry(&args.query)?;
+            let about = entry.decrypt_about_notes(context.try_encrypter()?)?.about;
+            let group = normalize_group_path(&args.group);
+            context.store_entry_group(entry.id, &group)?;
+            let msg = if group.is_empty() {
+                format!("Moved entry #{} '{}' out of all groups", entry.id, about)
+            } else {
+                format!("Moved entry #{} '{}' to '{}'", entry.id, about, group)
+            };
+            println!("{}", msg.green());
+            return Ok(None);
+        }
+
         // cli 要求 find
         if let Some(find) = &self.find {
-            if context.is_need_mp_on_run() {
-                context = await_verifier_main_pwd(context)?;
+            // 全加密模式下 about 为密文，查找前必须校验主密码
+            if context.is_need_mp_on_run() || context.is_full_encrypt() {
+                context = await_verifier_main_pwd(context, &mut mp_input)?;
             }
             context
-                .storage
-                .select_entry_by_about_like(find)
+                .find_entry_by_about_like(find)?
                 .into_iter()
                 .enumerate()
                 .for_each(|(i, entry)| println!("{:>4}: {}", i + 1, entry.about));
@@ -253,6 +1208,8 @@ impl CliArgs {
             return Ok(None);
         }
 
+        // 提前读取 data file 要求的密钥文件，以免进入 tui 后才发现缺少之
+        context.keyfile()?;
         // 返回 Context 要求 tui 运行
         Ok(Some(context))
     }
@@ -265,8 +1222,16 @@ impl CliArgs {
 /// ### 优先级
 ///
 /// 明确Cli --data 参数 or -> conf.default_data or -> env -> default
-fn handle_pnt_data_init(init_arg_target: Option<PathBuf>) -> anyhow::Result<()> {
+///
+/// 仅明确给定 cli --keyfile 参数时，新 data file 要求该密钥文件
+fn handle_pnt_data_init(
+    init_arg_target: Option<PathBuf>, keyfile_path: Option<&Path>, args: &SubCmdInitArgs,
+    mp_input: &mut Option<ScriptedMainPwd>,
+) -> anyhow::Result<()> {
     println!("{}", "Data file initialized\n".bold().dark_cyan());
+    // 先校验 KDF 参数及读取密钥文件，以免输入主密码后才发现参数无效
+    let kdf = args.kdf.overwrite(KdfParams::default())?;
+    let keyfile = keyfile_path.map(Keyfile::read).transpose()?;
     /*
     // 先从参数 --data 找需要，
     // 若无，则从可能存在的配置文件中找
@@ -288,18 +1253,31 @@ fn handle_pnt_data_init(init_arg_target: Option<PathBuf>) -> anyhow::Result<()>
         data_target_path.display()
     );
     println!("{}", msg.bold().cyan());
-    println!(
-        "\npress Enter to init main password with interactive context or press Ctrl-C to exit"
-    );
     let mut buf = String::new();
-    std::io::stdin().read_line(&mut buf)?;
-    // 初始化主密码
-    let mph = MainPwdEncrypter::new_from_random_salt()
-        .encrypt(setting_main_pwd_by_stdin("Init main password")?)?;
+    // 非交互给出主密码时不等待确认，stdin 可能即为主密码来源
+    if mp_input.is_none() {
+        println!(
+            "\npress Enter to init main password with interactive context or press Ctrl-C to exit"
+        );
+        std::io::stdin().read_line(&mut buf)?;
+    }
+    // 初始化主密码，随机生成数据密钥并以主密码及恢复密钥分别包裹之
+    let data_key = DataKey::generate();
+    let record = MainPwdEncrypter::new_from_random_salt(kdf, keyfile)
+        .encrypt(&setting_main_pwd_by_stdin("Init main password", mp_input)?, &data_key)?;
+    let recovery_key = RecoveryKey::generate();
+    let recovery_kek = recovery_key.wrap_subkey()?;
+    let recovery_wrapped_key = data_key.wrap(&recovery_kek)?;
     println!("{}", "successfully init main password".green());
 
     // 检查 data local path 位置是否存在文件，若存在，则提示其是否覆盖
-    if data_target_path.exists() {
+    if data_target_path.exists() && mp_input.is_some() {
+        // 非交互时不覆盖已有文件
+        return Err(anyhow!(
+            "file '{}' already exists,\ncannot create data file",
+            data_target_path.display().to_string()
+        ));
+    } else if data_target_path.exists() {
         println!("\nfile '{}'already exists", data_target_path.display());
         println!("{}", "overwrite this file?".red());
         println!("\nenter 'yes' to overwrite existing file or press Ctrl-C to exit");
@@ -315,9 +1293,28 @@ fn handle_pnt_data_init(init_arg_target: Option<PathBuf>) -> anyhow::Result<()>
         }
     }
 
-    println!("\nmain password hash:\n{mph}\n");
+    println!("\nmain password hash:\n{}\n", record.b64_s_mph);
     let conn = Storage::open_in_memory()?;
-    conn.store_b64_s_mph(&mph);
+    conn.store_main_pwd_record(&record);
+    conn.store_recovery_wrapped_data_key(&recovery_wrapped_key);
+    conn.store_kdf_params(&kdf);
+    println!("{} = {}", InnerCfg::KDF, kdf);
+    conn.store_keyfile_required(keyfile_path.is_some());
+    if let Some(keyfile_path) = keyfile_path {
+        println!(
+            "keyfile required: '{}' (keep it safe, the data file cannot be unlocked without it)",
+            keyfile_path.display()
+        );
+    }
+    conn.store_entry_encrypt_mode(&EntryEncryptMode {
+        full_encrypt: args.full_encrypt,
+        entry_bound: true,
+        cipher: args.cipher,
+    });
+    println!("{} = {}", InnerCfg::CIPHER, args.cipher);
+    if args.full_encrypt {
+        println!("full encryption mode: 'about' and 'notes' of entries will be encrypted");
+    }
     // 存储数据文件至指定位置, 该方法不会覆盖文件，位置已有会Err
     conn.db_mem_to_disk(&data_target_path)?;
     let msg = format!("data file created: {}", data_target_path.display());
@@ -334,23 +1331,35 @@ fn handle_pnt_data_init(init_arg_target: Option<PathBuf>) -> anyhow::Result<()>
     }
 
     println!("{}", "\nsuccessfully created data file".green());
+    print_recovery_key(&recovery_key);
     Ok(())
 }
 
+/// 显示恢复密钥，其仅显示这一次
+fn print_recovery_key(recovery_key: &RecoveryKey) {
+    println!("\n{}", "Recovery key (shown only once):".bold().yellow());
+    println!("\n    {}\n", recovery_key.to_string().bold());
+    println!(
+        "{}",
+        "Write it down and keep it safe, it can reset a forgotten main password with `pnt recover`".yellow()
+    );
+}
+
 /// 向stdin索要输入的密码，若有utf8字符则提示无效字符
 ///
 /// 若给定check_too_short参数则该方法内校验输入密码字符长度至少大于等于给定参数
 ///
 /// 该方法内会 loop 阻塞当前线程直到输入有效字符返回或收到 Ctrl + C 终止信号停止进程
-fn loop_read_stdin_ascii_passwd(check_too_short: Option<u8>) -> anyhow::Result<String> {
+fn loop_read_stdin_ascii_passwd(check_too_short: Option<u8>) -> anyhow::Result<SecretString> {
     loop {
         match rpassword::prompt_password("Main password: ".yellow()) {
             Ok(p) => {
-                if let Some(min) = check_too_short {
-                    if p.chars().filter(|c| !c.is_ascii_control()).count() < min as usize {
-                        println!("{}", "> Password too short".red());
-                        continue;
-                    }
+                let p = SecretString::from(p);
+                if let Some(min) = check_too_short
+                    && p.chars().filter(|c| !c.is_ascii_control()).count() < min as usize
+                {
+                    println!("{}", "> Password too short".red());
+                    continue;
                 }
                 return Ok(p);
             }
@@ -369,11 +1378,104 @@ fn loop_read_stdin_ascii_passwd(check_too_short: Option<u8>) -> anyhow::Result<S
     }
 }
 
+/// 向stdin索要输入的恢复密钥，以之解包给定的被包裹数据密钥，
+/// 至多重试 [`ALLOC_INVALID_MAIN_PASS_MAX`] 次，仍无效则Err
+fn await_recovery_key(recovery_wrapped_key: &str) -> anyhow::Result<DataKey> {
+    for n in 0..ALLOC_INVALID_MAIN_PASS_MAX {
+        let input = SecretString::from(rpassword::prompt_password("Recovery key: ".yellow())?);
+        // 格式无效或不可解包均视为无效的恢复密钥
+        if let Ok(recovery_key) = input.parse::<RecoveryKey>() {
+            let recovery_kek = recovery_key.wrap_subkey()?;
+            if let Ok(data_key) = DataKey::unwrap(&recovery_kek, recovery_wrapped_key) {
+                return Ok(data_key);
+            }
+        }
+        let tip = format!(
+            "{} ({}/{})",
+            "Invalid Recovery Key",
+            n + 1,
+            ALLOC_INVALID_MAIN_PASS_MAX
+        );
+        println!("{}", tip.on_dark_red().white())
+    }
+    Err(AppError::InvalidRecoveryKey)?
+}
+
+/// 阻塞当前线程直到允许下次尝试校验主密码
+fn wait_until_retry(retry_after: DateTime<Local>) {
+    if let Some(secs) = secs_until(retry_after) {
+        let tip = format!("Too many failed unlock attempts, wait {secs}s before retrying");
+        println!("{}", tip.yellow());
+        std::thread::sleep(Duration::from_secs(secs));
+    }
+}
+
+/// 按策略生成密码，输出至 stdout 或复制到剪贴板
+fn output_generated_password(
+    policy: &PasswordPolicy, copy: bool, clipboard: ClipboardBackend,
+) -> anyhow::Result<()> {
+    let passwd = policy.generate()?;
+    if copy {
+        copy_to_clipboard(clipboard, &passwd, "the generated password")?;
+    } else {
+        println!("{}", passwd.as_str());
+    }
+    Ok(())
+}
+
+/// 复制到剪贴板并提示，`what` 为所复制内容的描述，
+/// 剪贴板内容由本进程持有时（见 [`clipboard::copy_and_hold`]）阻塞至多 [`CLI_CLIPBOARD_HOLD_SEC`] 秒
+fn copy_to_clipboard(clipboard: ClipboardBackend, info: &str, what: &str) -> anyhow::Result<()> {
+    clipboard::copy_and_hold(
+        clipboard,
+        info,
+        Duration::from_secs(CLI_CLIPBOARD_HOLD_SEC),
+        |backend, hold| {
+            let tip = match hold {
+                Some(hold) => format!("Copied {what} to the clipboard (kept for up to {}s)", hold.as_secs()),
+                None => format!("Copied {what} to the clipboard ({backend})"),
+            };
+            eprintln!("{}", tip.green());
+        },
+    )
+}
+
+/// 阻塞读取stdin，要求两次输入相同的秘密（条目密码、秘密字段值），不可为空，
+/// `label` 为提示中秘密的名称
+fn read_secret_by_prompt(label: &str) -> anyhow::Result<SecretString> {
+    loop {
+        let p = SecretString::from(rpassword::prompt_password(format!("{label}: ").yellow())?);
+        if p.is_empty() {
+            println!("{}", format!("> {label} is required").red());
+            continue;
+        }
+        let again = SecretString::from(rpassword::prompt_password(format!("{label} again: ").yellow())?);
+        if p == again {
+            return Ok(p);
+        }
+        println!(
+            "{}",
+            "> Values entered twice do not match, please re-enter".red()
+        );
+    }
+}
+
 /// 阻塞读取stdin，
 /// 要求至少两次主密码,
 /// 至少要求密码字符大于等于6个
-/// 返回的字符串为明文
-fn setting_main_pwd_by_stdin(prefix: &str) -> anyhow::Result<String> {
+/// 返回的字符串为明文，drop 时清零
+///
+/// 给定非交互的主密码来源时仅读取一次，过短则立即 Err
+fn setting_main_pwd_by_stdin(
+    prefix: &str, mp_input: &mut Option<ScriptedMainPwd>,
+) -> anyhow::Result<SecretString> {
+    if let Some(mp_input) = mp_input {
+        let p = mp_input.next()?;
+        if p.chars().filter(|c| !c.is_ascii_control()).count() < MAIN_PWD_MIN_LEN as usize {
+            Err(AppError::MainPwdTooShort(MAIN_PWD_MIN_LEN))?
+        }
+        return Ok(p);
+    }
     let mut vec = Vec::with_capacity(2);
     let p = loop {
         if vec.is_empty() {
@@ -384,7 +1486,7 @@ fn setting_main_pwd_by_stdin(prefix: &str) -> anyhow::Result<String> {
             println!("{}", prefix_msg.yellow());
         }
         // 该并不支持中文，密码字符有所限制，应显式提示
-        let rl = loop_read_stdin_ascii_passwd(Some(6))?;
+        let rl = loop_read_stdin_ascii_passwd(Some(MAIN_PWD_MIN_LEN))?;
         vec.push(rl);
         // 判定是否两个且相等
         if vec.len() >= 2 {
@@ -413,7 +1515,11 @@ fn assert_data_file_ready(data_file_path: &Path) -> anyhow::Result<Storage> {
             "Unable to find the data file with: {}\nYou might want to use 'pnt init' to create a data file",
             data_file_path.display().to_string()
         )),
-        DataFileState::NoMainPwd => Err(AppError::DataCorrupted)?,
+        // 仅主密码校验失败次数达到上限而擦除时，data file 存在而无主密码
+        DataFileState::NoMainPwd => Err(anyhow!(
+            "The data file was wiped after too many failed unlock attempts: {}\nYou might want to use 'pnt init' to create a new data file",
+            data_file_path.display().to_string()
+        )),
         DataFileState::Ready(conn) => Ok(conn),
     }
 }
@@ -421,16 +1527,49 @@ fn assert_data_file_ready(data_file_path: &Path) -> anyhow::Result<Storage> {
 /// 等待 stdin输入并校验主密码，该方法要求所有权，因为内部可能执行 drop(conn关闭文件占用），
 /// 当失败到一定次数时 释放 storage 对文件的连接资源并退出进程，
 /// 该方法要么返回，要么因stdin错误返回Err
-fn await_verifier_main_pwd(mut context: PntContext) -> anyhow::Result<PntContext> {
+fn await_verifier_main_pwd(
+    context: PntContext, mp_input: &mut Option<ScriptedMainPwd>,
+) -> anyhow::Result<PntContext> {
+    await_verifier_main_pwd_with_plaintext(context, mp_input).map(|(context, _)| context)
+}
+
+/// 同 [`await_verifier_main_pwd`]，另返回校验通过的主密码明文，
+/// 仅用于需以主密码重新派生密钥的情况（如 re-key）
+///
+/// 给定非交互的主密码来源时仅尝试一次，不等待亦不重试
+fn await_verifier_main_pwd_with_plaintext(
+    mut context: PntContext, mp_input: &mut Option<ScriptedMainPwd>,
+) -> anyhow::Result<(PntContext, SecretString)> {
     let verifier = context.mpv()?;
-    // 后续可设定该值为inner配置项，且重试大于一定次数可选操作... 比如删除库文件？
+    if let Some(mp_input) = mp_input {
+        if let Some(secs) = context.unlock_retry_after()?.and_then(secs_until) {
+            Err(AppError::UnlockRetryLater { secs })?
+        }
+        let mp = mp_input.next()?;
+        if let Some(security_context) = verifier.try_load_security_context(&mp)? {
+            if let Some(failed) = context.hold_security_context(security_context)? {
+                eprintln!("{}", failed.summary().yellow());
+            }
+            return Ok((context, mp));
+        }
+        context.record_failed_unlock()?;
+        context.storage.close();
+        return Err(AppError::InvalidPassword.into());
+    }
+    // 单次运行至多重试 ALLOC_INVALID_MAIN_PASS_MAX 次，失败次数另跨进程累计（见 FailedUnlock）
     for n in 0..ALLOC_INVALID_MAIN_PASS_MAX {
+        if let Some(retry_after) = context.unlock_retry_after()? {
+            wait_until_retry(retry_after);
+        }
         let mp = loop_read_stdin_ascii_passwd(None)?;
-        if verifier.verify(&mp)? {
-            // 验证通过，返回SecurityContext
-            context.security_context = Some(verifier.load_security_context(&mp)?);
-            return Ok(context);
+        if let Some(security_context) = verifier.try_load_security_context(&mp)? {
+            // 验证通过，持有SecurityContext
+            if let Some(failed) = context.hold_security_context(security_context)? {
+                println!("{}", failed.summary().yellow());
+            }
+            return Ok((context, mp));
         } else {
+            context.record_failed_unlock()?;
             // 校验失败，提示
             let tip = format!(
                 "{} ({}/{})",
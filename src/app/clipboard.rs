@@ -0,0 +1,316 @@
+//! 系统剪贴板
+//!
+//! 后端有 [arboard]、OSC 52 终端转义序列（经 SSH 及 tmux 亦可用）及外部命令，
+//! 由配置文件 `pnt.toml` 中的 `clipboard` 选择，默认按环境自动探测
+//!
+//! https://crates.io/crates/arboard
+
+use anyhow::{Context, anyhow};
+use arboard::Clipboard;
+use base64ct::{Base64, Encoding};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+/// 剪贴板后端，即配置文件中的 `clipboard`
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardBackend {
+    /// 按环境依次尝试可用的后端，见 [`ClipboardBackend::candidates`]
+    #[default]
+    Auto,
+    /// 系统剪贴板，Linux 下需 X11 或 Wayland (XWayland) 显示
+    Arboard,
+    /// 由终端写入其所在机器的剪贴板，不可读取，遂超时清除时不判断剪贴板是否仍为复制的内容
+    Osc52,
+    WlCopy,
+    Xclip,
+    Pbcopy,
+    /// tmux 缓冲区，`load-buffer -w` 同时经 tmux 的 set-clipboard 写入终端剪贴板
+    Tmux,
+}
+
+impl Display for ClipboardBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ClipboardBackend::Auto => "auto",
+            ClipboardBackend::Arboard => "arboard",
+            ClipboardBackend::Osc52 => "osc52",
+            ClipboardBackend::WlCopy => "wl-copy",
+            ClipboardBackend::Xclip => "xclip",
+            ClipboardBackend::Pbcopy => "pbcopy",
+            ClipboardBackend::Tmux => "tmux",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl ClipboardBackend {
+    /// 要依次尝试的后端，非 Auto 时仅其自身，
+    /// Auto 时按环境变量探测：有显示时优先系统剪贴板，其次外部命令，最后 OSC 52
+    fn candidates(self) -> Vec<ClipboardBackend> {
+        use ClipboardBackend::*;
+        if self != Auto {
+            return vec![self];
+        }
+        let has_env = |key: &str| std::env::var_os(key).is_some_and(|v| !v.is_empty());
+        let mut candidates = Vec::new();
+        if cfg!(not(target_os = "linux")) || has_env("DISPLAY") || has_env("WAYLAND_DISPLAY") {
+            candidates.push(Arboard);
+        }
+        if has_env("WAYLAND_DISPLAY") {
+            candidates.push(WlCopy);
+        }
+        if has_env("DISPLAY") {
+            candidates.push(Xclip);
+        }
+        if cfg!(target_os = "macos") {
+            candidates.push(Pbcopy);
+        }
+        if has_env("TMUX") {
+            candidates.push(Tmux);
+        }
+        if std::io::stdout().is_terminal() {
+            candidates.push(Osc52);
+        }
+        candidates
+    }
+
+    fn set_text(self, info: &str) -> anyhow::Result<()> {
+        match self {
+            ClipboardBackend::Auto => unreachable!("auto is resolved by candidates"),
+            ClipboardBackend::Arboard => Clipboard::new()?
+                .set_text(info)
+                .context("Failed to set clipboard contents"),
+            ClipboardBackend::Osc52 => {
+                let encoded = Zeroizing::new(Base64::encode_string(info.as_bytes()));
+                write_osc52(&encoded)
+            }
+            ClipboardBackend::WlCopy => run_with_input("wl-copy", &[], info),
+            ClipboardBackend::Xclip => run_with_input("xclip", &["-selection", "clipboard"], info),
+            ClipboardBackend::Pbcopy => run_with_input("pbcopy", &[], info),
+            ClipboardBackend::Tmux => run_with_input("tmux", &["load-buffer", "-w", "-"], info),
+        }
+    }
+
+    /// 读取剪贴板文本，不可读取的后端（OSC 52）为 None
+    fn get_text(self) -> Option<anyhow::Result<Zeroizing<String>>> {
+        let text = match self {
+            ClipboardBackend::Auto | ClipboardBackend::Osc52 => return None,
+            ClipboardBackend::Arboard => Clipboard::new()
+                .and_then(|mut c| c.get_text())
+                .map(Zeroizing::new)
+                .map_err(Into::into),
+            ClipboardBackend::WlCopy => run_for_output("wl-paste", &["-n"]),
+            ClipboardBackend::Xclip => run_for_output("xclip", &["-selection", "clipboard", "-o"]),
+            ClipboardBackend::Pbcopy => run_for_output("pbpaste", &[]),
+            ClipboardBackend::Tmux => run_for_output("tmux", &["save-buffer", "-"]),
+        };
+        Some(text)
+    }
+
+    fn clear(self) -> anyhow::Result<()> {
+        match self {
+            ClipboardBackend::Auto => unreachable!("auto is resolved by candidates"),
+            ClipboardBackend::Arboard => Clipboard::new()?
+                .clear()
+                .context("Failed to clear clipboard contents"),
+            // 非 base64 的内容即要求终端清除剪贴板
+            ClipboardBackend::Osc52 => write_osc52("!"),
+            ClipboardBackend::WlCopy => run_with_input("wl-copy", &["--clear"], ""),
+            ClipboardBackend::Xclip => run_with_input("xclip", &["-selection", "clipboard"], ""),
+            ClipboardBackend::Pbcopy => run_with_input("pbcopy", &[], ""),
+            // load-buffer -w 亦经 OSC 52 设定外层终端的剪贴板，遂先以占位内容覆盖之，再删除该缓冲区
+            ClipboardBackend::Tmux => {
+                run_with_input("tmux", &["load-buffer", "-w", "-"], " ")?;
+                run_with_input("tmux", &["delete-buffer"], "")
+            }
+        }
+    }
+}
+
+/// 复制到剪贴板的内容的摘要及所用后端，用于判断剪贴板是否仍为该内容，而无需保留其明文
+#[derive(Clone, Eq, PartialEq)]
+pub struct Copied {
+    backend: ClipboardBackend,
+    digest: [u8; 32],
+}
+
+impl Copied {
+    pub fn new(backend: ClipboardBackend, info: &str) -> Self {
+        Self {
+            backend,
+            digest: digest(info),
+        }
+    }
+
+    /// 实际使用的后端，不为 Auto
+    pub fn backend(&self) -> ClipboardBackend {
+        self.backend
+    }
+}
+
+fn digest(info: &str) -> [u8; 32] {
+    Sha256::digest(info.as_bytes()).into()
+}
+
+/// 将给定内容复制到剪贴板，依次尝试各后端，返回实际所用后端及内容摘要，
+/// 用于 TUI：Linux 下 arboard 的剪贴板内容由进程持有，TUI 运行期间均可粘贴
+///
+/// 无可用后端时 Err，其中包含各后端失败的原因
+pub fn copy(backend: ClipboardBackend, info: &str) -> anyhow::Result<Copied> {
+    let mut failures = Vec::new();
+    for candidate in backend.candidates() {
+        match candidate.set_text(info) {
+            Ok(()) => return Ok(Copied::new(candidate, info)),
+            Err(e) => failures.push(format!("{candidate}: {e:#}")),
+        }
+    }
+    Err(no_backend_works(failures))
+}
+
+fn no_backend_works(failures: Vec<String>) -> anyhow::Error {
+    if failures.is_empty() {
+        anyhow!("no clipboard backend available (set `clipboard` in pnt.toml)")
+    } else {
+        anyhow!("no clipboard backend works ({})", failures.join("; "))
+    }
+}
+
+/// 若剪贴板仍为给定摘要对应的内容则清除之，返回是否清除，
+/// 剪贴板已被替换（或非文本）时不做任何事，不可读取的后端总是清除
+pub fn clear_if_unchanged(copied: &Copied) -> anyhow::Result<bool> {
+    if let Some(current) = copied.backend.get_text() {
+        let Ok(current) = current else {
+            return Ok(false);
+        };
+        if digest(&current) != copied.digest {
+            return Ok(false);
+        }
+    }
+    copied.backend.clear()?;
+    Ok(true)
+}
+
+/// 将给定内容复制到剪贴板，用于复制后即退出的 CLI，
+/// 复制后（arboard 为开始持有前）以所用后端及本进程持有的时长调用 `on_copied`
+///
+/// Linux 下 arboard 的剪贴板内容由进程持有，进程退出即丢失，
+/// 遂阻塞当前线程持有之，直到其被其他内容替换或超过给定时长，
+/// 其他后端的内容不由本进程持有，不阻塞
+pub fn copy_and_hold(
+    backend: ClipboardBackend, info: &str, hold: Duration,
+    on_copied: impl FnOnce(ClipboardBackend, Option<Duration>),
+) -> anyhow::Result<()> {
+    #[cfg(not(target_os = "linux"))]
+    let _ = hold;
+    let mut failures = Vec::new();
+    for candidate in backend.candidates() {
+        #[cfg(target_os = "linux")]
+        if candidate == ClipboardBackend::Arboard {
+            use arboard::SetExtLinux;
+            match Clipboard::new() {
+                Ok(mut clipboard) => {
+                    on_copied(candidate, Some(hold));
+                    return clipboard
+                        .set()
+                        .wait_until(std::time::Instant::now() + hold)
+                        .text(info)
+                        .context("Failed to set clipboard contents");
+                }
+                Err(e) => {
+                    failures.push(format!("{candidate}: {e}"));
+                    continue;
+                }
+            }
+        }
+        match candidate.set_text(info) {
+            Ok(()) => {
+                on_copied(candidate, None);
+                return Ok(());
+            }
+            Err(e) => failures.push(format!("{candidate}: {e:#}")),
+        }
+    }
+    Err(no_backend_works(failures))
+}
+
+/// 向终端写入 OSC 52 序列，tmux 中以 DCS passthrough 包裹之
+fn write_osc52(payload: &str) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    anyhow::ensure!(stdout.is_terminal(), "stdout is not a terminal");
+    let mut seq = Zeroizing::new(format!("\x1b]52;c;{payload}\x07"));
+    if std::env::var_os("TMUX").is_some() {
+        seq = Zeroizing::new(format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b")));
+    }
+    stdout.write_all(seq.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// 运行外部命令，将给定内容写入其 stdin，
+/// 其 stdout 及 stderr 丢弃，以免破坏 TUI 界面
+fn run_with_input(program: &str, args: &[&str], input: &str) -> anyhow::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run '{program}'"))?;
+    // 写入后 drop 以关闭 stdin
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())?;
+    let status = child.wait()?;
+    anyhow::ensure!(status.success(), "'{program}' exited with {status}");
+    Ok(())
+}
+
+/// 运行外部命令，读取其 stdout
+fn run_for_output(program: &str, args: &[&str]) -> anyhow::Result<Zeroizing<String>> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run '{program}'"))?;
+    let stdout = Zeroizing::new(output.stdout);
+    anyhow::ensure!(output.status.success(), "'{program}' exited with {}", output.status);
+    Ok(Zeroizing::new(String::from_utf8(stdout.to_vec())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_from_toml() {
+        #[derive(Deserialize)]
+        struct Conf {
+            clipboard: ClipboardBackend,
+        }
+        for backend in [
+            ClipboardBackend::Auto,
+            ClipboardBackend::Arboard,
+            ClipboardBackend::Osc52,
+            ClipboardBackend::WlCopy,
+            ClipboardBackend::Xclip,
+            ClipboardBackend::Pbcopy,
+            ClipboardBackend::Tmux,
+        ] {
+            let conf: Conf = toml::from_str(&format!("clipboard = '{backend}'")).unwrap();
+            assert_eq!(conf.clipboard, backend);
+            if backend != ClipboardBackend::Auto {
+                assert_eq!(backend.candidates(), vec![backend]);
+            }
+        }
+        assert!(!ClipboardBackend::Auto.candidates().contains(&ClipboardBackend::Auto));
+    }
+}
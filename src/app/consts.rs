@@ -4,6 +4,10 @@ use indoc::indoc;
 pub const ENV_CONF_PATH_KEY: &str = "PNT_CONF_FILE";
 /// 环境变量中指向的 默认 data file 位置 key（值优先级高于配置文件中的）
 pub const ENV_DEFAULT_DATA_FILE_PATH_KEY: &str = "PNT_DEFAULT_DATA_FILE";
+/// 环境变量中的 非交互给出的主密码 key（见 `main_pwd_input`）
+pub const ENV_MAIN_PASSWORD_KEY: &str = "PNT_PASSWORD";
+/// 环境变量中的 非交互给出的新主密码 key，用于 mmp
+pub const ENV_NEW_MAIN_PASSWORD_KEY: &str = "PNT_NEW_PASSWORD";
 /// app name
 pub const APP_NAME: &str = clap::crate_name!();
 pub const APP_NAME_AND_VERSION: &str = concat!(clap::crate_name!(), " v", clap::crate_version!());
@@ -17,8 +21,27 @@ pub const CONF_FILE_NAME: &str = "pnt.toml";
 /// 允许的最多输错主密码次数
 pub const ALLOC_INVALID_MAIN_PASS_MAX: u8 = 3;
 
+/// 设定主密码时要求的最少字符数
+pub const MAIN_PWD_MIN_LEN: u8 = 6;
+
+/// TUI 中掩码密码所用的字符，详情页面以固定个数显示之，不泄露密码长度
+pub const PASSWORD_MASK_CHAR: char = '•';
+
+/// 每个条目默认保留的历史版本个数
+pub const DEFAULT_HISTORY_KEEP: u32 = 10;
+
+/// 回收站中的条目默认在多少天后永久删除
+pub const DEFAULT_TRASH_PURGE_DAYS: u32 = 30;
+
+/// TUI 删除条目后可撤销的秒数
+pub const TRASH_UNDO_SEC: u8 = 8;
+
+/// CLI 复制到剪贴板后持有其内容的最长秒数（仅 Linux 需进程持有剪贴板内容）
+pub const CLI_CLIPBOARD_HOLD_SEC: u64 = 45;
+
 /// 锁图标
 #[cfg(test)]
+#[allow(dead_code)]
 pub const LOCK_ICON: &str = indoc! {"
  ▄▄▄▄▄
  █   ▀
@@ -1,23 +1,75 @@
 use crate::app::cfg::Cfg;
-use crate::app::crypto::aes_gcm::EntryAes256GcmSecretEncrypter;
-use crate::app::crypto::{MainPwdVerifier, build_mpv};
-use crate::app::errors::AppError;
+use crate::app::crypto::entry_secret::{EntryEncryptMode, EntrySecretEncrypter};
+use crate::app::crypto::keyfile::Keyfile;
+use crate::app::crypto::subkey::DataKey;
+use crate::app::crypto::{MainPwdRecord, MainPwdVerifier, build_mpv};
+use crate::app::entry::{EncryptedEntry, GROUP_SEPARATOR, Group, Tag, TrashedEntry, ValidEntry, parse_group_path};
+use crate::app::errors::{AppError, CryptoError};
+use crate::app::failed_unlock::FailedUnlock;
+use crate::app::generator::PasswordPolicy;
 use crate::app::storage::Storage;
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
 use std::ops::Deref;
 use std::path::Path;
 
-/// 安全上下文，包含主密码校验器和条目加密解密器
+/// 安全上下文，包含数据密钥和条目加密解密器
 pub struct SecurityContext {
-    encrypter: EntryAes256GcmSecretEncrypter,
+    encrypter: EntrySecretEncrypter,
+    /// 条目加密解密器的子密钥由之派生，修改主密码或恢复密钥时以之重新包裹
+    data_key: DataKey,
+    /// 早于子密钥或数据密钥的 data file 解锁时附带，持有时即升级
+    ///
+    /// 仅升级时存在，使用Box引用以免增大 Event enum（其 MainPwdVerifySuccess 变体携带安全上下文）
+    key_upgrade: Option<Box<KeyUpgrade>>,
 }
 impl SecurityContext {
-    pub fn new(encrypter: EntryAes256GcmSecretEncrypter) -> Self {
-        Self { encrypter }
+    pub fn new(encrypter: EntrySecretEncrypter, data_key: DataKey) -> Self {
+        Self {
+            encrypter,
+            data_key,
+            key_upgrade: None,
+        }
+    }
+
+    /// 早于子密钥或数据密钥的 data file 的安全上下文
+    pub fn with_key_upgrade(encrypter: EntrySecretEncrypter, data_key: DataKey, key_upgrade: KeyUpgrade) -> Self {
+        Self {
+            encrypter,
+            data_key,
+            key_upgrade: Some(Box::new(key_upgrade)),
+        }
+    }
+
+    /// 使用相同密钥构建指定加密模式的安全上下文
+    pub fn with_mode(&self, mode: EntryEncryptMode) -> Self {
+        Self::new(self.encrypter.with_mode(mode), self.data_key.clone())
+    }
+
+    /// 数据密钥
+    pub fn data_key(&self) -> &DataKey {
+        &self.data_key
+    }
+
+    /// 取出升级所需，之后该安全上下文不再附带之
+    pub fn take_key_upgrade(&mut self) -> Option<KeyUpgrade> {
+        self.key_upgrade.take().map(|upgrade| *upgrade)
     }
 }
 
+/// 将早于子密钥或数据密钥的 data file 升级为数据密钥加密所需，
+/// 由主密码校验时派生的主密钥得到，遂升级无需再次 KDF
+pub struct KeyUpgrade {
+    /// 新形式的主密码校验段及被包裹的数据密钥，盐及 KDF 参数不变
+    pub record: MainPwdRecord,
+    /// 以数据密钥的子密钥加密的条目加密解密器，
+    /// 早于子密钥的 data file 须以之重新加密所有条目，否则为 None（条目无需重新加密）
+    pub encrypter: Option<EntrySecretEncrypter>,
+}
+
 impl Deref for SecurityContext {
-    type Target = EntryAes256GcmSecretEncrypter;
+    type Target = EntrySecretEncrypter;
     fn deref(&self) -> &Self::Target {
         &self.encrypter
     }
@@ -61,15 +113,394 @@ impl PntContext {
     ///
     /// 当 data file 被人为修改导致主密码hash找不到或被篡改结构导致无法定位salt等时
     pub fn mpv(&self) -> anyhow::Result<MainPwdVerifier> {
-        build_mpv(&self.storage)
+        build_mpv(&self.storage, self.keyfile()?)
+    }
+
+    /// 读取 data file 要求的密钥文件，不要求则 Ok(None)，
+    /// 要求但未给定（cli 参数或配置文件）或不可读则 Err
+    pub fn keyfile(&self) -> anyhow::Result<Option<Keyfile>> {
+        if !self.storage.query_keyfile_required()? {
+            return Ok(None);
+        }
+        let path = self.cfg.keyfile.as_deref().ok_or(AppError::KeyfileRequired)?;
+        Ok(Some(Keyfile::read(path)?))
     }
 
     /// 检查是否已验证主密码
     pub fn is_verified(&self) -> bool {
         self.security_context.is_some()
     }
+
+    /// 校验主密码前调用，返回允许下次尝试的时间（见 [`FailedUnlock::retry_after`]），无需等待则 None，
+    /// 若失败次数已达配置的上限，则 Err [`AppError::UnlockLockedOut`]
+    pub fn unlock_retry_after(&self) -> anyhow::Result<Option<DateTime<Local>>> {
+        let failed = self.storage.query_failed_unlock()?;
+        if let Some(max) = self.max_failed_unlock()
+            && failed.count >= max
+        {
+            Err(AppError::UnlockLockedOut { count: failed.count })?
+        }
+        Ok(failed.retry_after())
+    }
+
+    /// 记录一次主密码校验失败，返回允许下次尝试的时间，
+    ///
+    /// 若失败次数达到配置的上限，配置擦除时擦除 data file 后 Err [`AppError::DataWiped`]，
+    /// 否则 Err [`AppError::UnlockLockedOut`]
+    pub fn record_failed_unlock(&self) -> anyhow::Result<Option<DateTime<Local>>> {
+        let mut failed = self.storage.query_failed_unlock()?;
+        failed.record(Local::now());
+        self.storage.store_failed_unlock(&failed);
+        if let Some(max) = self.max_failed_unlock()
+            && failed.count >= max
+        {
+            if self.cfg.inner_cfg.wipe_on_max_failed_unlock {
+                self.storage.wipe()?;
+                Err(AppError::DataWiped)?
+            }
+            Err(AppError::UnlockLockedOut { count: failed.count })?
+        }
+        Ok(failed.retry_after())
+    }
+
+    /// 配置的失败次数上限，0 即不限制
+    fn max_failed_unlock(&self) -> Option<u32> {
+        self.cfg.inner_cfg.max_failed_unlock.filter(|max| *max > 0)
+    }
+
+    /// 持有主密码校验通过后载入的安全上下文，
+    ///
+    /// 若 data file 的条目密文尚未绑定条目或尚未以子密钥加密（早于该特性的 data file），
+    /// 则于此时重新加密所有条目以升级之，若尚未存储被包裹的数据密钥，则于此时存储之，
+    /// 此后仅持有升级后的安全上下文
+    ///
+    /// 返回自上次成功解锁以来的失败记录（若有），并清除之
+    pub fn hold_security_context(
+        &mut self, mut security_context: SecurityContext,
+    ) -> anyhow::Result<Option<FailedUnlock>> {
+        let bound_mode = EntryEncryptMode {
+            entry_bound: true,
+            ..security_context.mode()
+        };
+        if let Some(upgrade) = security_context.take_key_upgrade() {
+            let kdf = &self.cfg.inner_cfg.kdf;
+            match upgrade.encrypter {
+                Some(encrypter) => {
+                    let upgraded =
+                        SecurityContext::new(encrypter.with_mode(bound_mode), security_context.data_key().clone());
+                    self.storage
+                        .update_b64_s_mph(&upgrade.record, kdf, &security_context, &upgraded)?;
+                    security_context = upgraded;
+                }
+                None => self.storage.update_main_pwd(&upgrade.record, kdf)?,
+            }
+        }
+        let security_context = if security_context.is_entry_bound() {
+            security_context
+        } else {
+            let bound = security_context.with_mode(bound_mode);
+            self.storage
+                .update_entry_encrypt_mode(&security_context, &bound)?;
+            bound
+        };
+        self.security_context = Some(security_context);
+        let failed = self.storage.query_failed_unlock()?;
+        if failed.count == 0 {
+            return Ok(None);
+        }
+        self.storage.store_failed_unlock(&FailedUnlock::default());
+        Ok(Some(failed))
+    }
+
+    /// 返回 data file 是否为全加密模式（about 及 notes 亦加密）
+    pub fn is_full_encrypt(&self) -> bool {
+        self.cfg.inner_cfg.full_encrypt
+    }
+
+    /// 查询所有条目，用于列表显示及查找，
+    ///
+    /// 全加密模式下，若已验证主密码则解密其 about 及 notes，否则以掩码替代之
+    pub fn select_all_listing_entry(&self) -> anyhow::Result<Vec<EncryptedEntry>> {
+        let all_entry = self.storage.select_all_entry();
+        if !self.is_full_encrypt() {
+            return self.fill_tags_and_group(all_entry);
+        }
+        match &self.security_context {
+            Some(security_ctx) => self.fill_tags_and_group(
+                all_entry
+                    .iter()
+                    .map(|e| e.decrypt_about_notes(security_ctx))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            None => Ok(all_entry
+                .into_iter()
+                .map(|e| EncryptedEntry {
+                    about: Self::MASKED_ABOUT.to_owned(),
+                    notes: None,
+                    ..e
+                })
+                .collect()),
+        }
+    }
+    /// 查询回收站中的所有条目，最近删除的在前，
+    ///
+    /// 全加密模式下解密其 about 及 notes，遂要求已验证主密码
+    pub fn select_all_trashed_listing_entry(&self) -> anyhow::Result<Vec<TrashedEntry>> {
+        let trashed = self.storage.select_all_trashed_entry();
+        if !self.is_full_encrypt() {
+            return Ok(trashed);
+        }
+        let encrypter = self.try_encrypter()?;
+        trashed
+            .into_iter()
+            .map(|t| {
+                Ok(TrashedEntry {
+                    entry: t.entry.decrypt_about_notes(encrypter)?,
+                    ..t
+                })
+            })
+            .collect()
+    }
+    /// 全加密模式下未验证主密码时，列表中 about 的掩码
+    const MASKED_ABOUT: &'static str = "********";
+
+    /// 通过 about 模糊查找条目，
+    ///
+    /// 全加密模式下 about 为密文，遂要求已验证主密码，并在内存中对解密后的值过滤
+    pub fn find_entry_by_about_like(&self, like: &str) -> anyhow::Result<Vec<EncryptedEntry>> {
+        if !self.is_full_encrypt() {
+            return self.fill_tags_and_group(self.storage.select_entry_by_about_like(like));
+        }
+        self.try_encrypter()?;
+        let lower_like = like.to_ascii_lowercase();
+        Ok(self
+            .select_all_listing_entry()?
+            .into_iter()
+            .filter(|e| e.about.to_ascii_lowercase().contains(&lower_like))
+            .collect())
+    }
+    /// 通过 id 或 about 查找唯一条目，用于非交互的 `pnt get`，
+    ///
+    /// query 为数字且存在该 id 的条目时即为之，否则按 about 模糊查找，
+    /// 若匹配多个，其中仅一个 about 与 query 相同（忽略大小写）时即为之，
+    /// 否则无匹配 Err [`AppError::EntryNotFound`]，多个匹配 Err [`AppError::AmbiguousEntry`]
+    ///
+    /// 返回的条目为 storage 中的原样（全加密模式下其 about 仍为密文），但已填充标签及分组
+    pub fn find_single_entry(&self, query: &str) -> anyhow::Result<EncryptedEntry> {
+        if let Some(entry) = query
+            .parse::<u32>()
+            .ok()
+            .and_then(|id| self.storage.select_entry_by_id(id))
+        {
+            return self.with_tags_and_group(entry);
+        }
+        let mut matched = self.find_entry_by_about_like(query)?;
+        if matched.len() > 1 {
+            let exact: Vec<_> = matched
+                .iter()
+                .filter(|e| e.about.eq_ignore_ascii_case(query))
+                .collect();
+            if let [entry] = exact.as_slice() {
+                let id = entry.id;
+                matched.retain(|e| e.id == id);
+            }
+        }
+        match matched.as_slice() {
+            [] => Err(AppError::EntryNotFound(query.to_owned()))?,
+            // 全加密模式下查找结果的 about 已解密，重新读取原样的条目
+            [entry] => self.with_tags_and_group(
+                self.storage
+                    .select_entry_by_id(entry.id)
+                    .ok_or(AppError::DataCorrupted)?,
+            ),
+            _ => Err(AppError::AmbiguousEntry {
+                query: query.to_owned(),
+                matches: matched
+                    .iter()
+                    .map(|e| format!("#{} {}", e.id, e.about))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })?,
+        }
+    }
+
+    /// 以各条目的标签（按名称排序）及分组路径填充 entries 的 tags 及 group，
+    ///
+    /// 全加密模式下标签名及分组名为密文，若未验证主密码则不填充
+    fn fill_tags_and_group(&self, mut entries: Vec<EncryptedEntry>) -> anyhow::Result<Vec<EncryptedEntry>> {
+        let mut tags: HashMap<u32, Vec<String>> = HashMap::new();
+        for (e_id, tag) in self.storage.select_all_entry_tag() {
+            match self.plain_name(|enc| enc.decrypt_tag(&tag), &tag.name)? {
+                Some(name) => tags.entry(e_id).or_default().push(name),
+                None => return Ok(entries),
+            }
+        }
+        let Some(paths) = self.group_paths()? else {
+            return Ok(entries);
+        };
+        let groups: HashMap<u32, u32> = self.storage.select_all_entry_group().into_iter().collect();
+        for entry in &mut entries {
+            if let Some(mut names) = tags.remove(&entry.id) {
+                names.sort_by_key(|n| n.to_lowercase());
+                entry.tags = names;
+            }
+            entry.group = groups.get(&entry.id).and_then(|g_id| paths.get(g_id)).cloned();
+        }
+        Ok(entries)
+    }
+
+    /// 以条目的标签（按名称排序）及分组路径填充其 tags 及 group，用于 storage 中原样查询的条目，
+    ///
+    /// 全加密模式下标签名及分组名为密文，若未验证主密码则不填充
+    pub fn with_tags_and_group(&self, entry: EncryptedEntry) -> anyhow::Result<EncryptedEntry> {
+        Ok(self.fill_tags_and_group(vec![entry])?.remove(0))
+    }
+
+    /// 标签名或分组名的明文，全加密模式下未验证主密码则 None
+    fn plain_name(
+        &self, decrypt: impl FnOnce(&EntrySecretEncrypter) -> Result<String, CryptoError>, name: &str,
+    ) -> anyhow::Result<Option<String>> {
+        match &self.security_context {
+            Some(security_ctx) => Ok(Some(decrypt(security_ctx).with_context(|| AppError::DataCorrupted)?)),
+            None if self.is_full_encrypt() => Ok(None),
+            None => Ok(Some(name.to_owned())),
+        }
+    }
+
+    /// 所有分组的路径（如 `work/aws/prod`），全加密模式下未验证主密码则 None
+    fn group_paths(&self) -> anyhow::Result<Option<HashMap<u32, String>>> {
+        let mut groups = HashMap::new();
+        for group in self.storage.select_all_group() {
+            match self.plain_name(|enc| enc.decrypt_group(&group), &group.name)? {
+                Some(name) => groups.insert(group.id, (group.parent, name)),
+                None => return Ok(None),
+            };
+        }
+        let mut paths = HashMap::with_capacity(groups.len());
+        for &g_id in groups.keys() {
+            let mut names = Vec::new();
+            let mut current = Some(g_id);
+            while let Some(id) = current {
+                let (parent, name) = groups.get(&id).ok_or(AppError::DataCorrupted)?;
+                names.push(name.as_str());
+                // 上级分组成环即数据损坏
+                if names.len() > groups.len() {
+                    Err(AppError::DataCorrupted)?
+                }
+                current = *parent;
+            }
+            names.reverse();
+            paths.insert(g_id, names.join(GROUP_SEPARATOR));
+        }
+        Ok(Some(paths))
+    }
+
+    /// 插入用户新建的条目，并以给定的标签名及分组路径设定其标签及分组，
+    /// 见 [`PntContext::resolve_entry_tags`]、[`PntContext::store_entry_group`]，这些操作在一个事务中进行
+    pub fn insert_entry(&self, entry: &ValidEntry, tags: &[String], group: &str) -> anyhow::Result<()> {
+        let (new_tags, t_ids) = self.resolve_entry_tags(tags)?;
+        let (new_groups, g_id) = self.resolve_entry_group(group)?;
+        self.storage
+            .insert_entry_with_tags_and_group(entry, &new_tags, &t_ids, &new_groups, g_id)
+    }
+
+    /// 更新用户修改的条目，其更新前的版本归档为历史版本，并以给定的标签名及分组路径设定其标签及分组，
+    /// 这些操作在一个事务中进行
+    pub fn update_entry(&self, entry: &ValidEntry, tags: &[String], group: &str) -> anyhow::Result<()> {
+        let (new_tags, t_ids) = self.resolve_entry_tags(tags)?;
+        let (new_groups, g_id) = self.resolve_entry_group(group)?;
+        let keep = self.cfg.inner_cfg.history_keep;
+        self.storage
+            .update_entry_with_tags_and_group(entry, keep, &new_tags, &t_ids, &new_groups, g_id)
+    }
+
+    /// 将条目移至给定路径（见 [`parse_group_path`]）的分组，空路径即移出所有分组，
+    /// 路径中各级已有同名（不区分大小写）的分组即复用之，否则新建，不再使用的分组即删除，
+    /// 全加密模式下分组名亦加密，遂要求已验证主密码
+    pub fn store_entry_group(&self, e_id: u32, path: &str) -> anyhow::Result<()> {
+        let (new_groups, g_id) = self.resolve_entry_group(path)?;
+        self.storage.store_entry_group(e_id, &new_groups, g_id)
+    }
+
+    /// 解析分组路径，返回需新建的（已加密的）分组及条目所属分组的id，仅查询不写入
+    fn resolve_entry_group(&self, path: &str) -> anyhow::Result<(Vec<Group>, Option<u32>)> {
+        let encrypter = self.try_encrypter()?;
+        let mut known = Vec::new();
+        for group in self.storage.select_all_group() {
+            let name = encrypter
+                .decrypt_group(&group)
+                .with_context(|| AppError::DataCorrupted)?;
+            known.push((group.parent, name.to_lowercase(), group.id));
+        }
+        let mut next_id = self.storage.next_group_id();
+        let mut new_groups: Vec<Group> = Vec::new();
+        let mut g_id = None;
+        for name in parse_group_path(path) {
+            let lower = name.to_lowercase();
+            let id = match known.iter().find(|(p, n, _)| *p == g_id && *n == lower) {
+                Some((_, _, id)) => *id,
+                None => {
+                    let id = next_id;
+                    next_id += 1;
+                    new_groups.push(encrypter.encrypt_group(id, g_id, &name)?);
+                    known.push((g_id, lower, id));
+                    id
+                }
+            };
+            g_id = Some(id);
+        }
+        Ok((new_groups, g_id))
+    }
+
+    /// 解析条目的标签名，返回需新建的（已加密的）标签及条目的所有标签的id，仅查询不写入，
+    /// 已有同名（不区分大小写）的标签即复用之，否则新建，不再被任何条目使用的标签在写入时删除，
+    /// 全加密模式下标签名亦加密，遂要求已验证主密码
+    fn resolve_entry_tags(&self, tags: &[String]) -> anyhow::Result<(Vec<Tag>, Vec<u32>)> {
+        let encrypter = self.try_encrypter()?;
+        let mut known = Vec::new();
+        for tag in self.storage.select_all_tag() {
+            let name = encrypter
+                .decrypt_tag(&tag)
+                .with_context(|| AppError::DataCorrupted)?;
+            known.push((name.to_lowercase(), tag.id));
+        }
+        let mut next_id = self.storage.next_tag_id();
+        let mut new_tags = Vec::new();
+        let mut t_ids = Vec::with_capacity(tags.len());
+        for name in tags {
+            let lower = name.to_lowercase();
+            let t_id = match known.iter().find(|(n, _)| *n == lower) {
+                Some((_, t_id)) => *t_id,
+                None => {
+                    let t_id = next_id;
+                    next_id += 1;
+                    new_tags.push(encrypter.encrypt_tag(t_id, name)?);
+                    known.push((lower, t_id));
+                    t_id
+                }
+            };
+            t_ids.push(t_id);
+        }
+        Ok((new_tags, t_ids))
+    }
+
+    /// 查找密码生成策略，给定名称而不存在则 Err [`AppError::GenPolicyNotFound`]，
+    /// 未给定名称则为名为 [`PasswordPolicy::DEFAULT_NAME`] 的策略，不存在即 [`PasswordPolicy::default`]
+    pub fn gen_policy(&self, name: Option<&str>) -> anyhow::Result<PasswordPolicy> {
+        match name {
+            Some(name) => Ok(self
+                .storage
+                .query_gen_policy(name)?
+                .ok_or_else(|| AppError::GenPolicyNotFound(name.to_owned()))?),
+            None => Ok(self
+                .storage
+                .query_gen_policy(PasswordPolicy::DEFAULT_NAME)?
+                .unwrap_or_default()),
+        }
+    }
+
     /// 尝试获取条目加密解密器，若未验证主密码则返回Err
-    pub fn try_encrypter(&self) -> Result<&EntryAes256GcmSecretEncrypter, AppError> {
+    pub fn try_encrypter(&self) -> Result<&EntrySecretEncrypter, AppError> {
         match &self.security_context {
             Some(security_ctx) => Ok(security_ctx),
             None => Err(AppError::MainPwdNotVerified),
@@ -104,3 +535,69 @@ impl DataFileState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::cfg::InnerCfg;
+
+    fn context_with_max_failed(max: u32, wipe: bool) -> PntContext {
+        let cfg = Cfg {
+            load_data: Default::default(),
+            keyfile: None,
+            clipboard: Default::default(),
+            inner_cfg: InnerCfg {
+                max_failed_unlock: Some(max),
+                wipe_on_max_failed_unlock: wipe,
+                ..InnerCfg::default()
+            },
+        };
+        let storage = Storage::open_in_memory().unwrap();
+        storage.store_main_pwd_record(&MainPwdRecord {
+            b64_s_mph: "mp".to_owned(),
+            wrapped_key: "wk".to_owned(),
+        });
+        PntContext::new_with_un_verified(cfg, storage)
+    }
+
+    #[test]
+    fn test_failed_unlock_lockout_and_wipe() {
+        let locking = context_with_max_failed(2, false);
+        assert!(locking.record_failed_unlock().unwrap().is_none());
+        assert!(locking.record_failed_unlock().is_err());
+        // 失败记录持久化于 data file，之后的尝试均被拒绝
+        let err = locking.unlock_retry_after().unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(AppError::UnlockLockedOut { count: 2 })));
+        assert!(locking.storage.query_b64_s_mph().is_some());
+
+        let wiping = context_with_max_failed(1, true);
+        let err = wiping.record_failed_unlock().unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(AppError::DataWiped)));
+        assert!(wiping.storage.query_b64_s_mph().is_none());
+        assert!(wiping.storage.query_wrapped_data_key().is_none());
+    }
+
+    #[test]
+    fn test_find_single_entry() {
+        let context = context_with_max_failed(0, false);
+        for about in ["github", "github work", "gitlab"] {
+            let id = context.storage.next_entry_id();
+            context.storage.insert_entry(&ValidEntry {
+                id,
+                about: about.to_owned(),
+                notes: None,
+                encrypted_username: String::new(),
+                encrypted_password: String::new(),
+                fields: None,
+            });
+        }
+        // 完全相同的 about 优先于部分匹配
+        assert_eq!(context.find_single_entry("GitHub").unwrap().about, "github");
+        assert_eq!(context.find_single_entry("3").unwrap().about, "gitlab");
+        assert_eq!(context.find_single_entry("lab").unwrap().about, "gitlab");
+        let err = context.find_single_entry("git").unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(AppError::AmbiguousEntry { .. })));
+        let err = context.find_single_entry("bitbucket").unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(AppError::EntryNotFound(_))));
+    }
+}
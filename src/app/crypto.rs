@@ -1,15 +1,26 @@
 //! pnt 使用的加密解密部分
 
 pub mod aes_gcm;
+pub mod entry_secret;
+pub mod envelope;
+pub mod kdf;
+pub mod keyfile;
+pub mod subkey;
+pub mod xchacha20_poly1305;
 
-use crate::app::context::SecurityContext;
-use crate::app::crypto::aes_gcm::EntryAes256GcmSecretEncrypter;
+use crate::app::context::{KeyUpgrade, SecurityContext};
+use crate::app::crypto::entry_secret::{EntryEncryptMode, EntrySecretEncrypter};
+use crate::app::crypto::kdf::KdfParams;
+use crate::app::crypto::keyfile::Keyfile;
+use crate::app::crypto::subkey::{DataKey, KeyPurpose, MasterKey};
 use crate::app::errors::{AppError, CryptoError};
 use crate::app::storage::Storage;
 use argon2::password_hash::rand_core::{OsRng, RngCore};
 use argon2::password_hash::{Error, SaltString};
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::{PasswordHash, PasswordVerifier};
+use anyhow::Context;
 use base64ct::{Base64, Encoding};
+use std::fmt::{Debug, Formatter};
 
 /// 加密器
 pub trait Encrypter<P, C> {
@@ -24,86 +35,195 @@ pub trait Decrypter<C, P> {
     fn decrypt(&self, ciphertext: C) -> Result<P, Self::DecrypterError>;
 }
 
-/// 读取storage中salt和storage中主密码的哈希校验段，
-/// 构建 主密码校验器，
+/// 读取storage中salt和storage中主密码的哈希校验段、被包裹的数据密钥及 KDF 参数，
+/// 与给定的密钥文件（data file 要求时）一同构建 主密码校验器，
 /// 若主密码在storage中找不到或因salt等原因构建失败则返回Err
-pub fn build_mpv(storage: &Storage) -> anyhow::Result<MainPwdVerifier> {
+pub fn build_mpv(storage: &Storage, keyfile: Option<Keyfile>) -> anyhow::Result<MainPwdVerifier> {
     let b64_s_mph = storage.query_b64_s_mph().ok_or(AppError::DataCorrupted)?;
-    MainPwdVerifier::from_b64_s_mph(&b64_s_mph)
+    MainPwdVerifier::from_b64_s_mph(
+        &b64_s_mph,
+        storage.query_wrapped_data_key(),
+        storage.query_kdf_params()?,
+        keyfile,
+        storage.query_entry_encrypt_mode()?,
+    )
 }
 
-/// 主密码加密器，使用Argon2id算法加密主密码明文
-/// 返回的加密后为单向hash的b64编码
+/// 主密码加密器，使用给定 KDF 参数的 Argon2 算法派生主密钥（给定密钥文件时其参与派生），
+/// 返回的加密后为主密钥派生的校验值（见 [`MasterKey::verifier_mac`]）的编码及被其包裹的数据密钥
 pub struct MainPwdEncrypter {
     salt: [u8; 32],
+    kdf: KdfParams,
+    keyfile: Option<Keyfile>,
 }
 impl MainPwdEncrypter {
-    pub fn from_salt(salt: [u8; 32]) -> Self {
-        Self { salt }
+    pub fn from_salt(salt: [u8; 32], kdf: KdfParams, keyfile: Option<Keyfile>) -> Self {
+        Self { salt, kdf, keyfile }
     }
 
-    pub fn new_from_random_salt() -> Self {
+    pub fn new_from_random_salt(kdf: KdfParams, keyfile: Option<Keyfile>) -> Self {
         let mut salt = [0u8; 32];
         OsRng.fill_bytes(&mut salt);
-        Self::from_salt(salt)
+        Self::from_salt(salt, kdf, keyfile)
     }
 
     #[cfg(test)]
     pub fn salt(&self) -> &[u8; 32] {
         &self.salt
     }
-}
 
-impl Encrypter<String, String> for MainPwdEncrypter {
-    type EncrypterError = CryptoError;
-    /// 加密主密码，使用Argon2id算法单向加密，后续仅校验hash，
-    /// 返回 b64(salt32 + mph)
-    /// # Panics
-    /// salt 太短 <8 或 太长 >64
-    /// mph > usize::MAX/4
-    fn encrypt(&self, plaintext: String) -> Result<String, CryptoError> {
+    /// 加密主密码，使用Argon2算法派生主密钥，后续仅校验主密钥派生的校验值，
+    /// 并以主密钥派生的包裹子密钥包裹给定的数据密钥
+    pub fn encrypt(&self, passwd: &str, data_key: &DataKey) -> anyhow::Result<MainPwdRecord> {
         let ss = SaltString::encode_b64(&self.salt).map_err(CryptoError::DecodeSalt)?;
-        let mph = Argon2::default()
-            .hash_password(plaintext.as_bytes(), &ss)
-            .map_err(CryptoError::EncryptMainPwd)?
-            .to_string();
-        Ok(encode_b64_s_mph(&self.salt, &mph))
+        let master = MasterKey::derive(passwd, ss.as_str().as_bytes(), &self.kdf, self.keyfile.as_ref())?;
+        let kek = master.subkey(KeyPurpose::Wrap)?;
+        Ok(MainPwdRecord {
+            b64_s_mph: encode_b64_s_mac(&self.salt, &master.verifier_mac()?),
+            wrapped_key: data_key.wrap(&kek)?,
+        })
     }
 }
 
-/// 主密码校验器
-#[derive(Debug)]
+/// 主密码加密的结果，即 data file 中存储的主密码校验段及被主密钥包裹的数据密钥，二者须一同存储
+pub struct MainPwdRecord {
+    /// `$` + b64(salt32 + mac32)
+    pub b64_s_mph: String,
+    /// 被主密钥派生的包裹子密钥包裹的数据密钥
+    pub wrapped_key: String,
+}
+
+/// data file 中存储的主密码校验段
+enum MainPwdHash {
+    /// 主密钥派生的校验值
+    Mac([u8; 32]),
+    /// 早于子密钥的 data file：Argon2 PHC 字符串，校验与派生密钥需两次 KDF
+    Legacy(String),
+}
+
+/// 主密码校验器，Debug 不输出其盐及校验段
 pub struct MainPwdVerifier {
-    salt: SaltString,
-    mph: String,
+    salt: [u8; 32],
+    mph: MainPwdHash,
+    /// 被主密钥包裹的数据密钥，早于数据密钥的 data file 为 None
+    wrapped_key: Option<String>,
+    /// 派生主密钥使用的 KDF 参数
+    kdf: KdfParams,
+    /// 参与派生主密钥的密钥文件，data file 不要求密钥文件时为 None
+    keyfile: Option<Keyfile>,
+    /// data file 的条目加密模式，决定载入的安全上下文的加密方式
+    mode: EntryEncryptMode,
 }
 impl MainPwdVerifier {
     /// 构建一个主密码校验器
     /// # Arguments
-    /// * `salt` - 盐
-    /// * `b64_mph` - argon2 hash 加密后的主密码(b64编码）
-    pub fn from_b64_s_mph(b64_s_mph: &str) -> anyhow::Result<Self> {
+    /// * `b64_s_mph` - `$` + b64(salt32 + mac32)，或早于子密钥的 b64(salt32 + argon2 hash 加密后的主密码)
+    /// * `wrapped_key` - 被主密钥包裹的数据密钥
+    /// * `kdf` - data file 的 KDF 参数
+    /// * `keyfile` - data file 要求的密钥文件
+    /// * `mode` - data file 的条目加密模式
+    pub fn from_b64_s_mph(
+        b64_s_mph: &str, wrapped_key: Option<String>, kdf: KdfParams, keyfile: Option<Keyfile>,
+        mode: EntryEncryptMode,
+    ) -> anyhow::Result<Self> {
         // 从 s_mp_b64 可base64de到salt，若过程失败，则证明数据已被破坏
-        let (salt, mph) = decode_b64_s_mph(b64_s_mph)?;
+        let (salt, mph) = match b64_s_mph.strip_prefix(MAC_PREFIX) {
+            Some(b64_s_mac) => {
+                let (salt, mac) = decode_b64_s_mac(b64_s_mac)?;
+                (salt, MainPwdHash::Mac(mac))
+            }
+            None => {
+                let (salt, mph) = decode_b64_s_mph(b64_s_mph)?;
+                (salt, MainPwdHash::Legacy(mph))
+            }
+        };
         Ok(Self {
-            salt: SaltString::encode_b64(&salt).map_err(CryptoError::DecodeSalt)?,
+            salt,
             mph,
+            wrapped_key,
+            kdf,
+            keyfile,
+            mode,
         })
     }
 }
 
+impl Debug for MainPwdVerifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MainPwdVerifier")
+            .field("kdf", &self.kdf)
+            .field("keyfile", &self.keyfile)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
 impl MainPwdVerifier {
-    /// 校验主密码，返回 Result
-    /// 若校验通过，则返回 Ok(true)
-    /// 若校验失败，则返回 Ok(false)
+    /// 校验主密码，校验通过则一并载入安全上下文，返回 Result
+    /// 若校验通过，则返回 Ok(Some)
+    /// 若校验失败，则返回 Ok(None)
     /// 若校验过程中出现错误，则返回 Err
-    pub fn verify(&self, passwd: &str) -> anyhow::Result<bool> {
+    ///
+    /// 早于子密钥或数据密钥的 data file 载入的安全上下文附带升级所需（见 [`KeyUpgrade`]）
+    pub fn try_load_security_context(&self, passwd: &str) -> anyhow::Result<Option<SecurityContext>> {
+        let ss = SaltString::encode_b64(&self.salt).map_err(CryptoError::DecodeSalt)?;
+        match &self.mph {
+            MainPwdHash::Mac(mac) => {
+                // 仅一次 KDF，校验值与包裹子密钥均由该主密钥派生
+                let master =
+                    MasterKey::derive(passwd, ss.as_str().as_bytes(), &self.kdf, self.keyfile.as_ref())?;
+                if !master.verify_mac(mac)? {
+                    return Ok(None);
+                }
+                let kek = master.subkey(KeyPurpose::Wrap)?;
+                let Some(wrapped_key) = &self.wrapped_key else {
+                    // 早于数据密钥，以主密钥的值作为数据密钥，条目无需重新加密，仅需存储被包裹的数据密钥
+                    let data_key = DataKey::from_master(&master);
+                    let upgrade = KeyUpgrade {
+                        record: MainPwdRecord {
+                            b64_s_mph: encode_b64_s_mac(&self.salt, mac),
+                            wrapped_key: data_key.wrap(&kek)?,
+                        },
+                        encrypter: None,
+                    };
+                    let encrypter = data_key.entry_encrypter(self.mode)?;
+                    return Ok(Some(SecurityContext::with_key_upgrade(encrypter, data_key, upgrade)));
+                };
+                // 校验值已通过，解包失败即说明数据已被破坏
+                let data_key = DataKey::unwrap(&kek, wrapped_key).with_context(|| AppError::DataCorrupted)?;
+                let encrypter = data_key.entry_encrypter(self.mode)?;
+                Ok(Some(SecurityContext::new(encrypter, data_key)))
+            }
+            MainPwdHash::Legacy(mph) => {
+                if !self.verify_legacy(mph, passwd)? {
+                    return Ok(None);
+                }
+                // 密钥文件晚于子密钥，旧 data file 不会要求之
+                let master = MasterKey::derive(passwd, ss.as_str().as_bytes(), &self.kdf, None)?;
+                let legacy_key = master.legacy_entry_key();
+                let encrypter = EntrySecretEncrypter::from_keys(&legacy_key, &legacy_key, self.mode)?;
+                let data_key = DataKey::from_master(&master);
+                let kek = master.subkey(KeyPurpose::Wrap)?;
+                let upgrade = KeyUpgrade {
+                    record: MainPwdRecord {
+                        b64_s_mph: encode_b64_s_mac(&self.salt, &master.verifier_mac()?),
+                        wrapped_key: data_key.wrap(&kek)?,
+                    },
+                    encrypter: Some(data_key.entry_encrypter(self.mode)?),
+                };
+                Ok(Some(SecurityContext::with_key_upgrade(encrypter, data_key, upgrade)))
+            }
+        }
+    }
+
+    /// 以 Argon2 PHC 字符串校验早于子密钥的主密码
+    fn verify_legacy(&self, mph: &str, passwd: &str) -> anyhow::Result<bool> {
         // argon2 实例仅是值容器，创建代价小，无需存储实例
-        // 其param使用 pub const DEFAULT，编译时确定
-        let argon2 = Argon2::default();
+        // 其param由 data file 中存储的 KDF 参数确定
+        let argon2 = self.kdf.argon2()?;
         let verify_r = argon2.verify_password(
             passwd.as_bytes(),
-            &PasswordHash::new(&self.mph).map_err(CryptoError::EncryptMainPwd)?,
+            &PasswordHash::new(mph).map_err(CryptoError::EncryptMainPwd)?,
         );
         match verify_r {
             Ok(()) => Ok(true),
@@ -116,22 +236,32 @@ impl MainPwdVerifier {
             }
         }
     }
-    /// 生成加密解密条目的密钥
-    fn gen_key(&self, passwd: &str) -> anyhow::Result<[u8; 32]> {
-        let mut gp = [0u8; 32];
-        Argon2::default()
-            .hash_password_into(passwd.as_bytes(), self.salt.as_str().as_bytes(), &mut gp)
-            .map_err(|_| CryptoError::GenerateKey)?; // 安全相关 用map_err 缩减 暴露的err信息
-        Ok(gp)
-    }
+}
+
+/// 主密码校验值形式的前缀，早于子密钥的 b64(SALT(32) + MPH) 无该前缀，
+/// 标准 base64 字母表中没有 `$`，遂二者可无歧义地区分
+const MAC_PREFIX: char = '$';
 
-    /// 在校验成功后加载安全上下文，返回安全上下文
-    /// 该方法不会对给定的密码再进行主密码校验
-    pub fn load_security_context(&self, passwd: &str) -> anyhow::Result<SecurityContext> {
-        Ok(SecurityContext::new(EntryAes256GcmSecretEncrypter::from_key(
-            self.gen_key(passwd)?,
-        )?))
+/// 将 b64(SALT(32) + MAC(32)) 解码为 (SALT(32), MAC(32)) 返回
+///
+/// 若数据被破坏则返回Err
+fn decode_b64_s_mac(b64_s_mac: &str) -> anyhow::Result<([u8; 32], [u8; 32])> {
+    let dec = Base64::decode_vec(b64_s_mac).map_err(|_| AppError::DataCorrupted)?;
+    if dec.len() != 64 {
+        Err(AppError::DataCorrupted)?
     }
+    let mut salt = [0_u8; 32];
+    salt.copy_from_slice(&dec[..32]);
+    let mut mac = [0_u8; 32];
+    mac.copy_from_slice(&dec[32..]);
+    Ok((salt, mac))
+}
+/// 将 SALT(32), MAC(32) 编码为 `$` + b64(SALT(32) + MAC(32)) 返回
+fn encode_b64_s_mac(salt: &[u8; 32], mac: &[u8; 32]) -> String {
+    let mut vec = Vec::with_capacity(64);
+    vec.extend(salt);
+    vec.extend(mac);
+    format!("{MAC_PREFIX}{}", Base64::encode_string(&vec))
 }
 
 /// 将 b64(SALT(32) + MPH) 解码为 (SALT(32), MPH) 返回
@@ -140,6 +270,9 @@ impl MainPwdVerifier {
 fn decode_b64_s_mph(b64_s_mph: &str) -> anyhow::Result<([u8; 32], String)> {
     // 正常情况下不会 b64 decode 失败，只有当 文件被手动人为修改，才会有这种情况，遂向外告知数据已损坏
     let dec = Base64::decode_vec(b64_s_mph).map_err(|_| AppError::DataCorrupted)?;
+    if dec.len() < 32 {
+        Err(AppError::DataCorrupted)?
+    }
     // 前32位为salt，后为utf8 mph
     let mut salt = [0_u8; 32];
     salt.copy_from_slice(&dec[..32]);
@@ -147,7 +280,8 @@ fn decode_b64_s_mph(b64_s_mph: &str) -> anyhow::Result<([u8; 32], String)> {
     let mph = str::from_utf8(mph).map_err(|_| AppError::DataCorrupted)?.to_string();
     Ok((salt, mph))
 }
-/// 将 SALT(32), MPH 编码为 b64(SALT(32) + MPH) 返回
+/// 将 SALT(32), MPH 编码为 b64(SALT(32) + MPH) 返回，仅用于测试旧 data file 的兼容
+#[cfg(test)]
 fn encode_b64_s_mph(salt: &[u8; 32], mph: &str) -> String {
     let mut vec = Vec::with_capacity(32 + mph.len());
     vec.extend(salt);
@@ -158,29 +292,33 @@ fn encode_b64_s_mph(salt: &[u8; 32], mph: &str) -> String {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::app::entry::{EncryptedEntry, InputEntry};
+    use argon2::{Argon2, PasswordHasher};
     #[test]
     fn test_encrypter_mph() {
         let plaintext = "Hello, world!".to_owned();
-        let encrypter = MainPwdEncrypter::new_from_random_salt();
-        let cs1 = encrypter.encrypt(plaintext.clone()).unwrap();
-        let cs2 = encrypter.encrypt(plaintext.clone()).unwrap();
+        let data_key = DataKey::generate();
+        let encrypter = MainPwdEncrypter::new_from_random_salt(KdfParams::default(), None);
+        let cs1 = encrypter.encrypt(&plaintext, &data_key).unwrap();
+        let cs2 = encrypter.encrypt(&plaintext, &data_key).unwrap();
         let salt = encrypter.salt();
-        assert_eq!(cs1, cs2);
-        let cs3 = MainPwdEncrypter::from_salt(*salt).encrypt(plaintext).unwrap();
-        assert_eq!(cs3, cs1);
-        // println!("cs1: {cs1}");
+        assert_eq!(cs1.b64_s_mph, cs2.b64_s_mph);
+        // 包裹使用随机 nonce
+        assert_ne!(cs1.wrapped_key, cs2.wrapped_key);
+        let cs3 = MainPwdEncrypter::from_salt(*salt, KdfParams::default(), None)
+            .encrypt(&plaintext, &data_key)
+            .unwrap();
+        assert_eq!(cs3.b64_s_mph, cs1.b64_s_mph);
     }
 
     #[test]
     fn test_encode_salt_and_b64_mph() {
-        let foobar = String::from("foobar");
-        let encrypter = MainPwdEncrypter::new_from_random_salt();
-        let b64_mph = encrypter.encrypt(foobar.clone()).unwrap();
-        let salt = encrypter.salt();
-        let b64_s_mph = encode_b64_s_mph(&salt, &b64_mph);
+        let salt = [7_u8; 32];
+        let phc = "$argon2id$v=19$m=19456,t=2,p=1$foobar";
+        let b64_s_mph = encode_b64_s_mph(&salt, phc);
         let (salt_de, mph) = decode_b64_s_mph(&b64_s_mph).unwrap();
-        assert_eq!(*salt, salt_de);
-        assert_eq!(b64_mph, mph);
+        assert_eq!(salt, salt_de);
+        assert_eq!(phc, mph);
     }
 
     #[test]
@@ -198,4 +336,158 @@ mod test {
         let rpb = r_p.as_bytes();
         assert_ne!(gp, *rpb);
     }
+
+    const MODE: EntryEncryptMode = EntryEncryptMode {
+        full_encrypt: false,
+        entry_bound: true,
+        cipher: envelope::CipherAlg::Aes256Gcm,
+    };
+
+    #[test]
+    fn test_verify_with_kdf_params() {
+        let mp = "foobar";
+        let kdf = KdfParams {
+            m_cost: 8192,
+            t_cost: 3,
+            ..KdfParams::default()
+        };
+        let encrypter = MainPwdEncrypter::new_from_random_salt(kdf, None);
+        let record = encrypter.encrypt(mp, &DataKey::generate()).unwrap();
+        let wk = Some(record.wrapped_key);
+        let verifier = MainPwdVerifier::from_b64_s_mph(&record.b64_s_mph, wk.clone(), kdf, None, MODE).unwrap();
+        let mut ctx = verifier.try_load_security_context(mp).unwrap().unwrap();
+        assert!(ctx.take_key_upgrade().is_none());
+        assert!(verifier.try_load_security_context("barfoo").unwrap().is_none());
+        // 相同盐不同参数，派生的主密钥不同，遂校验不通过
+        let default_verifier =
+            MainPwdVerifier::from_b64_s_mph(&record.b64_s_mph, wk, KdfParams::default(), None, MODE).unwrap();
+        assert!(default_verifier.try_load_security_context(mp).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_verify_with_keyfile() {
+        let mp = "foobar";
+        let kdf = KdfParams {
+            m_cost: 8192,
+            t_cost: 1,
+            ..KdfParams::default()
+        };
+        let keyfile = Keyfile::from_contents(b"second factor");
+        let record = MainPwdEncrypter::new_from_random_salt(kdf, Some(keyfile.clone()))
+            .encrypt(mp, &DataKey::generate())
+            .unwrap();
+        let (b64_s_mph, wk) = (&record.b64_s_mph, Some(record.wrapped_key.clone()));
+        let verifier = MainPwdVerifier::from_b64_s_mph(b64_s_mph, wk.clone(), kdf, Some(keyfile), MODE).unwrap();
+        assert!(verifier.try_load_security_context(mp).unwrap().is_some());
+        // 缺少或给定其他密钥文件，正确的主密码亦不可解锁
+        let without = MainPwdVerifier::from_b64_s_mph(b64_s_mph, wk.clone(), kdf, None, MODE).unwrap();
+        assert!(without.try_load_security_context(mp).unwrap().is_none());
+        let other = Keyfile::from_contents(b"other");
+        let with_other = MainPwdVerifier::from_b64_s_mph(b64_s_mph, wk, kdf, Some(other), MODE).unwrap();
+        assert!(with_other.try_load_security_context(mp).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_legacy_mph_key_upgrade() {
+        let mp = "foobar";
+        let kdf = KdfParams {
+            m_cost: 8192,
+            t_cost: 1,
+            ..KdfParams::default()
+        };
+        let salt = [3_u8; 32];
+        let ss = SaltString::encode_b64(&salt).unwrap();
+        let phc = kdf.argon2().unwrap().hash_password(mp.as_bytes(), &ss).unwrap().to_string();
+        let legacy_b64_s_mph = encode_b64_s_mph(&salt, &phc);
+        let verifier = MainPwdVerifier::from_b64_s_mph(&legacy_b64_s_mph, None, kdf, None, MODE).unwrap();
+        assert!(verifier.try_load_security_context("barfoo").unwrap().is_none());
+        let mut legacy_ctx = verifier.try_load_security_context(mp).unwrap().unwrap();
+        let upgrade = legacy_ctx.take_key_upgrade().unwrap();
+        // 升级后的校验段以相同盐及参数校验，且解包的数据密钥与升级所需的一致
+        let upgraded = MainPwdVerifier::from_b64_s_mph(
+            &upgrade.record.b64_s_mph,
+            Some(upgrade.record.wrapped_key.clone()),
+            kdf,
+            None,
+            MODE,
+        )
+        .unwrap()
+        .try_load_security_context(mp)
+        .unwrap()
+        .unwrap();
+        let input = InputEntry {
+            about: "github".to_owned(),
+            notes: String::new(),
+            username: "def".to_owned(),
+            password: "abc".to_owned(),
+            tags: Vec::new(),
+            group: String::new(),
+            fields: Vec::new(),
+        };
+        let v_e = upgrade.encrypter.as_ref().unwrap().encrypt((1, &input)).unwrap();
+        let enc_entry = EncryptedEntry {
+            id: v_e.id,
+            about: v_e.about,
+            notes: v_e.notes,
+            encrypted_username: v_e.encrypted_username,
+            encrypted_password: v_e.encrypted_password,
+            created_time: Default::default(),
+            updated_time: Default::default(),
+            tags: Vec::new(),
+            group: None,
+            fields: None,
+        };
+        assert_eq!(upgraded.decrypt(&enc_entry).unwrap().password, input.password);
+        // 旧密钥即 KDF 输出，与子密钥不同
+        assert!(legacy_ctx.decrypt(&enc_entry).is_err());
+    }
+
+    #[test]
+    fn test_unwrapped_mac_upgrade_keeps_entry_key() {
+        let mp = "foobar";
+        let kdf = KdfParams {
+            m_cost: 8192,
+            t_cost: 1,
+            ..KdfParams::default()
+        };
+        let salt = [5_u8; 32];
+        let ss = SaltString::encode_b64(&salt).unwrap();
+        let master = MasterKey::derive(mp, ss.as_str().as_bytes(), &kdf, None).unwrap();
+        // 早于数据密钥的 data file，条目子密钥直接由主密钥派生
+        let b64_s_mph = encode_b64_s_mac(&salt, &master.verifier_mac().unwrap());
+        let old_encrypter = DataKey::from_master(&master).entry_encrypter(MODE).unwrap();
+        let verifier = MainPwdVerifier::from_b64_s_mph(&b64_s_mph, None, kdf, None, MODE).unwrap();
+        let mut ctx = verifier.try_load_security_context(mp).unwrap().unwrap();
+        let upgrade = ctx.take_key_upgrade().unwrap();
+        assert!(upgrade.encrypter.is_none());
+        assert_eq!(upgrade.record.b64_s_mph, b64_s_mph);
+        let upgraded = MainPwdVerifier::from_b64_s_mph(&b64_s_mph, Some(upgrade.record.wrapped_key), kdf, None, MODE)
+            .unwrap()
+            .try_load_security_context(mp)
+            .unwrap()
+            .unwrap();
+        let input = InputEntry {
+            about: "github".to_owned(),
+            notes: String::new(),
+            username: "def".to_owned(),
+            password: "abc".to_owned(),
+            tags: Vec::new(),
+            group: String::new(),
+            fields: Vec::new(),
+        };
+        let v_e = old_encrypter.encrypt((1, &input)).unwrap();
+        let enc_entry = EncryptedEntry {
+            id: v_e.id,
+            about: v_e.about,
+            notes: v_e.notes,
+            encrypted_username: v_e.encrypted_username,
+            encrypted_password: v_e.encrypted_password,
+            created_time: Default::default(),
+            updated_time: Default::default(),
+            tags: Vec::new(),
+            group: None,
+            fields: None,
+        };
+        assert_eq!(upgraded.decrypt(&enc_entry).unwrap().password, input.password);
+    }
 }
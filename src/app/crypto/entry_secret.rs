@@ -0,0 +1,562 @@
+//! 条目秘密字段的加密解密
+
+use crate::app::crypto::aes_gcm::StrAes256GcmEncrypter;
+use crate::app::crypto::envelope::{CipherAlg, Envelope, EnvelopeCipher};
+use crate::app::crypto::xchacha20_poly1305::StrXChaCha20Poly1305Encrypter;
+use crate::app::crypto::{Decrypter, Encrypter};
+use crate::app::entry::{CustomField, EncryptedEntry, Group, InputEntry, Tag, ValidEntry};
+use crate::app::errors::CryptoError;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 条目中加密字段的标签，与条目 id 一同作为关联数据，
+/// 使密文仅能在其所属条目的所属字段中解密，被交换或移植的密文将解密失败，
+/// 标签名（`Tag`）及分组名（`Group`）不属于单个条目，其关联数据中为标签或分组的 id，
+/// 自定义字段的关联数据另含其在条目中的位置，秘密与否的值标签不同，遂被调换位置或秘密标记的密文亦解密失败
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+enum EntryField {
+    About = b'a',
+    Notes = b'n',
+    Username = b'k',
+    Password = b'v',
+    Tag = b't',
+    Group = b'g',
+    FieldName = b'f',
+    FieldValue = b'x',
+    SecretFieldValue = b's',
+}
+
+impl EntryField {
+    /// 关联数据：字段标签(1) + 条目id(4, big-endian)
+    fn associated_data(self, e_id: u32) -> [u8; 5] {
+        let mut aad = [0_u8; 5];
+        aad[0] = self as u8;
+        aad[1..].copy_from_slice(&e_id.to_be_bytes());
+        aad
+    }
+
+    /// 自定义字段的关联数据：字段标签(1) + 条目id(4, big-endian) + 位置(4, big-endian)
+    fn indexed_associated_data(self, e_id: u32, index: u32) -> [u8; 9] {
+        let mut aad = [0_u8; 9];
+        aad[..5].copy_from_slice(&self.associated_data(e_id));
+        aad[5..].copy_from_slice(&index.to_be_bytes());
+        aad
+    }
+}
+
+/// 存储于条目 f 列的一个自定义字段，n 及 v 为名称及值（可能为密文），s 为是否为秘密字段
+#[derive(Serialize, Deserialize)]
+struct StoredField {
+    n: String,
+    v: String,
+    #[serde(default)]
+    s: bool,
+}
+
+/// 条目的加密模式，反映 data file 状态，存储于 cfg 表
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct EntryEncryptMode {
+    /// 全加密模式，about 及 notes 也加密
+    pub full_encrypt: bool,
+    /// 密文以条目 id 及字段标签为关联数据，早于该特性的 data file 为 false，解锁后即升级
+    pub entry_bound: bool,
+    /// 新写入的密文使用的算法，已有密文的算法由其信封描述
+    pub cipher: CipherAlg,
+}
+
+/// 一把密钥下的各算法实现
+#[derive(Clone)]
+struct FieldCipher {
+    aes: StrAes256GcmEncrypter,
+    xchacha: StrXChaCha20Poly1305Encrypter,
+}
+
+impl FieldCipher {
+    fn from_key(key: &[u8; 32]) -> Result<Self> {
+        Ok(Self {
+            aes: StrAes256GcmEncrypter::from_key(key)?,
+            xchacha: StrXChaCha20Poly1305Encrypter::from_key(key),
+        })
+    }
+    #[cfg(test)]
+    fn from_random_key() -> Self {
+        Self {
+            aes: StrAes256GcmEncrypter::from_random_key(),
+            xchacha: StrXChaCha20Poly1305Encrypter::from_random_key(),
+        }
+    }
+
+    fn encrypt(&self, cipher: CipherAlg, plaintext: &str, aad: &[u8]) -> Result<String, CryptoError> {
+        match cipher {
+            CipherAlg::Aes256Gcm => self.aes.encrypt_envelope(plaintext, aad),
+            CipherAlg::XChaCha20Poly1305 => self.xchacha.encrypt_envelope(plaintext, aad),
+        }
+    }
+
+    fn decrypt(&self, ciphertext: &str, aad: &[u8]) -> Result<String, CryptoError> {
+        match Envelope::parse(ciphertext)? {
+            // 早于信封的密文均为 AES-256-GCM
+            None => self.aes.decrypt_legacy(ciphertext, aad),
+            Some(envelope) => match envelope.alg {
+                CipherAlg::Aes256Gcm => self.aes.decrypt_envelope(&envelope, aad),
+                CipherAlg::XChaCha20Poly1305 => self.xchacha.decrypt_envelope(&envelope, aad),
+            },
+        }
+    }
+}
+
+/// Entry 的 部分秘密字段的加密解密器
+///
+/// username 及 password 总是加密，
+/// 全加密模式（full_encrypt）下 about 及 notes 也加密，
+/// 二者使用不同的子密钥（见 [`super::subkey::KeyPurpose`]），
+/// 加密使用模式中的算法，解密按密文信封描述的算法进行，无信封的旧密文按 AES-256-GCM 解密
+pub struct EntrySecretEncrypter {
+    /// 加密 username 及 password
+    secret: FieldCipher,
+    /// 加密 about 及 notes
+    notes: FieldCipher,
+    mode: EntryEncryptMode,
+}
+impl EntrySecretEncrypter {
+    /// 由 username/password 及 about/notes 各自的密钥构建，
+    /// 早于子密钥的 data file 二者为同一密钥
+    pub fn from_keys(
+        secret_key: &[u8; 32], notes_key: &[u8; 32], mode: EntryEncryptMode,
+    ) -> Result<EntrySecretEncrypter> {
+        Ok(Self {
+            secret: FieldCipher::from_key(secret_key)?,
+            notes: FieldCipher::from_key(notes_key)?,
+            mode,
+        })
+    }
+    #[cfg(test)]
+    fn from_random_key(mode: EntryEncryptMode) -> EntrySecretEncrypter {
+        Self {
+            secret: FieldCipher::from_random_key(),
+            notes: FieldCipher::from_random_key(),
+            mode,
+        }
+    }
+
+    /// 使用相同密钥，构建指定模式的加密解密器，
+    /// 用于切换 data file 的加密模式（旧模式解密，新模式加密）
+    pub fn with_mode(&self, mode: EntryEncryptMode) -> EntrySecretEncrypter {
+        Self {
+            secret: self.secret.clone(),
+            notes: self.notes.clone(),
+            mode,
+        }
+    }
+
+    /// 当前加密模式
+    pub fn mode(&self) -> EntryEncryptMode {
+        self.mode
+    }
+
+    /// 是否为全加密模式
+    pub fn is_full_encrypt(&self) -> bool {
+        self.mode.full_encrypt
+    }
+
+    /// 密文是否绑定条目（以条目 id 及字段标签为关联数据）
+    pub fn is_entry_bound(&self) -> bool {
+        self.mode.entry_bound
+    }
+
+    /// 字段所用密钥的实现
+    fn field_cipher(&self, field: EntryField) -> &FieldCipher {
+        match field {
+            EntryField::Username | EntryField::Password | EntryField::SecretFieldValue => &self.secret,
+            EntryField::About
+            | EntryField::Notes
+            | EntryField::Tag
+            | EntryField::Group
+            | EntryField::FieldName
+            | EntryField::FieldValue => &self.notes,
+        }
+    }
+
+    /// 加密条目的一个字段，密文绑定条目时以条目 id 及字段标签为关联数据
+    fn encrypt_field(&self, field: EntryField, e_id: u32, plaintext: &str) -> Result<String, CryptoError> {
+        self.encrypt_with_aad(field, &field.associated_data(e_id), plaintext)
+    }
+
+    /// 解密条目的一个字段，密文绑定条目时，被交换或移植的密文将解密失败
+    fn decrypt_field(&self, field: EntryField, e_id: u32, ciphertext: &str) -> Result<String, CryptoError> {
+        self.decrypt_with_aad(field, &field.associated_data(e_id), ciphertext)
+    }
+
+    /// 以给定的关联数据加密，密文不绑定条目时（早于该特性的 data file）不使用关联数据
+    fn encrypt_with_aad(&self, field: EntryField, aad: &[u8], plaintext: &str) -> Result<String, CryptoError> {
+        let aad: &[u8] = if self.mode.entry_bound { aad } else { &[] };
+        self.field_cipher(field).encrypt(self.mode.cipher, plaintext, aad)
+    }
+
+    /// 以给定的关联数据解密
+    fn decrypt_with_aad(&self, field: EntryField, aad: &[u8], ciphertext: &str) -> Result<String, CryptoError> {
+        let aad: &[u8] = if self.mode.entry_bound { aad } else { &[] };
+        self.field_cipher(field).decrypt(ciphertext, aad)
+    }
+
+    /// 加密条目的自定义字段，秘密字段的值总是加密，名称及其余的值仅全加密模式下加密，
+    /// 密文绑定条目及字段的位置，没有自定义字段即 None
+    fn encrypt_custom_fields(&self, e_id: u32, fields: &[CustomField]) -> Result<Option<String>, CryptoError> {
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        let mut stored = Vec::with_capacity(fields.len());
+        for (index, field) in (0_u32..).zip(fields) {
+            let name_aad = EntryField::FieldName.indexed_associated_data(e_id, index);
+            let value_label = if field.secret { EntryField::SecretFieldValue } else { EntryField::FieldValue };
+            let value_aad = value_label.indexed_associated_data(e_id, index);
+            let full_encrypt = self.mode.full_encrypt;
+            stored.push(StoredField {
+                n: if full_encrypt {
+                    self.encrypt_with_aad(EntryField::FieldName, &name_aad, &field.name)?
+                } else {
+                    field.name.clone()
+                },
+                v: if full_encrypt || field.secret {
+                    self.encrypt_with_aad(value_label, &value_aad, &field.value)?
+                } else {
+                    field.value.clone()
+                },
+                s: field.secret,
+            });
+        }
+        serde_json::to_string(&stored)
+            .map(Some)
+            .map_err(|_| CryptoError::DecodeCustomFields)
+    }
+
+    /// 解密条目的自定义字段
+    fn decrypt_custom_fields(&self, e_id: u32, stored: Option<&str>) -> Result<Vec<CustomField>, CryptoError> {
+        let Some(stored) = stored else {
+            return Ok(Vec::new());
+        };
+        let stored: Vec<StoredField> = serde_json::from_str(stored).map_err(|_| CryptoError::DecodeCustomFields)?;
+        let mut fields = Vec::with_capacity(stored.len());
+        for (index, field) in (0_u32..).zip(stored) {
+            let name_aad = EntryField::FieldName.indexed_associated_data(e_id, index);
+            let value_label = if field.s { EntryField::SecretFieldValue } else { EntryField::FieldValue };
+            let value_aad = value_label.indexed_associated_data(e_id, index);
+            let full_encrypt = self.mode.full_encrypt;
+            fields.push(CustomField {
+                name: if full_encrypt {
+                    self.decrypt_with_aad(EntryField::FieldName, &name_aad, &field.n)?
+                } else {
+                    field.n
+                },
+                value: if full_encrypt || field.s {
+                    self.decrypt_with_aad(value_label, &value_aad, &field.v)?
+                } else {
+                    field.v
+                },
+                secret: field.s,
+            });
+        }
+        Ok(fields)
+    }
+
+    /// 加密 about 或 notes，非全加密模式下原样返回
+    fn encrypt_plain_field(&self, field: EntryField, e_id: u32, plaintext: &str) -> Result<String, CryptoError> {
+        if self.mode.full_encrypt {
+            self.encrypt_field(field, e_id, plaintext)
+        } else {
+            Ok(plaintext.to_owned())
+        }
+    }
+
+    /// 解密 about 或 notes，非全加密模式下原样返回
+    fn decrypt_plain_field(&self, field: EntryField, e_id: u32, ciphertext: &str) -> Result<String, CryptoError> {
+        if self.mode.full_encrypt {
+            self.decrypt_field(field, e_id, ciphertext)
+        } else {
+            Ok(ciphertext.to_owned())
+        }
+    }
+
+    /// 解密条目的 about 及 notes，返回的条目中 about 及 notes 为明文，
+    /// username 及 password 仍为密文，用于列表显示及查找
+    pub fn decrypt_about_notes(&self, encrypted_entry: &EncryptedEntry) -> Result<EncryptedEntry, CryptoError> {
+        let e_id = encrypted_entry.id;
+        Ok(EncryptedEntry {
+            about: self.decrypt_plain_field(EntryField::About, e_id, &encrypted_entry.about)?,
+            notes: encrypted_entry
+                .notes
+                .as_deref()
+                .map(|n| self.decrypt_plain_field(EntryField::Notes, e_id, n))
+                .transpose()?,
+            ..encrypted_entry.clone()
+        })
+    }
+
+    /// 加密标签名，非全加密模式下原样返回，
+    /// 与 about 及 notes 相同，密文绑定标签 id，遂新建的标签应先分配 id（见 `Storage::next_tag_id`）
+    pub fn encrypt_tag(&self, t_id: u32, name: &str) -> Result<Tag, CryptoError> {
+        Ok(Tag {
+            id: t_id,
+            name: self.encrypt_plain_field(EntryField::Tag, t_id, name)?,
+        })
+    }
+
+    /// 解密标签名，非全加密模式下原样返回
+    pub fn decrypt_tag(&self, tag: &Tag) -> Result<String, CryptoError> {
+        self.decrypt_plain_field(EntryField::Tag, tag.id, &tag.name)
+    }
+
+    /// 加密分组名，非全加密模式下原样返回，同标签名，密文绑定分组 id（见 `Storage::next_group_id`）
+    pub fn encrypt_group(&self, g_id: u32, parent: Option<u32>, name: &str) -> Result<Group, CryptoError> {
+        Ok(Group {
+            id: g_id,
+            parent,
+            name: self.encrypt_plain_field(EntryField::Group, g_id, name)?,
+        })
+    }
+
+    /// 解密分组名，非全加密模式下原样返回
+    pub fn decrypt_group(&self, group: &Group) -> Result<String, CryptoError> {
+        self.decrypt_plain_field(EntryField::Group, group.id, &group.name)
+    }
+}
+
+/// 加密给定 id 的条目，密文绑定该 id，遂新建的条目应先分配 id（见 `Storage::next_entry_id`）
+impl Encrypter<(u32, &InputEntry), ValidEntry> for EntrySecretEncrypter {
+    type EncrypterError = CryptoError;
+    fn encrypt(&self, (e_id, input_entry): (u32, &InputEntry)) -> Result<ValidEntry, Self::EncrypterError> {
+        // 加密敏感字段
+        let cipher_username = self.encrypt_field(EntryField::Username, e_id, &input_entry.username)?;
+        let cipher_passwd = self.encrypt_field(EntryField::Password, e_id, &input_entry.password)?;
+        Ok(ValidEntry {
+            id: e_id,
+            about: self.encrypt_plain_field(EntryField::About, e_id, &input_entry.about)?,
+            notes: if input_entry.notes.is_empty() {
+                None
+            } else {
+                Some(self.encrypt_plain_field(EntryField::Notes, e_id, &input_entry.notes)?)
+            },
+            encrypted_username: cipher_username,
+            encrypted_password: cipher_passwd,
+            fields: self.encrypt_custom_fields(e_id, &input_entry.fields)?,
+        })
+    }
+}
+impl Decrypter<&EncryptedEntry, InputEntry> for EntrySecretEncrypter {
+    type DecrypterError = CryptoError;
+    fn decrypt(&self, encrypted_entry: &EncryptedEntry) -> Result<InputEntry, Self::DecrypterError> {
+        let e_id = encrypted_entry.id;
+        let username = self.decrypt_field(EntryField::Username, e_id, &encrypted_entry.encrypted_username)?;
+        let password = self.decrypt_field(EntryField::Password, e_id, &encrypted_entry.encrypted_password)?;
+        Ok(InputEntry {
+            about: self.decrypt_plain_field(EntryField::About, e_id, &encrypted_entry.about)?,
+            notes: if let Some(desc) = &encrypted_entry.notes {
+                self.decrypt_plain_field(EntryField::Notes, e_id, desc)?
+            } else {
+                String::new()
+            },
+            username,
+            password,
+            // 标签存储于 tag 表，EncryptedEntry 中的已为明文
+            tags: encrypted_entry.tags.clone(),
+            group: encrypted_entry.group.clone().unwrap_or_default(),
+            fields: self.decrypt_custom_fields(e_id, encrypted_entry.fields.as_deref())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::DateTime;
+
+    const PLAIN_MODE: EntryEncryptMode = EntryEncryptMode {
+        full_encrypt: false,
+        entry_bound: true,
+        cipher: CipherAlg::Aes256Gcm,
+    };
+    const FULL_MODE: EntryEncryptMode = EntryEncryptMode {
+        full_encrypt: true,
+        ..PLAIN_MODE
+    };
+
+    fn to_encrypted_entry(v_e: ValidEntry) -> EncryptedEntry {
+        EncryptedEntry {
+            id: v_e.id,
+            about: v_e.about,
+            notes: v_e.notes,
+            encrypted_username: v_e.encrypted_username,
+            encrypted_password: v_e.encrypted_password,
+            created_time: DateTime::default(),
+            updated_time: DateTime::default(),
+            tags: Vec::new(),
+            group: None,
+            fields: v_e.fields,
+        }
+    }
+
+    fn input_entry() -> InputEntry {
+        InputEntry {
+            about: "github".to_owned(),
+            notes: "2fa on".to_owned(),
+            username: "def".to_owned(),
+            password: "abc".to_owned(),
+            tags: Vec::new(),
+            group: String::new(),
+            fields: vec![
+                CustomField {
+                    name: "host".to_owned(),
+                    value: "example.com".to_owned(),
+                    secret: false,
+                },
+                CustomField {
+                    name: "recovery codes".to_owned(),
+                    value: "1234 5678".to_owned(),
+                    secret: true,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_entry() {
+        let encrypter = EntrySecretEncrypter::from_random_key(PLAIN_MODE);
+        let u_input = InputEntry {
+            about: "name".to_owned(),
+            notes: String::new(),
+            username: "def".to_owned(),
+            password: "abc".to_owned(),
+            tags: Vec::new(),
+            group: String::new(),
+            fields: Vec::new(),
+        };
+        let v_e = encrypter.encrypt((123, &u_input)).unwrap();
+        let enc_entry = to_encrypted_entry(v_e);
+        let entry = encrypter.decrypt(&enc_entry).unwrap();
+        assert_eq!(u_input.about, entry.about);
+        assert_eq!(u_input.notes, entry.notes);
+        assert_eq!(u_input.password, entry.password);
+        assert_eq!(u_input.username, entry.username);
+    }
+
+    #[test]
+    fn test_full_encrypt_entry() {
+        let encrypter = EntrySecretEncrypter::from_random_key(FULL_MODE);
+        let u_input = input_entry();
+        let v_e = encrypter.encrypt((1, &u_input)).unwrap();
+        assert_ne!(v_e.about, u_input.about);
+        assert_ne!(v_e.notes.as_deref(), Some(u_input.notes.as_str()));
+        let enc_entry = to_encrypted_entry(v_e);
+        let heads = encrypter.decrypt_about_notes(&enc_entry).unwrap();
+        assert_eq!(heads.about, u_input.about);
+        assert_eq!(heads.notes.as_deref(), Some(u_input.notes.as_str()));
+        assert_eq!(heads.encrypted_password, enc_entry.encrypted_password);
+        let entry = encrypter.decrypt(&enc_entry).unwrap();
+        assert_eq!(u_input.about, entry.about);
+        assert_eq!(u_input.notes, entry.notes);
+        // 非全加密模式不可解读全加密模式的 about
+        let plain_mode = encrypter.with_mode(PLAIN_MODE);
+        assert_ne!(plain_mode.decrypt(&enc_entry).unwrap().about, u_input.about);
+        // 标签名
+        let tag = encrypter.encrypt_tag(2, "work").unwrap();
+        assert_ne!(tag.name, "work");
+        assert_eq!(encrypter.decrypt_tag(&tag).unwrap(), "work");
+        assert!(encrypter.decrypt_tag(&Tag { id: 3, ..tag }).is_err());
+        assert_eq!(plain_mode.encrypt_tag(2, "work").unwrap().name, "work");
+        // 分组名，与同 id 的标签名不可互换
+        let group = encrypter.encrypt_group(2, None, "work").unwrap();
+        assert_eq!(encrypter.decrypt_group(&group).unwrap(), "work");
+        assert!(encrypter.decrypt_tag(&Tag { id: 2, name: group.name }).is_err());
+    }
+
+    #[test]
+    fn test_entry_bound_ciphertext() {
+        let encrypter = EntrySecretEncrypter::from_random_key(FULL_MODE);
+        let u_input = input_entry();
+        let enc_entry = to_encrypted_entry(encrypter.encrypt((7, &u_input)).unwrap());
+        assert!(encrypter.decrypt(&enc_entry).is_ok());
+        // 交换字段
+        let swapped = EncryptedEntry {
+            encrypted_username: enc_entry.encrypted_password.clone(),
+            encrypted_password: enc_entry.encrypted_username.clone(),
+            ..enc_entry.clone()
+        };
+        assert!(encrypter.decrypt(&swapped).is_err());
+        let swapped = EncryptedEntry {
+            about: enc_entry.notes.clone().unwrap(),
+            notes: Some(enc_entry.about.clone()),
+            ..enc_entry.clone()
+        };
+        assert!(encrypter.decrypt_about_notes(&swapped).is_err());
+        // 移植到其他条目
+        let transplanted = EncryptedEntry { id: 8, ..enc_entry.clone() };
+        assert!(encrypter.decrypt(&transplanted).is_err());
+        // 未绑定条目的旧密文：仅未绑定的解密器可解密，升级后的解密器不可解密
+        let legacy = encrypter.with_mode(EntryEncryptMode {
+            entry_bound: false,
+            ..FULL_MODE
+        });
+        let legacy_entry = to_encrypted_entry(legacy.encrypt((7, &u_input)).unwrap());
+        assert_eq!(legacy.decrypt(&legacy_entry).unwrap().password, u_input.password);
+        assert!(encrypter.decrypt(&legacy_entry).is_err());
+    }
+
+    #[test]
+    fn test_custom_fields() {
+        let encrypter = EntrySecretEncrypter::from_random_key(PLAIN_MODE);
+        let u_input = input_entry();
+        let enc_entry = to_encrypted_entry(encrypter.encrypt((5, &u_input)).unwrap());
+        // 非全加密模式下仅秘密字段的值为密文
+        let stored = enc_entry.fields.clone().unwrap();
+        assert!(stored.contains("example.com") && stored.contains("recovery codes"));
+        assert!(!stored.contains("1234 5678"));
+        assert!(encrypter.decrypt(&enc_entry).unwrap().fields == u_input.fields);
+        // 调换字段位置或秘密标记的密文解密失败
+        let mut swapped: Vec<serde_json::Value> = serde_json::from_str(&stored).unwrap();
+        swapped.swap(0, 1);
+        swapped[0]["s"] = false.into();
+        swapped[1]["s"] = true.into();
+        let swapped = EncryptedEntry {
+            fields: Some(serde_json::to_string(&swapped).unwrap()),
+            ..enc_entry.clone()
+        };
+        assert!(encrypter.decrypt(&swapped).is_err());
+        // 全加密模式下名称及值均为密文
+        let encrypter = encrypter.with_mode(FULL_MODE);
+        let enc_entry = to_encrypted_entry(encrypter.encrypt((5, &u_input)).unwrap());
+        let stored = enc_entry.fields.clone().unwrap();
+        assert!(!stored.contains("example.com") && !stored.contains("recovery codes"));
+        assert!(encrypter.decrypt(&enc_entry).unwrap().fields == u_input.fields);
+        let transplanted = EncryptedEntry { id: 6, ..enc_entry };
+        assert!(encrypter.decrypt(&transplanted).is_err());
+    }
+
+    #[test]
+    fn test_cipher_agility() {
+        let aes_mode = EntrySecretEncrypter::from_random_key(FULL_MODE);
+        let xchacha_mode = aes_mode.with_mode(EntryEncryptMode {
+            cipher: CipherAlg::XChaCha20Poly1305,
+            ..FULL_MODE
+        });
+        let u_input = input_entry();
+        let aes_entry = to_encrypted_entry(aes_mode.encrypt((3, &u_input)).unwrap());
+        let xchacha_entry = to_encrypted_entry(xchacha_mode.encrypt((3, &u_input)).unwrap());
+        assert_ne!(
+            Envelope::parse(&aes_entry.encrypted_password).unwrap().unwrap().alg,
+            Envelope::parse(&xchacha_entry.encrypted_password).unwrap().unwrap().alg
+        );
+        // 按信封描述的算法解密，与模式中的算法无关
+        for encrypter in [&aes_mode, &xchacha_mode] {
+            assert_eq!(encrypter.decrypt(&aes_entry).unwrap().password, u_input.password);
+            assert_eq!(encrypter.decrypt(&xchacha_entry).unwrap().password, u_input.password);
+        }
+        // 早于信封的旧密文
+        let aad = EntryField::Password.associated_data(3);
+        let legacy_entry = EncryptedEntry {
+            encrypted_password: aes_mode.secret.aes.encrypt_legacy(&u_input.password, &aad).unwrap(),
+            ..aes_entry
+        };
+        assert_eq!(xchacha_mode.decrypt(&legacy_entry).unwrap().password, u_input.password);
+    }
+}
@@ -0,0 +1,208 @@
+//! 自描述的密文信封
+//!
+//! 形如 `$` + base64(version(1) || alg(1) || nonce_len(1) || nonce || ciphertext)，
+//! 早于信封的密文为无前缀的 base64(nonce(12) || ciphertext)（均为 AES-256-GCM），
+//! 标准 base64 字母表中没有 `$`，遂二者可无歧义地区分
+
+use crate::app::errors::CryptoError;
+use base64ct::{Base64, Encoding};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// 信封前缀
+const ENVELOPE_PREFIX: char = '$';
+/// 当前信封版本，信封格式变化时递增
+const ENVELOPE_VERSION: u8 = 1;
+/// 信封头长度：version(1) + alg(1) + nonce_len(1)
+const HEADER_LEN: usize = 3;
+
+/// 信封中的加密算法
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum CipherAlg {
+    #[default]
+    Aes256Gcm = 1,
+    XChaCha20Poly1305 = 2,
+}
+
+impl CipherAlg {
+    /// 所有算法，用于提示可选值
+    pub const ALL: [CipherAlg; 2] = [CipherAlg::Aes256Gcm, CipherAlg::XChaCha20Poly1305];
+
+    fn from_id(id: u8) -> Result<Self, CryptoError> {
+        Self::ALL
+            .into_iter()
+            .find(|alg| *alg as u8 == id)
+            .ok_or(CryptoError::UnknownCipherAlg)
+    }
+
+    /// 该算法的 nonce 长度
+    pub fn nonce_len(self) -> usize {
+        match self {
+            CipherAlg::Aes256Gcm => 12,
+            CipherAlg::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    /// 算法名，该名即为 cfg 表中的存储形式
+    pub fn name(self) -> &'static str {
+        match self {
+            CipherAlg::Aes256Gcm => "aes-256-gcm",
+            CipherAlg::XChaCha20Poly1305 => "xchacha20-poly1305",
+        }
+    }
+
+    /// 信封头
+    fn header(self) -> [u8; HEADER_LEN] {
+        [ENVELOPE_VERSION, self as u8, self.nonce_len() as u8]
+    }
+
+    /// 认证的关联数据：信封头 + 给定的关联数据，使信封头不可被篡改
+    fn associated_data(self, aad: &[u8]) -> Vec<u8> {
+        let mut vec = Vec::with_capacity(HEADER_LEN + aad.len());
+        vec.extend_from_slice(&self.header());
+        vec.extend_from_slice(aad);
+        vec
+    }
+}
+
+impl Display for CipherAlg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for CipherAlg {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|alg| alg.name() == s)
+            .ok_or(CryptoError::UnknownCipherAlg)
+    }
+}
+
+/// 解析后的信封
+pub(super) struct Envelope {
+    pub alg: CipherAlg,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    /// 解析密文，无信封前缀（早于信封的密文）时返回 Ok(None)
+    pub fn parse(s: &str) -> Result<Option<Envelope>, CryptoError> {
+        let Some(b64) = s.strip_prefix(ENVELOPE_PREFIX) else {
+            return Ok(None);
+        };
+        let bytes = Base64::decode_vec(b64).map_err(|_| CryptoError::DecodeCiphertext)?;
+        if bytes.len() < HEADER_LEN {
+            return Err(CryptoError::CiphertextSplit);
+        }
+        if bytes[0] != ENVELOPE_VERSION {
+            return Err(CryptoError::UnsupportedEnvelopeVersion(bytes[0]));
+        }
+        let alg = CipherAlg::from_id(bytes[1])?;
+        let nonce_len = bytes[2] as usize;
+        if nonce_len != alg.nonce_len() {
+            return Err(CryptoError::InvalidNonceLength);
+        }
+        let body = &bytes[HEADER_LEN..];
+        if body.len() < nonce_len {
+            return Err(CryptoError::CiphertextSplit);
+        }
+        let (nonce, ciphertext) = body.split_at(nonce_len);
+        Ok(Some(Envelope {
+            alg,
+            nonce: nonce.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+        }))
+    }
+
+    /// 编码为信封字符串
+    fn encode(&self) -> String {
+        let mut vec = Vec::with_capacity(HEADER_LEN + self.nonce.len() + self.ciphertext.len());
+        vec.extend_from_slice(&self.alg.header());
+        vec.extend_from_slice(&self.nonce);
+        vec.extend_from_slice(&self.ciphertext);
+        format!("{}{}", ENVELOPE_PREFIX, Base64::encode_string(&vec))
+    }
+}
+
+/// 可封装为信封的 AEAD 算法实现
+pub(super) trait EnvelopeCipher {
+    /// 该实现的算法
+    const ALG: CipherAlg;
+
+    /// 以随机 nonce 加密，返回 (nonce, ciphertext)
+    fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<(Vec<u8>, Vec<u8>), CryptoError>;
+
+    /// 以给定 nonce 解密
+    fn open(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError>;
+
+    /// 加密为信封字符串，并以给定关联数据认证，
+    /// 解密时须给定相同的关联数据
+    fn encrypt_envelope(&self, plaintext: &str, aad: &[u8]) -> Result<String, CryptoError> {
+        self.encrypt_envelope_bytes(plaintext.as_bytes(), aad)
+    }
+
+    /// 同 [`EnvelopeCipher::encrypt_envelope`]，明文为字节
+    fn encrypt_envelope_bytes(&self, plaintext: &[u8], aad: &[u8]) -> Result<String, CryptoError> {
+        let (nonce, ciphertext) = self.seal(plaintext, &Self::ALG.associated_data(aad))?;
+        Ok(Envelope {
+            alg: Self::ALG,
+            nonce,
+            ciphertext,
+        }
+        .encode())
+    }
+
+    /// 解密信封，信封算法非该实现的算法或关联数据与加密时不同则 Err
+    fn decrypt_envelope(&self, envelope: &Envelope, aad: &[u8]) -> Result<String, CryptoError> {
+        let plaintext = self.decrypt_envelope_bytes(envelope, aad)?;
+        String::from_utf8(plaintext).map_err(|_| CryptoError::DecodeCiphertext)
+    }
+
+    /// 同 [`EnvelopeCipher::decrypt_envelope`]，明文为字节
+    fn decrypt_envelope_bytes(&self, envelope: &Envelope, aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if envelope.alg != Self::ALG {
+            return Err(CryptoError::CipherAlgMismatch);
+        }
+        self.open(
+            &envelope.nonce,
+            &envelope.ciphertext,
+            &Self::ALG.associated_data(aad),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_parse() {
+        assert!(Envelope::parse("bm90IGFuIGVudmVsb3Bl").unwrap().is_none());
+        let envelope = Envelope {
+            alg: CipherAlg::XChaCha20Poly1305,
+            nonce: vec![7; 24],
+            ciphertext: vec![1, 2, 3],
+        };
+        let parsed = Envelope::parse(&envelope.encode()).unwrap().unwrap();
+        assert_eq!(parsed.alg, envelope.alg);
+        assert_eq!(parsed.nonce, envelope.nonce);
+        assert_eq!(parsed.ciphertext, envelope.ciphertext);
+        // 未知版本
+        let mut bytes = envelope.alg.header().to_vec();
+        bytes[0] = ENVELOPE_VERSION + 1;
+        let s = format!("{}{}", ENVELOPE_PREFIX, Base64::encode_string(&bytes));
+        assert!(matches!(
+            Envelope::parse(&s),
+            Err(CryptoError::UnsupportedEnvelopeVersion(_))
+        ));
+        for alg in CipherAlg::ALL {
+            assert_eq!(alg.name().parse::<CipherAlg>().unwrap(), alg);
+        }
+    }
+}
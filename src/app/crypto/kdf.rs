@@ -0,0 +1,119 @@
+//! 主密码 KDF（密钥派生）算法及参数
+//!
+//! 参数随 data file 存储于 cfg 表，早于该配置的 data file 使用 Argon2 默认参数
+
+use crate::app::errors::{AppError, CryptoError};
+use argon2::{Algorithm, Argon2, Params, Version};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Argon2 算法及参数
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct KdfParams {
+    pub algorithm: Algorithm,
+    /// 内存开销，单位 KiB
+    pub m_cost: u32,
+    /// 迭代次数
+    pub t_cost: u32,
+    /// 并行度
+    pub p_cost: u32,
+}
+
+/// 与 `Argon2::default()` 一致
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::default(),
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    /// 以该参数构建 Argon2 实例，参数超出 Argon2 允许范围则 Err
+    pub fn argon2(&self) -> Result<Argon2<'static>, CryptoError> {
+        Ok(Argon2::new(self.algorithm, Version::default(), self.params()?))
+    }
+
+    /// 以该参数及给定 secret（密钥文件摘要）构建 Argon2 实例，
+    /// 相同的密码及盐，secret 不同则派生的密钥不同
+    pub fn argon2_with_secret<'k>(&self, secret: &'k [u8]) -> Result<Argon2<'k>, CryptoError> {
+        Argon2::new_with_secret(secret, self.algorithm, Version::default(), self.params()?)
+            .map_err(CryptoError::InvalidKdfParams)
+    }
+
+    fn params(&self) -> Result<Params, CryptoError> {
+        Params::new(self.m_cost, self.t_cost, self.p_cost, None).map_err(CryptoError::InvalidKdfParams)
+    }
+
+    /// 校验参数是否在 Argon2 允许范围内
+    pub fn validate(&self) -> Result<(), CryptoError> {
+        self.argon2().map(|_| ())
+    }
+}
+
+/// 形如 `argon2id$m=19456,t=2,p=1`，该形式即为 cfg 表中的存储形式
+impl Display for KdfParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}$m={},t={},p={}",
+            self.algorithm, self.m_cost, self.t_cost, self.p_cost
+        )
+    }
+}
+
+/// 仅用于解析 cfg 表中存储的值，解析失败即说明数据已被破坏
+impl FromStr for KdfParams {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (alg, params) = s.split_once('$').ok_or(AppError::DataCorrupted)?;
+        let algorithm = Algorithm::new(alg).map_err(|_| AppError::DataCorrupted)?;
+        let mut costs = [None; 3];
+        for kv in params.split(',') {
+            let (k, v) = kv.split_once('=').ok_or(AppError::DataCorrupted)?;
+            let idx = match k {
+                "m" => 0,
+                "t" => 1,
+                "p" => 2,
+                _ => Err(AppError::DataCorrupted)?,
+            };
+            costs[idx] = Some(v.parse::<u32>().map_err(|_| AppError::DataCorrupted)?);
+        }
+        let [Some(m_cost), Some(t_cost), Some(p_cost)] = costs else {
+            Err(AppError::DataCorrupted)?
+        };
+        Ok(Self {
+            algorithm,
+            m_cost,
+            t_cost,
+            p_cost,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kdf_params_str() {
+        let default = KdfParams::default();
+        assert_eq!(default.to_string(), "argon2id$m=19456,t=2,p=1");
+        assert_eq!(default.to_string().parse::<KdfParams>().unwrap(), default);
+        let custom = KdfParams {
+            algorithm: Algorithm::Argon2i,
+            m_cost: 65536,
+            t_cost: 3,
+            p_cost: 4,
+        };
+        assert_eq!(custom.to_string().parse::<KdfParams>().unwrap(), custom);
+        assert!("argon2id$m=1,t=2".parse::<KdfParams>().is_err());
+        assert!("scrypt$m=1,t=2,p=1".parse::<KdfParams>().is_err());
+        // 超出 argon2 允许范围
+        assert!(KdfParams { m_cost: 1, ..default }.validate().is_err());
+    }
+}
@@ -0,0 +1,46 @@
+//! 密钥文件，作为主密码之外的第二解锁因素
+//!
+//! 文件内容的 SHA-256 摘要作为 Argon2 的 secret 参与主密钥派生，
+//! 遂要求密钥文件的 data file 缺少该文件时无法解锁
+
+use crate::app::errors::AppError;
+use crate::app::secret::{REDACTED, SecretKey};
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// 密钥文件内容的摘要
+#[derive(Clone)]
+pub struct Keyfile(SecretKey);
+
+impl Keyfile {
+    /// 读取密钥文件，文件不可读或为空则 Err
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let contents = Zeroizing::new(
+            std::fs::read(path)
+                .with_context(|| format!("failed to read keyfile '{}'", path.display()))?,
+        );
+        if contents.is_empty() {
+            Err(AppError::EmptyKeyfile)?
+        }
+        Ok(Self::from_contents(&contents))
+    }
+
+    /// 由密钥文件内容构建
+    pub fn from_contents(contents: &[u8]) -> Self {
+        Self(Zeroizing::new(Sha256::digest(contents).into()))
+    }
+
+    /// 作为 Argon2 secret 的字节
+    pub(super) fn secret(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl Debug for Keyfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Keyfile").field(&REDACTED).finish()
+    }
+}
@@ -0,0 +1,299 @@
+//! 主密钥、数据密钥、恢复密钥及其派生的各用途子密钥
+//!
+//! 主密码经一次 KDF 派生主密钥，主密钥不直接用于加密，
+//! 而是以 HKDF-SHA256 按用途派生相互独立的子密钥，
+//! 主密码校验值亦由子密钥计算，遂解锁只需一次 KDF
+//!
+//! 条目加密子密钥由随机生成的数据密钥派生，数据密钥由主密钥（及恢复密钥）派生的包裹子密钥包裹存储，
+//! 遂修改主密码或以恢复密钥重设主密码时仅需重新包裹数据密钥，无需重新加密条目
+
+use crate::app::crypto::aes_gcm::StrAes256GcmEncrypter;
+use crate::app::crypto::entry_secret::{EntryEncryptMode, EntrySecretEncrypter};
+use crate::app::crypto::envelope::{Envelope, EnvelopeCipher};
+use crate::app::crypto::kdf::KdfParams;
+use crate::app::crypto::keyfile::Keyfile;
+use crate::app::errors::{AppError, CryptoError};
+use crate::app::secret::{REDACTED, SecretKey};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// 子密钥的用途，不同用途以不同的 HKDF info 派生，
+/// 新的加密特性应新增用途，而非复用已有用途的子密钥
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum KeyPurpose {
+    /// 计算主密码校验值，由主密钥派生
+    Verify,
+    /// 包裹数据密钥，由主密钥派生
+    Wrap,
+    /// 包裹数据密钥，由恢复密钥派生
+    RecoveryWrap,
+    /// 加密条目的 username 及 password，由数据密钥派生
+    Entry,
+    /// 全加密模式下加密条目的 about 及 notes，由数据密钥派生
+    Notes,
+}
+
+impl KeyPurpose {
+    /// HKDF info，一经发布不可修改，否则已有 data file 将无法解密
+    fn info(self) -> &'static [u8] {
+        match self {
+            KeyPurpose::Verify => b"pnt/v1/verify",
+            KeyPurpose::Wrap => b"pnt/v1/wrap",
+            KeyPurpose::RecoveryWrap => b"pnt/v1/recovery-wrap",
+            KeyPurpose::Entry => b"pnt/v1/entry",
+            KeyPurpose::Notes => b"pnt/v1/notes",
+        }
+    }
+}
+
+/// 以 HKDF-SHA256 由给定密钥派生给定用途的子密钥
+fn derive_subkey(ikm: &[u8; 32], purpose: KeyPurpose) -> Result<SecretKey, CryptoError> {
+    // 各密钥已是 KDF 的输出或随机值，均匀分布，无需 HKDF salt
+    let mut okm = Zeroizing::new([0_u8; 32]);
+    Hkdf::<Sha256>::new(None, ikm)
+        .expand(purpose.info(), okm.as_mut())
+        .map_err(|_| CryptoError::GenerateKey)?;
+    Ok(okm)
+}
+
+/// 32 字节随机值
+fn random_key() -> [u8; 32] {
+    let mut key = [0_u8; 32];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+/// 为持有 32 字节密钥的类型实现 drop 时清零及不输出内容的 Debug
+macro_rules! impl_secret_key {
+    ($name:ident) => {
+        impl Drop for $name {
+            fn drop(&mut self) {
+                self.0.zeroize();
+            }
+        }
+        impl ZeroizeOnDrop for $name {}
+
+        impl Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&REDACTED).finish()
+            }
+        }
+    };
+}
+
+/// 主密码校验值所认证的消息
+const VERIFY_MESSAGE: &[u8] = b"pnt main password verifier";
+
+/// 主密钥，由主密码以 data file 的 KDF 参数派生，drop 时清零
+pub struct MasterKey([u8; 32]);
+impl_secret_key!(MasterKey);
+
+impl MasterKey {
+    /// 以 KDF 派生主密钥，给定密钥文件时其摘要作为 Argon2 secret 参与派生，
+    /// 无密钥文件时该值与早于子密钥的 data file 的条目密钥相同
+    pub fn derive(
+        passwd: &str, salt: &[u8], kdf: &KdfParams, keyfile: Option<&Keyfile>,
+    ) -> Result<Self, CryptoError> {
+        let argon2 = match keyfile {
+            Some(keyfile) => kdf.argon2_with_secret(keyfile.secret())?,
+            None => kdf.argon2()?,
+        };
+        let mut key = Self([0_u8; 32]);
+        argon2
+            .hash_password_into(passwd.as_bytes(), salt, &mut key.0)
+            .map_err(|_| CryptoError::GenerateKey)?; // 安全相关 用map_err 缩减 暴露的err信息
+        Ok(key)
+    }
+
+    /// 派生给定用途的子密钥
+    pub fn subkey(&self, purpose: KeyPurpose) -> Result<SecretKey, CryptoError> {
+        derive_subkey(&self.0, purpose)
+    }
+
+    /// 早于子密钥的 data file 中，条目直接以主密钥（KDF 输出）加密
+    pub fn legacy_entry_key(&self) -> SecretKey {
+        Zeroizing::new(self.0)
+    }
+
+    /// 主密码校验值：以校验子密钥对固定消息计算的 HMAC-SHA256
+    pub fn verifier_mac(&self) -> Result<[u8; 32], CryptoError> {
+        Ok(self.verify_mac_state()?.finalize().into_bytes().into())
+    }
+
+    /// 以常数时间比较给定的主密码校验值
+    pub fn verify_mac(&self, mac: &[u8]) -> Result<bool, CryptoError> {
+        Ok(self.verify_mac_state()?.verify_slice(mac).is_ok())
+    }
+
+    fn verify_mac_state(&self) -> Result<Hmac<Sha256>, CryptoError> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.subkey(KeyPurpose::Verify)?.as_ref())
+            .map_err(|_| CryptoError::GenerateKey)?;
+        mac.update(VERIFY_MESSAGE);
+        Ok(mac)
+    }
+}
+
+/// 包裹数据密钥时认证的关联数据
+const WRAP_AAD: &[u8] = b"pnt data key";
+
+/// 数据密钥，条目加密子密钥由其派生，drop 时清零
+#[derive(Clone)]
+pub struct DataKey([u8; 32]);
+impl_secret_key!(DataKey);
+
+impl DataKey {
+    /// 随机生成数据密钥，用于新建的 data file
+    pub fn generate() -> Self {
+        Self(random_key())
+    }
+
+    /// 早于数据密钥的 data file 的条目子密钥由主密钥派生，
+    /// 升级时即以主密钥的值作为数据密钥，遂无需重新加密条目
+    pub fn from_master(master: &MasterKey) -> Self {
+        Self(master.0)
+    }
+
+    /// 派生给定用途的子密钥
+    pub fn subkey(&self, purpose: KeyPurpose) -> Result<SecretKey, CryptoError> {
+        derive_subkey(&self.0, purpose)
+    }
+
+    /// 以数据密钥派生的子密钥构建条目加密解密器
+    pub fn entry_encrypter(&self, mode: EntryEncryptMode) -> anyhow::Result<EntrySecretEncrypter> {
+        let entry_key = self.subkey(KeyPurpose::Entry)?;
+        let notes_key = self.subkey(KeyPurpose::Notes)?;
+        EntrySecretEncrypter::from_keys(&entry_key, &notes_key, mode)
+    }
+
+    /// 以给定的包裹子密钥包裹，返回信封形式的密文
+    pub fn wrap(&self, kek: &[u8; 32]) -> anyhow::Result<String> {
+        Ok(StrAes256GcmEncrypter::from_key(kek)?.encrypt_envelope_bytes(&self.0, WRAP_AAD)?)
+    }
+
+    /// 以给定的包裹子密钥解包，包裹子密钥不符或密文被篡改则 Err
+    pub fn unwrap(kek: &[u8; 32], wrapped: &str) -> anyhow::Result<Self> {
+        let envelope = Envelope::parse(wrapped)?.ok_or(CryptoError::DecodeCiphertext)?;
+        let bytes = Zeroizing::new(
+            StrAes256GcmEncrypter::from_key(kek)?.decrypt_envelope_bytes(&envelope, WRAP_AAD)?,
+        );
+        let mut key = Self([0_u8; 32]);
+        if bytes.len() != key.0.len() {
+            Err(AppError::DataCorrupted)?
+        }
+        key.0.copy_from_slice(&bytes);
+        Ok(key)
+    }
+}
+
+/// 恢复密钥，随机生成，仅显示一次，可在遗忘主密码时解包数据密钥以重设主密码，drop 时清零
+pub struct RecoveryKey([u8; 32]);
+impl_secret_key!(RecoveryKey);
+
+impl RecoveryKey {
+    /// 显示时每组的字节数
+    const GROUP_BYTES: usize = 4;
+
+    pub fn generate() -> Self {
+        Self(random_key())
+    }
+
+    /// 派生包裹数据密钥的子密钥
+    pub fn wrap_subkey(&self) -> Result<SecretKey, CryptoError> {
+        derive_subkey(&self.0, KeyPurpose::RecoveryWrap)
+    }
+}
+
+/// 形如 `0a1b2c3d-...`，共 8 组，每组 8 个十六进制字符
+impl Display for RecoveryKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, group) in self.0.chunks(Self::GROUP_BYTES).enumerate() {
+            if i > 0 {
+                f.write_str("-")?;
+            }
+            for b in group {
+                write!(f, "{b:02x}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 解析用户输入的恢复密钥，忽略分隔符、空白及大小写
+impl FromStr for RecoveryKey {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = Zeroizing::new(
+            s.chars()
+                .filter(|c| *c != '-' && !c.is_whitespace())
+                .collect::<String>(),
+        );
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(AppError::InvalidRecoveryKey);
+        }
+        let mut key = Self([0_u8; 32]);
+        for (i, b) in key.0.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| AppError::InvalidRecoveryKey)?;
+        }
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subkey_separation() {
+        let kdf = KdfParams {
+            m_cost: 8192,
+            t_cost: 1,
+            ..KdfParams::default()
+        };
+        let master = MasterKey::derive("foobar", &[1; 32], &kdf, None).unwrap();
+        let entry = master.subkey(KeyPurpose::Entry).unwrap();
+        let notes = master.subkey(KeyPurpose::Notes).unwrap();
+        let verify = master.subkey(KeyPurpose::Verify).unwrap();
+        assert_ne!(entry, notes);
+        assert_ne!(entry, verify);
+        assert_ne!(entry, master.legacy_entry_key());
+        // 相同输入派生相同子密钥
+        assert_eq!(entry, master.subkey(KeyPurpose::Entry).unwrap());
+        // 由主密钥升级的数据密钥，派生的条目子密钥不变
+        assert_eq!(entry, DataKey::from_master(&master).subkey(KeyPurpose::Entry).unwrap());
+
+        let mac = master.verifier_mac().unwrap();
+        assert!(master.verify_mac(&mac).unwrap());
+        let other = MasterKey::derive("barfoo", &[1; 32], &kdf, None).unwrap();
+        assert!(!other.verify_mac(&mac).unwrap());
+        // 相同密码，密钥文件不同或缺少之，主密钥均不同
+        let keyfile = Keyfile::from_contents(b"keyfile");
+        let with_keyfile = MasterKey::derive("foobar", &[1; 32], &kdf, Some(&keyfile)).unwrap();
+        assert!(!with_keyfile.verify_mac(&mac).unwrap());
+        let other_keyfile = Keyfile::from_contents(b"other keyfile");
+        let with_other = MasterKey::derive("foobar", &[1; 32], &kdf, Some(&other_keyfile)).unwrap();
+        assert!(!with_other.verify_mac(&with_keyfile.verifier_mac().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_wrap_data_key_with_recovery_key() {
+        let data_key = DataKey::generate();
+        let recovery = RecoveryKey::generate();
+        let wrapped = data_key.wrap(&recovery.wrap_subkey().unwrap()).unwrap();
+        // 用户输入可为大写或含空白
+        let input = format!(" {} ", recovery.to_string().to_uppercase());
+        let parsed: RecoveryKey = input.parse().unwrap();
+        let unwrapped = DataKey::unwrap(&parsed.wrap_subkey().unwrap(), &wrapped).unwrap();
+        assert_eq!(unwrapped.0, data_key.0);
+        // 其他恢复密钥不可解包
+        let other = RecoveryKey::generate();
+        assert!(DataKey::unwrap(&other.wrap_subkey().unwrap(), &wrapped).is_err());
+        assert!("0123-abcd".parse::<RecoveryKey>().is_err());
+        assert!(!format!("{recovery:?}").contains(&recovery.to_string()[..8]));
+    }
+}
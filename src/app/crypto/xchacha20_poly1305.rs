@@ -0,0 +1,85 @@
+//! 使用 chacha20poly1305 库的 XChaCha20-Poly1305 进行加密和解密
+//!
+//! 其 nonce 为 24 字节，随机生成 nonce 时碰撞概率可忽略
+
+use crate::app::crypto::envelope::{CipherAlg, Envelope, EnvelopeCipher};
+use crate::app::crypto::{Decrypter, Encrypter};
+use crate::app::errors::CryptoError;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+/// 使用 XChaCha20-Poly1305 实现对 string 的加密解密，
+/// 同明文在不同次加密时会被加密为不同密文，
+/// 输出总是信封形式（见 [`super::envelope`]）
+#[derive(Clone)]
+pub(super) struct StrXChaCha20Poly1305Encrypter(XChaCha20Poly1305);
+
+impl StrXChaCha20Poly1305Encrypter {
+    pub(super) fn from_key(key: &[u8; 32]) -> Self {
+        Self(XChaCha20Poly1305::new(Key::from_slice(key)))
+    }
+    #[cfg(test)]
+    pub(super) fn from_random_key() -> Self {
+        Self(XChaCha20Poly1305::new(&XChaCha20Poly1305::generate_key(
+            &mut OsRng,
+        )))
+    }
+}
+
+impl EnvelopeCipher for StrXChaCha20Poly1305Encrypter {
+    const ALG: CipherAlg = CipherAlg::XChaCha20Poly1305;
+
+    fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<(Vec<u8>, Vec<u8>), CryptoError> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload { msg: plaintext, aad };
+        let cipher = self.0.encrypt(&nonce, payload).map_err(CryptoError::Encrypt)?;
+        Ok((nonce.to_vec(), cipher))
+    }
+
+    fn open(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if nonce.len() != 24 {
+            // 饱和校验
+            return Err(CryptoError::InvalidNonceLength);
+        }
+        let payload = Payload { msg: ciphertext, aad };
+        self.0
+            .decrypt(XNonce::from_slice(nonce), payload)
+            .map_err(CryptoError::Decrypt)
+    }
+}
+
+impl Encrypter<&str, String> for StrXChaCha20Poly1305Encrypter {
+    type EncrypterError = CryptoError;
+    fn encrypt(&self, plaintext: &str) -> Result<String, Self::EncrypterError> {
+        self.encrypt_envelope(plaintext, &[])
+    }
+}
+impl Decrypter<&str, String> for StrXChaCha20Poly1305Encrypter {
+    type DecrypterError = CryptoError;
+    fn decrypt(&self, ciphertext: &str) -> Result<String, Self::DecrypterError> {
+        // 早于信封的密文均为 AES-256-GCM，该实现不可解密
+        let envelope = Envelope::parse(ciphertext)?.ok_or(CryptoError::CipherAlgMismatch)?;
+        self.decrypt_envelope(&envelope, &[])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::crypto::aes_gcm::StrAes256GcmEncrypter;
+
+    #[test]
+    fn test_str_xchacha20_poly1305_impl() {
+        let encrypter = StrXChaCha20Poly1305Encrypter::from_random_key();
+        let plaintext = "hello world";
+        let cip = encrypter.encrypt(plaintext).unwrap();
+        assert_ne!(cip, encrypter.encrypt(plaintext).unwrap());
+        assert_eq!(encrypter.decrypt(&cip).unwrap(), plaintext);
+        // 算法不同的信封
+        let aes_cip = StrAes256GcmEncrypter::from_random_key().encrypt(plaintext).unwrap();
+        assert!(matches!(
+            encrypter.decrypt(&aes_cip),
+            Err(CryptoError::CipherAlgMismatch)
+        ));
+    }
+}
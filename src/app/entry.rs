@@ -1,22 +1,111 @@
 use crate::app::crypto::Decrypter;
+use crate::app::crypto::entry_secret::EntrySecretEncrypter;
 use crate::app::errors::AppError;
+use crate::app::secret::REDACTED;
 use anyhow::Context;
 use chrono::{DateTime, Local};
+use std::fmt::{Debug, Formatter};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// 完全映射用户的输入
-/// 其中 identity and password 尚未加密
-#[derive(Debug, Default, Clone)]
+/// 其中 identity and password 尚未加密，
+/// 遂各字段在 drop 时清零，Debug 不输出其内容
+#[derive(Default, Clone)]
 pub struct InputEntry {
     pub about: String,
     pub notes: String,
     pub username: String,
     pub password: String,
+    /// 标签名，见 [`parse_tags`]
+    pub tags: Vec<String>,
+    /// 分组路径，见 [`parse_group_path`]，空即不属于任何分组
+    pub group: String,
+    /// 自定义字段，按用户给定的顺序
+    pub fields: Vec<CustomField>,
+}
+
+impl Drop for InputEntry {
+    fn drop(&mut self) {
+        self.about.zeroize();
+        self.notes.zeroize();
+        self.username.zeroize();
+        self.password.zeroize();
+        self.tags.zeroize();
+        self.group.zeroize();
+        self.fields.zeroize();
+    }
+}
+impl ZeroizeOnDrop for InputEntry {}
+
+impl Debug for InputEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // 全加密模式下 about 及 notes 亦为秘密
+        f.debug_struct("InputEntry")
+            .field("about", &REDACTED)
+            .field("notes", &REDACTED)
+            .field("username", &REDACTED)
+            .field("password", &REDACTED)
+            .field("tags", &REDACTED)
+            .field("group", &REDACTED)
+            .field("fields", &REDACTED)
+            .finish()
+    }
 }
 impl InputEntry {
     /// 验证当前状态是否合法，只有返回true才可进行加密及存储
     pub fn validate(&self) -> bool {
         // 名称和认证字段不能为空，不应判定trim后是否为空，因为这是刻意输入的
-        !self.about.is_empty() && !self.username.is_empty() && !self.password.is_empty()
+        !self.about.is_empty()
+            && !self.username.is_empty()
+            && !self.password.is_empty()
+            && self.fields.iter().all(|f| !f.name.is_empty())
+    }
+
+    /// 按名称（不区分大小写）查找自定义字段，重名的取首个
+    pub fn field(&self, name: &str) -> Option<&CustomField> {
+        let name = name.to_lowercase();
+        self.fields.iter().find(|f| f.name.to_lowercase() == name)
+    }
+
+    /// 以给定值设定自定义字段，已有该名称的字段则替换其值，否则追加于末尾
+    pub fn set_field(&mut self, name: &str, value: &str, secret: bool) {
+        let lower = name.to_lowercase();
+        match self.fields.iter_mut().find(|f| f.name.to_lowercase() == lower) {
+            Some(field) => {
+                field.value.zeroize();
+                field.value.push_str(value);
+                field.secret = secret;
+            }
+            None => self.fields.push(CustomField {
+                name: name.to_owned(),
+                value: value.to_owned(),
+                secret,
+            }),
+        }
+    }
+
+    /// 按名称（不区分大小写）删除自定义字段，返回是否有字段被删除
+    pub fn remove_field(&mut self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        let len = self.fields.len();
+        self.fields.retain(|f| f.name.to_lowercase() != name);
+        self.fields.len() != len
+    }
+}
+
+/// 条目的一个自定义字段，如 API key、账号、恢复码、主机名，
+/// 秘密字段（secret）的值总是加密且默认掩码显示，其余的同 notes，仅全加密模式下加密
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+    pub secret: bool,
+}
+
+impl Zeroize for CustomField {
+    fn zeroize(&mut self) {
+        self.name.zeroize();
+        self.value.zeroize();
     }
 }
 
@@ -24,10 +113,14 @@ impl InputEntry {
 /// 一个用户输入的Entry若能够通过验证，则会转换为该类型
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ValidEntry {
+    /// 该条目的id，密文与之绑定，新建条目在加密前即分配
+    pub id: u32,
     pub about: String,
     pub notes: Option<String>,
     pub encrypted_username: String,
     pub encrypted_password: String,
+    /// 自定义字段，见 [`EncryptedEntry::fields`]
+    pub fields: Option<String>,
 }
 
 /// 一个条目，与数据库中一个条目相关
@@ -46,12 +139,35 @@ pub struct EncryptedEntry {
     /// 创建时间
     pub created_time: DateTime<Local>,
     pub updated_time: DateTime<Local>,
+    /// 标签名（明文），标签存储于 tag 表而非条目中，
+    /// 由 `PntContext` 查询后填充，storage 中原样查询的条目该值为空
+    pub tags: Vec<String>,
+    /// 分组路径（明文），同 tags，由 `PntContext` 查询 grp 表后填充
+    pub group: Option<String>,
+    /// 自定义字段 - f，各字段名及值分别加密（秘密字段的值总是加密）后序列化的 json，没有自定义字段即 None
+    pub fields: Option<String>,
 }
 /// 实现排序，按照修改时间排序
 impl EncryptedEntry {
     pub fn sort_by_update_time_desc(left: &EncryptedEntry, right: &EncryptedEntry) -> std::cmp::Ordering {
         right.updated_time.cmp(&left.updated_time)
     }
+    pub fn sort_by_create_time_desc(left: &EncryptedEntry, right: &EncryptedEntry) -> std::cmp::Ordering {
+        right.created_time.cmp(&left.created_time)
+    }
+    /// 是否有给定的标签（不区分大小写）
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim_start_matches('#');
+        self.tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase())
+    }
+    /// 是否属于给定的分组或其下级分组（不区分大小写）
+    pub fn in_group(&self, path: &str) -> bool {
+        let Some(group) = &self.group else {
+            return false;
+        };
+        let (group, path) = (group.to_lowercase(), path.to_lowercase());
+        group == path || group.strip_prefix(&path).is_some_and(|rest| rest.starts_with(GROUP_SEPARATOR))
+    }
     /// 解密 Entry 为 UserInputEntry
     pub fn decrypt<'a, 'b: 'a, Dec>(&'b self, decrypt: &Dec) -> anyhow::Result<InputEntry>
     where
@@ -60,4 +176,127 @@ impl EncryptedEntry {
         // 主要提示DataCorrupted：存储成功的我想象不会解密失败，唯一解释是实际文件被人为修改，即提示数据已损坏
         decrypt.decrypt(self).with_context(|| AppError::DataCorrupted)
     }
+    /// 解密 Entry 的 about 及 notes（全加密模式下其为密文），
+    /// 返回的 Entry 中 username 及 password 仍为密文
+    pub fn decrypt_about_notes(&self, encrypter: &EntrySecretEncrypter) -> anyhow::Result<EncryptedEntry> {
+        encrypter
+            .decrypt_about_notes(self)
+            .with_context(|| AppError::DataCorrupted)
+    }
+}
+
+/// 回收站中的条目，即 entry 表中有删除时间的条目
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TrashedEntry {
+    /// 该条目，tags 及 group 未填充，为空
+    pub entry: EncryptedEntry,
+    /// 移入回收站的时间
+    pub deleted_time: DateTime<Local>,
+}
+
+/// entry_history 表中条目的一个历史版本，
+/// 密文与条目id绑定，遂其 entry 的 id 即所属条目的id，可如当前版本般解密
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EntryVersion {
+    /// 该历史版本的id
+    pub id: u32,
+    /// 该版本的条目，其 updated_time 即该版本保存的时间，tags 及 group 不随版本记录，为空
+    pub entry: EncryptedEntry,
+    /// 该版本被替换（即被归档）的时间
+    pub archived_time: DateTime<Local>,
+}
+
+/// tag 表中的一个标签，全加密模式下 name 为密文
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Tag {
+    /// 该标签的id，全加密模式下密文与之绑定
+    pub id: u32,
+    pub name: String,
+}
+
+/// grp 表中的一个分组，全加密模式下 name 为密文
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Group {
+    /// 该分组的id，全加密模式下密文与之绑定
+    pub id: u32,
+    /// 上级分组的id，None 即为顶级分组
+    pub parent: Option<u32>,
+    pub name: String,
+}
+
+/// 分组路径中各级分组名的分隔符
+pub const GROUP_SEPARATOR: &str = "/";
+
+/// 解析用户输入的分组路径（如 `work/aws/prod`），返回各级分组名，
+/// 忽略分组名两侧的空白及空的层级，遂空路径即不属于任何分组
+pub fn parse_group_path(input: &str) -> Vec<String> {
+    input
+        .split(GROUP_SEPARATOR)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// 规范化用户输入的分组路径，如 ` work/ aws/` 即 `work/aws`
+pub fn normalize_group_path(input: &str) -> String {
+    parse_group_path(input).join(GROUP_SEPARATOR)
+}
+
+/// 解析用户输入的标签，以空白或 `,` 分隔，可带 `#` 前缀，
+/// 忽略空标签，重复的（不区分大小写）仅保留首个
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(|c: char| c.is_whitespace() || c == ',') {
+        let tag = tag.trim_start_matches('#');
+        if !tag.is_empty() && !tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            tags.push(tag.to_owned());
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(parse_tags(" #work, mail  Work,,工作 "), vec!["work", "mail", "工作"]);
+        assert!(parse_tags("# , ").is_empty());
+    }
+
+    #[test]
+    fn test_group_path() {
+        assert_eq!(parse_group_path(" work/ aws //prod/"), vec!["work", "aws", "prod"]);
+        assert!(parse_group_path(" / ").is_empty());
+        assert_eq!(normalize_group_path(" work/ aws/"), "work/aws");
+        let entry = EncryptedEntry {
+            id: 1,
+            about: String::new(),
+            notes: None,
+            encrypted_username: String::new(),
+            encrypted_password: String::new(),
+            created_time: Default::default(),
+            updated_time: Default::default(),
+            tags: Vec::new(),
+            group: Some("work/AWS/prod".to_owned()),
+            fields: None,
+        };
+        assert!(entry.in_group("work") && entry.in_group("work/aws") && entry.in_group("work/aws/prod"));
+        assert!(!entry.in_group("wo") && !entry.in_group("work/aws/prod/x"));
+    }
+
+    #[test]
+    fn test_custom_fields() {
+        let mut entry = InputEntry::default();
+        entry.set_field("API key", "k1", true);
+        entry.set_field("host", "example.com", false);
+        entry.set_field("api KEY", "k2", true);
+        assert_eq!(entry.fields.len(), 2);
+        assert_eq!(entry.field("api key").unwrap().value, "k2");
+        assert!(entry.remove_field("HOST") && !entry.remove_field("host"));
+        entry.fields.push(CustomField::default());
+        assert!(!entry.fields.iter().all(|f| !f.name.is_empty()));
+    }
 }
@@ -14,6 +14,58 @@ pub enum AppError {
     /// 未校验主密码却到达了需要主密码的请求
     #[error("main password is not verified")]
     MainPwdNotVerified,
+    /// data file 结构版本高于当前程序支持的版本（即由更新版本的 pnt 写入）
+    #[error(
+        "data file schema version {found} is newer than the supported version {supported} (please upgrade pnt)"
+    )]
+    DataFileTooNew { found: u32, supported: u32 },
+    /// data file 要求密钥文件，但未给定
+    #[error("the data file requires a keyfile (use --keyfile or set `keyfile` in pnt.toml)")]
+    KeyfileRequired,
+    /// 密钥文件内容为空
+    #[error("the keyfile is empty")]
+    EmptyKeyfile,
+    /// 恢复密钥格式无效或不可解包数据密钥
+    #[error("invalid recovery key")]
+    InvalidRecoveryKey,
+    /// data file 没有恢复密钥（早于该特性或从未生成）
+    #[error("the data file has no recovery key (generate one with `pnt recovery-key`)")]
+    NoRecoveryKey,
+    /// 条目输入不合法（见 [`crate::app::entry::InputEntry::validate`]）
+    #[error("invalid entry: 'about', 'username' and 'password' are required")]
+    InvalidEntry,
+    /// 给定的 id 或 about 找不到条目
+    #[error("no entry matches '{0}'")]
+    EntryNotFound(String),
+    /// 条目没有给定名称的字段
+    #[error("entry '{about}' has no field '{field}'")]
+    FieldNotFound { about: String, field: String },
+    /// 给定的 about 匹配多个条目
+    #[error("'{query}' matches multiple entries ({matches}), use a more specific about or the entry id")]
+    AmbiguousEntry { query: String, matches: String },
+    /// 主密码校验失败次数达到配置的上限，不再允许尝试
+    #[error(
+        "{count} failed unlock attempts reached the configured limit, unlocking is locked out (reset the main password with `pnt recover`)"
+    )]
+    UnlockLockedOut { count: u32 },
+    /// 密码生成策略无效
+    #[error("invalid password policy: {0}")]
+    InvalidGenPolicy(String),
+    /// 给定名称的密码生成策略不存在
+    #[error("no password policy named '{0}' (save one with `pnt gen ... --save <NAME>`)")]
+    GenPolicyNotFound(String),
+    /// 非交互的主密码来源已无更多主密码
+    #[error("no main password given by {0}")]
+    MissingMainPwd(String),
+    /// 非交互地设定的主密码过短
+    #[error("the main password must be at least {0} characters")]
+    MainPwdTooShort(u8),
+    /// 非交互地解锁时尚需等待，不阻塞等待而立即失败
+    #[error("too many failed unlock attempts, retry after {secs}s")]
+    UnlockRetryLater { secs: u64 },
+    /// 主密码校验失败次数达到配置的上限，data file 已被擦除
+    #[error("too many failed unlock attempts, the data file has been wiped")]
+    DataWiped,
 }
 
 /// 加密解密错误
@@ -25,6 +77,8 @@ pub enum CryptoError {
     DecodeSalt(argon2::password_hash::Error),
     #[error("failed to decode main password: {0}")]
     DecodeMP(base64ct::Error),
+    #[error("invalid kdf parameters: {0}")]
+    InvalidKdfParams(argon2::Error),
     #[error("generate key error")]
     GenerateKey,
     #[error("encrypt error: {0}")]
@@ -39,4 +93,12 @@ pub enum CryptoError {
     DecodeCiphertext,
     #[error("invalid nonce length")]
     InvalidNonceLength,
+    #[error("unsupported ciphertext envelope version {0}")]
+    UnsupportedEnvelopeVersion(u8),
+    #[error("unknown cipher algorithm")]
+    UnknownCipherAlg,
+    #[error("cipher algorithm mismatch")]
+    CipherAlgMismatch,
+    #[error("decode custom fields error")]
+    DecodeCustomFields,
 }
@@ -0,0 +1,115 @@
+//! 跨进程累计的主密码校验失败记录
+//!
+//! 失败次数及最近一次失败的时间存储于 data file，遂重启 pnt 不会重置之，
+//! 超过单次运行允许的次数后，每次尝试前须等待指数增长的时长
+
+use crate::app::consts::ALLOC_INVALID_MAIN_PASS_MAX;
+use crate::app::errors::AppError;
+use chrono::{DateTime, Local, TimeDelta};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// 主密码校验失败记录，解锁成功时清除
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct FailedUnlock {
+    /// 自上次成功解锁以来的失败次数
+    pub count: u32,
+    /// 最近一次失败的时间，count 为 0 时为 None
+    pub last_failed: Option<DateTime<Local>>,
+}
+
+impl FailedUnlock {
+    /// 无需等待的失败次数，即单次运行允许的次数
+    const FREE_ATTEMPTS: u32 = ALLOC_INVALID_MAIN_PASS_MAX as u32;
+    /// 等待时长上限
+    const MAX_BACKOFF_SECS: i64 = 60 * 60;
+
+    /// 记录一次失败
+    pub fn record(&mut self, now: DateTime<Local>) {
+        self.count = self.count.saturating_add(1);
+        self.last_failed = Some(now);
+    }
+
+    /// 当前失败次数要求的等待时长，
+    /// 超过 [`Self::FREE_ATTEMPTS`] 后自 2 秒起每次翻倍，至多 [`Self::MAX_BACKOFF_SECS`]
+    pub fn backoff(&self) -> TimeDelta {
+        if self.count < Self::FREE_ATTEMPTS {
+            return TimeDelta::zero();
+        }
+        let exp = (self.count - Self::FREE_ATTEMPTS + 1).min(31);
+        TimeDelta::seconds((1_i64 << exp).min(Self::MAX_BACKOFF_SECS))
+    }
+
+    /// 允许下次尝试的时间，无需等待则 None
+    pub fn retry_after(&self) -> Option<DateTime<Local>> {
+        let backoff = self.backoff();
+        if backoff.is_zero() {
+            return None;
+        }
+        self.last_failed.map(|last| last + backoff)
+    }
+
+    /// 成功解锁时向用户显示的提示
+    pub fn summary(&self) -> String {
+        let last = self
+            .last_failed
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        format!("{} failed unlock attempt(s) since the last unlock, the last at {}", self.count, last)
+    }
+}
+
+/// 距给定时间的剩余秒数（向上取整），已过则 None
+pub fn secs_until(time: DateTime<Local>) -> Option<u64> {
+    let ms = (time - Local::now()).num_milliseconds();
+    (ms > 0).then(|| (ms as u64).div_ceil(1000))
+}
+
+/// 存储于 cfg 表的形式：`<失败次数>,<最近一次失败的 unix 时间戳>`
+impl Display for FailedUnlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ts = self.last_failed.map_or(0, |t| t.timestamp());
+        write!(f, "{},{}", self.count, ts)
+    }
+}
+
+/// 仅用于解析 cfg 表中存储的值，解析失败即说明数据已被破坏
+impl FromStr for FailedUnlock {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (count, ts) = s.split_once(',').ok_or(AppError::DataCorrupted)?;
+        let count = count.parse::<u32>().map_err(|_| AppError::DataCorrupted)?;
+        let ts = ts.parse::<i64>().map_err(|_| AppError::DataCorrupted)?;
+        let last_failed = DateTime::from_timestamp(ts, 0)
+            .ok_or(AppError::DataCorrupted)?
+            .with_timezone(&Local);
+        Ok(Self {
+            count,
+            last_failed: Some(last_failed),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_roundtrips() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap().with_timezone(&Local);
+        let mut failed = FailedUnlock::default();
+        for _ in 0..FailedUnlock::FREE_ATTEMPTS - 1 {
+            failed.record(now);
+            assert!(failed.retry_after().is_none());
+        }
+        failed.record(now);
+        assert_eq!(failed.backoff(), TimeDelta::seconds(2));
+        failed.record(now);
+        assert_eq!(failed.retry_after(), Some(now + TimeDelta::seconds(4)));
+        failed.count = 1000;
+        assert_eq!(failed.backoff(), TimeDelta::seconds(FailedUnlock::MAX_BACKOFF_SECS));
+        assert_eq!(failed.to_string().parse::<FailedUnlock>().unwrap(), failed);
+        assert!("3".parse::<FailedUnlock>().is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! 模糊匹配
+//!
+//! 以子序列匹配查找条目，按字符（非字节）匹配且不区分大小写，遂中文等亦可匹配，
+//! 匹配于词首、驼峰处及连续匹配的字符得分更高，匹配间的间隔扣分，
+//! 按得分最高的匹配方式给出得分及匹配的字符位置（用于高亮），
+//! `#` 前缀的查找词匹配条目的标签
+
+use crate::app::entry::EncryptedEntry;
+
+/// 每个匹配字符的得分
+const SCORE_MATCH: i32 = 16;
+/// 匹配于文本开头或非字母数字字符之后（即词首）的加分
+const BONUS_BOUNDARY: i32 = 10;
+/// 匹配于小写后的大写字符（驼峰）的加分
+const BONUS_CAMEL: i32 = 8;
+/// 与上一个匹配字符连续的加分
+const BONUS_CONSECUTIVE: i32 = 8;
+/// 匹配间隔开始的扣分
+const PENALTY_GAP_START: i32 = 3;
+/// 匹配间隔每多一个字符的扣分
+const PENALTY_GAP_EXTENSION: i32 = 1;
+/// 匹配于 notes 而非 about 的扣分，遂 about 的匹配排序在前
+const PENALTY_NOTES: i32 = SCORE_MATCH;
+
+/// 一次匹配，`indices` 为匹配的字符在文本中的位置（以 char 计），升序
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    pub indices: Vec<usize>,
+}
+
+/// 以 `pattern` 的字符按序匹配 `text`（子序列），无法匹配则 None，
+/// 空 pattern 匹配任何文本，得分为 0
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().map(fold_case).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch::default());
+    }
+    let text: Vec<char> = text.chars().collect();
+    let (n, m) = (pattern.len(), text.len());
+    if n > m {
+        return None;
+    }
+    let folded: Vec<char> = text.iter().copied().map(fold_case).collect();
+    let bonus: Vec<i32> = (0..m)
+        .map(|j| char_bonus(j.checked_sub(1).map(|p| text[p]), text[j]))
+        .collect();
+
+    // score[i][j]：pattern[..=i] 匹配且 pattern[i] 匹配于 text[j] 时的最高得分，
+    // from[i][j]：该情况下 pattern[i - 1] 匹配的位置，用于回溯匹配的字符位置
+    let mut score = vec![vec![None::<i32>; m]; n];
+    let mut from = vec![vec![0_usize; m]; n];
+    for j in 0..m {
+        if folded[j] == pattern[0] {
+            score[0][j] = Some(SCORE_MATCH + bonus[j]);
+        }
+    }
+    for i in 1..n {
+        // 间隔匹配（pattern[i - 1] 匹配于 j - 2 及之前）的最高得分及其位置
+        let mut gapped: Option<(i32, usize)> = None;
+        for j in i..m {
+            if j >= 2
+                && let Some(prev) = score[i - 1][j - 2]
+            {
+                let candidate = prev - PENALTY_GAP_START;
+                gapped = match gapped {
+                    Some((best, k)) if best - PENALTY_GAP_EXTENSION >= candidate => {
+                        Some((best - PENALTY_GAP_EXTENSION, k))
+                    }
+                    _ => Some((candidate, j - 2)),
+                };
+            } else if let Some((best, k)) = gapped {
+                gapped = Some((best - PENALTY_GAP_EXTENSION, k));
+            }
+            if folded[j] != pattern[i] {
+                continue;
+            }
+            let consecutive = score[i - 1][j - 1].map(|prev| (prev + BONUS_CONSECUTIVE, j - 1));
+            let best = match (consecutive, gapped) {
+                (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+                (c, g) => c.or(g),
+            };
+            if let Some((prev, k)) = best {
+                score[i][j] = Some(prev + SCORE_MATCH + bonus[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    // 得分最高的末字符位置，得分相同取靠前的
+    let (mut j, best) = (0..m)
+        .filter_map(|j| score[n - 1][j].map(|s| (j, s)))
+        .fold(None, |acc: Option<(usize, i32)>, (j, s)| match acc {
+            Some((_, best)) if best >= s => acc,
+            _ => Some((j, s)),
+        })?;
+    let mut indices = vec![0; n];
+    for i in (0..n).rev() {
+        indices[i] = j;
+        j = from[i][j];
+    }
+    Some(FuzzyMatch { score: best, indices })
+}
+
+/// 条目的匹配，`about` 及 `notes` 为其中匹配的字符位置，`tags` 为匹配的标签的位置
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct EntryMatch {
+    pub score: i32,
+    pub about: Vec<usize>,
+    pub notes: Vec<usize>,
+    pub tags: Vec<usize>,
+}
+
+/// 以 `query` 匹配条目的 about 及 notes，
+/// query 以空白分隔为多个词，每个词均须匹配 about 或 notes，不匹配则 None，
+/// `#` 前缀的词（如 `#work`）则须匹配条目的某个标签，仅 `#` 即须有标签
+pub fn match_entry(query: &str, entry: &EncryptedEntry) -> Option<EntryMatch> {
+    let mut entry_match = EntryMatch::default();
+    for term in query.split_whitespace() {
+        if let Some(tag_term) = term.strip_prefix('#') {
+            if entry.tags.is_empty() {
+                return None;
+            }
+            if tag_term.is_empty() {
+                continue;
+            }
+            // 得分最高的标签，得分相同取靠前的
+            let (score, t_idx) = entry
+                .tags
+                .iter()
+                .enumerate()
+                .filter_map(|(i, tag)| fuzzy_match(tag_term, tag).map(|m| (m.score, i)))
+                .min_by_key(|&(score, i)| (-score, i))?;
+            entry_match.score += score;
+            entry_match.tags.push(t_idx);
+            continue;
+        }
+        let about = fuzzy_match(term, &entry.about);
+        let notes = entry
+            .notes
+            .as_deref()
+            .and_then(|notes| fuzzy_match(term, notes))
+            .map(|m| FuzzyMatch {
+                score: m.score - PENALTY_NOTES,
+                ..m
+            });
+        let (m, indices) = match (about, notes) {
+            (Some(a), Some(n)) if n.score > a.score => (n, &mut entry_match.notes),
+            (Some(a), _) => (a, &mut entry_match.about),
+            (None, Some(n)) => (n, &mut entry_match.notes),
+            (None, None) => return None,
+        };
+        entry_match.score += m.score;
+        indices.extend(m.indices);
+    }
+    entry_match.about.sort_unstable();
+    entry_match.about.dedup();
+    entry_match.notes.sort_unstable();
+    entry_match.notes.dedup();
+    entry_match.tags.sort_unstable();
+    entry_match.tags.dedup();
+    Some(entry_match)
+}
+
+/// 不区分大小写的比较所用的字符
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// 匹配于某字符的加分，`prev` 为其前一个字符
+fn char_bonus(prev: Option<char>, c: char) -> i32 {
+    match prev {
+        None => BONUS_BOUNDARY,
+        Some(p) if !p.is_alphanumeric() && c.is_alphanumeric() => BONUS_BOUNDARY,
+        Some(p) if p.is_lowercase() && c.is_uppercase() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        // 子序列，不区分大小写
+        let m = fuzzy_match("gh", "GitHub").unwrap();
+        assert_eq!(m.indices, vec![0, 3]);
+        assert!(fuzzy_match("hg", "GitHub").is_none());
+        // 词首匹配优于词中匹配
+        let m = fuzzy_match("mb", "mail box").unwrap();
+        assert_eq!(m.indices, vec![0, 5]);
+        assert!(m.score > fuzzy_match("mb", "ambient").unwrap().score);
+        // 连续匹配优于分散匹配
+        assert!(fuzzy_match("git", "gitlab").unwrap().score > fuzzy_match("git", "great item").unwrap().score);
+        // 中文按字符匹配
+        let m = fuzzy_match("工邮", "工作邮箱").unwrap();
+        assert_eq!(m.indices, vec![0, 2]);
+        assert_eq!(fuzzy_match("", "any"), Some(FuzzyMatch::default()));
+    }
+
+    #[test]
+    fn test_match_entry() {
+        let entry = EncryptedEntry {
+            id: 1,
+            about: "GitHub".to_owned(),
+            notes: Some("2fa on".to_owned()),
+            encrypted_username: String::new(),
+            encrypted_password: String::new(),
+            created_time: Default::default(),
+            updated_time: Default::default(),
+            tags: vec!["dev".to_owned(), "work".to_owned()],
+            group: None,
+            fields: None,
+        };
+        let m = match_entry("gh 2fa", &entry).unwrap();
+        assert_eq!(m.about, vec![0, 3]);
+        assert_eq!(m.notes, vec![0, 1, 2]);
+        // 标签过滤
+        assert_eq!(match_entry("#wk", &entry).unwrap().tags, vec![1]);
+        assert_eq!(match_entry("git #", &entry).unwrap().tags, Vec::<usize>::new());
+        assert!(match_entry("#home", &entry).is_none());
+        assert!(match_entry("#", &EncryptedEntry { tags: Vec::new(), ..entry }).is_none());
+    }
+}
@@ -0,0 +1,347 @@
+//! 密码生成
+//!
+//! 按策略生成随机字符密码或 diceware 式的口令，
+//! 具名策略随 data file 存储于 cfg 表
+
+use crate::app::errors::AppError;
+use crate::app::secret::SecretString;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use bitflags::bitflags;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// 生成密码默认长度
+pub const DEFAULT_PASSWORD_LEN: u16 = 20;
+/// 生成口令默认单词数，约 66 bit
+pub const DEFAULT_PASSPHRASE_WORDS: u16 = 6;
+/// 生成口令默认分隔符
+pub const DEFAULT_PASSPHRASE_SEPARATOR: &str = "-";
+
+/// 口令词表，即 BIP-39 英文词表（CC0），2048 个单词，每个单词约 11 bit
+const WORDLIST: &str = include_str!("generator/wordlist.txt");
+
+/// 易混淆的字符
+const AMBIGUOUS: &[u8] = b"0O1lI";
+
+bitflags! {
+    /// 生成密码使用的字符类别
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub struct CharClasses: u8 {
+        const UPPER = 0b0000_0001;
+        const LOWER = 0b0000_0010;
+        const DIGIT = 0b0000_0100;
+        const SYMBOL = 0b0000_1000;
+    }
+}
+
+impl CharClasses {
+    /// 各类别、其字母表示及字符集
+    const ALL: [(CharClasses, char, &'static [u8]); 4] = [
+        (CharClasses::UPPER, 'u', b"ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+        (CharClasses::LOWER, 'l', b"abcdefghijklmnopqrstuvwxyz"),
+        (CharClasses::DIGIT, 'd', b"0123456789"),
+        (CharClasses::SYMBOL, 's', b"!@#$%^&*-_=+"),
+    ];
+}
+
+/// 形如 `ulds`，每个字母表示一个类别
+impl Display for CharClasses {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Self::ALL
+            .iter()
+            .filter(|(class, _, _)| self.contains(*class))
+            .try_for_each(|(_, c, _)| write!(f, "{c}"))
+    }
+}
+
+impl FromStr for CharClasses {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars().try_fold(CharClasses::empty(), |acc, c| {
+            Self::ALL
+                .iter()
+                .find(|(_, letter, _)| *letter == c)
+                .map(|(class, _, _)| acc | *class)
+                .ok_or_else(|| AppError::InvalidGenPolicy(format!("unknown character class '{c}' (use u, l, d, s)")))
+        })
+    }
+}
+
+/// 密码生成策略
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PasswordPolicy {
+    /// 从给定类别的字符中随机选取
+    Chars {
+        length: u16,
+        classes: CharClasses,
+        /// 排除易混淆的字符，见 [`AMBIGUOUS`]
+        exclude_ambiguous: bool,
+        /// 保证每个类别至少出现一次
+        require_each: bool,
+    },
+    /// 从词表中随机选取单词，以分隔符连接
+    Passphrase { words: u16, separator: String },
+}
+
+/// 20 位，包含全部类别且每类至少一个
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy::Chars {
+            length: DEFAULT_PASSWORD_LEN,
+            classes: CharClasses::all(),
+            exclude_ambiguous: false,
+            require_each: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// 未以名称指定策略时使用的具名策略，不存在即为 [`PasswordPolicy::default`]
+    pub const DEFAULT_NAME: &'static str = "default";
+
+    /// 给定长度的默认口令策略
+    pub fn passphrase(words: u16) -> Self {
+        PasswordPolicy::Passphrase {
+            words,
+            separator: DEFAULT_PASSPHRASE_SEPARATOR.to_owned(),
+        }
+    }
+
+    /// 以给定长度替换该策略的长度，口令策略则替换为给定长度的默认字符策略
+    pub fn with_length(self, len: u16) -> Self {
+        match self {
+            PasswordPolicy::Chars {
+                classes,
+                exclude_ambiguous,
+                require_each,
+                ..
+            } => PasswordPolicy::Chars {
+                length: len,
+                classes,
+                exclude_ambiguous,
+                require_each,
+            },
+            PasswordPolicy::Passphrase { .. } => PasswordPolicy::default().with_length(len),
+        }
+    }
+
+    /// 校验策略可生成密码，不可则 Err [`AppError::InvalidGenPolicy`]
+    pub fn validate(&self) -> Result<(), AppError> {
+        let invalid = |msg: &str| Err(AppError::InvalidGenPolicy(msg.to_owned()));
+        match self {
+            PasswordPolicy::Chars {
+                length,
+                classes,
+                require_each,
+                ..
+            } => {
+                if classes.is_empty() {
+                    return invalid("at least one character class is required");
+                }
+                if !(4..=1024).contains(length) {
+                    return invalid("length must be in 4..=1024");
+                }
+                if *require_each && (*length as u32) < classes.bits().count_ones() {
+                    return invalid("length is shorter than the number of required character classes");
+                }
+            }
+            PasswordPolicy::Passphrase { words, separator } => {
+                if !(3..=64).contains(words) {
+                    return invalid("words must be in 3..=64");
+                }
+                if separator.chars().count() > 8 || separator.chars().any(|c| c == ',' || c.is_control()) {
+                    return invalid("separator must be at most 8 characters, without ',' or control characters");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 以系统随机源按策略生成密码，策略无效则 Err
+    pub fn generate(&self) -> Result<SecretString, AppError> {
+        self.validate()?;
+        let mut passwd = SecretString::default();
+        match self {
+            PasswordPolicy::Chars {
+                length,
+                classes,
+                exclude_ambiguous,
+                require_each,
+            } => {
+                let sets: Vec<Vec<u8>> = CharClasses::ALL
+                    .iter()
+                    .filter(|(class, _, _)| classes.contains(*class))
+                    .map(|(_, _, set)| {
+                        set.iter()
+                            .copied()
+                            .filter(|b| !*exclude_ambiguous || !AMBIGUOUS.contains(b))
+                            .collect()
+                    })
+                    .collect();
+                let charset = sets.concat();
+                let mut chars = Vec::with_capacity(*length as usize);
+                // 先自每个类别选取一个，余下自全部字符中选取，再打乱顺序
+                if *require_each {
+                    chars.extend(sets.iter().map(|set| set[uniform_index(set.len() as u32)]));
+                }
+                while chars.len() < *length as usize {
+                    chars.push(charset[uniform_index(charset.len() as u32)]);
+                }
+                for i in (1..chars.len()).rev() {
+                    chars.swap(i, uniform_index(i as u32 + 1));
+                }
+                passwd.extend(chars.iter().map(|b| *b as char));
+                zeroize::Zeroize::zeroize(&mut chars);
+            }
+            PasswordPolicy::Passphrase { words, separator } => {
+                let wordlist: Vec<&str> = WORDLIST.lines().collect();
+                for i in 0..*words {
+                    if i > 0 {
+                        passwd.push_str(separator);
+                    }
+                    passwd.push_str(wordlist[uniform_index(wordlist.len() as u32)]);
+                }
+            }
+        }
+        Ok(passwd)
+    }
+}
+
+/// 形如 `chars$len=20,cls=ulds,amb=1,req=1` 或 `words$n=6,sep=-`，
+/// 该形式即为 cfg 表中的存储形式，amb 为 1 表示排除易混淆的字符
+impl Display for PasswordPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasswordPolicy::Chars {
+                length,
+                classes,
+                exclude_ambiguous,
+                require_each,
+            } => write!(
+                f,
+                "chars$len={},cls={},amb={},req={}",
+                length, classes, *exclude_ambiguous as u8, *require_each as u8
+            ),
+            PasswordPolicy::Passphrase { words, separator } => write!(f, "words$n={words},sep={separator}"),
+        }
+    }
+}
+
+/// 仅用于解析 cfg 表中存储的值，解析失败即说明数据已被破坏
+impl FromStr for PasswordPolicy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, params) = s.split_once('$').ok_or(AppError::DataCorrupted)?;
+        let mut policy = match kind {
+            "chars" => PasswordPolicy::default(),
+            "words" => PasswordPolicy::passphrase(DEFAULT_PASSPHRASE_WORDS),
+            _ => Err(AppError::DataCorrupted)?,
+        };
+        for kv in params.split(',') {
+            let (k, v) = kv.split_once('=').ok_or(AppError::DataCorrupted)?;
+            let num = || v.parse::<u16>().map_err(|_| AppError::DataCorrupted);
+            let flag = || match v {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                _ => Err(AppError::DataCorrupted),
+            };
+            match (&mut policy, k) {
+                (PasswordPolicy::Chars { length, .. }, "len") => *length = num()?,
+                (PasswordPolicy::Chars { classes, .. }, "cls") => {
+                    *classes = v.parse().map_err(|_| AppError::DataCorrupted)?
+                }
+                (PasswordPolicy::Chars { exclude_ambiguous, .. }, "amb") => *exclude_ambiguous = flag()?,
+                (PasswordPolicy::Chars { require_each, .. }, "req") => *require_each = flag()?,
+                (PasswordPolicy::Passphrase { words, .. }, "n") => *words = num()?,
+                (PasswordPolicy::Passphrase { separator, .. }, "sep") => *separator = v.to_owned(),
+                _ => Err(AppError::DataCorrupted)?,
+            }
+        }
+        policy.validate().map_err(|_| AppError::DataCorrupted)?;
+        Ok(policy)
+    }
+}
+
+/// 策略名称是否合法：1 至 32 个小写字母、数字、`-` 或 `_`
+pub fn is_valid_policy_name(name: &str) -> bool {
+    (1..=32).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// 返回 [0, n) 中均匀分布的随机下标，拒绝采样以避免取模偏差
+fn uniform_index(n: u32) -> usize {
+    let zone = u32::MAX - u32::MAX % n;
+    loop {
+        let r = OsRng.next_u32();
+        if r < zone {
+            return (r % n) as usize;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_chars() {
+        let p1 = PasswordPolicy::default().generate().unwrap();
+        let p2 = PasswordPolicy::default().generate().unwrap();
+        assert_eq!(p1.len(), DEFAULT_PASSWORD_LEN as usize);
+        assert_ne!(p1, p2);
+        for (_, _, set) in CharClasses::ALL {
+            assert!(p1.bytes().any(|b| set.contains(&b)));
+        }
+
+        let policy = PasswordPolicy::Chars {
+            length: 64,
+            classes: CharClasses::UPPER | CharClasses::DIGIT,
+            exclude_ambiguous: true,
+            require_each: true,
+        };
+        let p = policy.generate().unwrap();
+        assert!(p.bytes().all(|b| (b.is_ascii_uppercase() || b.is_ascii_digit()) && !AMBIGUOUS.contains(&b)));
+
+        let too_short = PasswordPolicy::Chars {
+            length: 4,
+            classes: CharClasses::all(),
+            exclude_ambiguous: false,
+            require_each: true,
+        };
+        assert!(too_short.generate().is_ok());
+        let shorter = PasswordPolicy::Chars {
+            length: 3,
+            classes: CharClasses::LOWER,
+            exclude_ambiguous: false,
+            require_each: false,
+        };
+        assert!(matches!(shorter.generate(), Err(AppError::InvalidGenPolicy(_))));
+    }
+
+    #[test]
+    fn test_generate_passphrase_and_roundtrip() {
+        assert_eq!(WORDLIST.lines().count(), 2048);
+        let p = PasswordPolicy::passphrase(5).generate().unwrap();
+        let words: Vec<_> = p.split('-').collect();
+        assert_eq!(words.len(), 5);
+        assert!(words.iter().all(|w| WORDLIST.lines().any(|l| l == *w)));
+
+        for policy in [
+            PasswordPolicy::default(),
+            PasswordPolicy::Passphrase {
+                words: 8,
+                separator: " ".to_owned(),
+            },
+        ] {
+            assert_eq!(policy.to_string().parse::<PasswordPolicy>().unwrap(), policy);
+        }
+        assert_eq!(PasswordPolicy::default().to_string(), "chars$len=20,cls=ulds,amb=0,req=1");
+        assert!("words$n=2,sep=-".parse::<PasswordPolicy>().is_err());
+        assert!("chars$len=20,n=3".parse::<PasswordPolicy>().is_err());
+        assert!("uldx".parse::<CharClasses>().is_err());
+    }
+}
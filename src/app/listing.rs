@@ -0,0 +1,263 @@
+//! `pnt list` 的条目输出
+//!
+//! 与 `-f` 不同，输出条目 id 而非显示序号，json 及 tsv 格式可供脚本稳定解析
+
+use crate::app::entry::{EncryptedEntry, InputEntry};
+use chrono::{DateTime, Local, SecondsFormat};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Write;
+use unicode_width::UnicodeWidthStr;
+use zeroize::Zeroize;
+
+/// 输出格式，变体注释即 cli 帮助
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ListFormat {
+    /// Aligned columns for reading
+    #[default]
+    Table,
+    /// A JSON array of objects
+    Json,
+    /// Tab-separated values with a header line
+    Tsv,
+}
+
+/// 排序方式，变体注释即 cli 帮助
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ListSort {
+    /// Last updated first
+    #[default]
+    Updated,
+    /// Last created first
+    Created,
+    /// Ascending id
+    Id,
+    /// Ascending about
+    About,
+}
+
+impl ListSort {
+    /// 按当前方式排序条目
+    pub fn sort(self, entries: &mut [EncryptedEntry]) {
+        match self {
+            ListSort::Updated => entries.sort_by(EncryptedEntry::sort_by_update_time_desc),
+            ListSort::Created => entries.sort_by(EncryptedEntry::sort_by_create_time_desc),
+            ListSort::Id => entries.sort_by_key(|e| e.id),
+            ListSort::About => entries.sort_by(|l, r| l.about.cmp(&r.about)),
+        }
+    }
+}
+
+/// 一个待输出的条目，仅明确要求时才携带 username 及 password，
+/// 遂其在 drop 时清零
+#[derive(Serialize)]
+pub struct ListedEntry {
+    pub id: u32,
+    pub about: String,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+    pub group: Option<String>,
+    pub created_time: DateTime<Local>,
+    pub updated_time: DateTime<Local>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+impl Drop for ListedEntry {
+    fn drop(&mut self) {
+        self.username.zeroize();
+        self.password.zeroize();
+    }
+}
+
+impl ListedEntry {
+    /// 不含秘密字段，entry 的 about 及 notes 应已为明文
+    pub fn without_secrets(entry: &EncryptedEntry) -> Self {
+        Self {
+            id: entry.id,
+            about: entry.about.clone(),
+            notes: entry.notes.clone().filter(|n| !n.is_empty()),
+            tags: entry.tags.clone(),
+            group: entry.group.clone(),
+            created_time: entry.created_time,
+            updated_time: entry.updated_time,
+            username: None,
+            password: None,
+        }
+    }
+
+    /// 含秘密字段，input 为 entry 解密的结果
+    pub fn with_secrets(entry: &EncryptedEntry, input: &InputEntry) -> Self {
+        Self {
+            id: entry.id,
+            about: input.about.clone(),
+            notes: Some(input.notes.clone()).filter(|n| !n.is_empty()),
+            tags: entry.tags.clone(),
+            group: entry.group.clone(),
+            created_time: entry.created_time,
+            updated_time: entry.updated_time,
+            username: Some(input.username.clone()),
+            password: Some(input.password.clone()),
+        }
+    }
+
+    /// 文本列的值：about、notes 及可能的 username、password
+    fn text_values(&self) -> Vec<&str> {
+        let mut values = vec![self.about.as_str(), self.notes.as_deref().unwrap_or_default()];
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            values.push(username);
+            values.push(password);
+        }
+        values
+    }
+}
+
+/// 列名，tsv 首行及表头
+fn columns(with_secrets: bool) -> Vec<&'static str> {
+    let mut cols = vec!["id", "about", "notes", "tags", "group", "created_time", "updated_time"];
+    if with_secrets {
+        cols.extend(["username", "password"]);
+    }
+    cols
+}
+
+/// 按给定格式渲染条目
+pub fn render(entries: &[ListedEntry], format: ListFormat, with_secrets: bool) -> anyhow::Result<String> {
+    Ok(match format {
+        ListFormat::Json => serde_json::to_string_pretty(entries)?,
+        ListFormat::Tsv => render_tsv(entries, with_secrets),
+        ListFormat::Table => render_table(entries, with_secrets),
+    })
+}
+
+fn render_tsv(entries: &[ListedEntry], with_secrets: bool) -> String {
+    let mut out = columns(with_secrets).join("\t");
+    for e in entries {
+        let time = |t: &DateTime<Local>| t.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let mut row = vec![e.id.to_string(), escape_tsv(&e.about)];
+        row.push(escape_tsv(e.notes.as_deref().unwrap_or_default()));
+        row.push(escape_tsv(&e.tags.join(",")));
+        row.push(escape_tsv(e.group.as_deref().unwrap_or_default()));
+        row.push(time(&e.created_time));
+        row.push(time(&e.updated_time));
+        if let (Some(username), Some(password)) = (&e.username, &e.password) {
+            row.push(escape_tsv(username));
+            row.push(escape_tsv(password));
+        }
+        let _ = write!(out, "\n{}", row.join("\t"));
+        row.zeroize();
+    }
+    out
+}
+
+/// 转义 tsv 值中的 `\`、tab 及换行
+fn escape_tsv(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_table(entries: &[ListedEntry], with_secrets: bool) -> String {
+    let time = |t: &DateTime<Local>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut rows: Vec<Vec<String>> = vec![columns(with_secrets).iter().map(|c| c.to_uppercase()).collect()];
+    for e in entries {
+        let mut row = vec![e.id.to_string()];
+        // 表格中的多行值仅保留一行
+        let mut texts = e.text_values().into_iter().map(single_line);
+        row.extend(texts.by_ref().take(2));
+        row.push(e.tags.join(","));
+        row.push(e.group.clone().unwrap_or_default());
+        row.push(time(&e.created_time));
+        row.push(time(&e.updated_time));
+        row.extend(texts);
+        rows.push(row);
+    }
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|i| rows.iter().map(|r| r[i].width()).max().unwrap_or_default())
+        .collect();
+    let mut out = String::new();
+    for (i, row) in rows.iter_mut().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let last = row.len() - 1;
+        for (j, cell) in row.iter().enumerate() {
+            out.push_str(cell);
+            if j < last {
+                out.push_str(&" ".repeat(widths[j] - cell.width() + 2));
+            }
+        }
+        row.zeroize();
+    }
+    out
+}
+
+/// 将值中的换行及 tab 替换为空格
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n', '\t'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u32, about: &str, notes: &str) -> ListedEntry {
+        let t = DateTime::from_timestamp(1_700_000_000 + id as i64, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        ListedEntry {
+            id,
+            about: about.to_owned(),
+            notes: Some(notes.to_owned()).filter(|n| !n.is_empty()),
+            tags: Vec::new(),
+            group: None,
+            created_time: t,
+            updated_time: t,
+            username: None,
+            password: None,
+        }
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let mut entries = vec![entry(1, "github", "a\tb\nc\\d"), entry(12, "邮箱", "")];
+        entries[0].tags = vec!["work".to_owned(), "dev".to_owned()];
+        entries[0].group = Some("work/dev".to_owned());
+        let tsv = render(&entries, ListFormat::Tsv, false).unwrap();
+        let lines: Vec<_> = tsv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "id\tabout\tnotes\ttags\tgroup\tcreated_time\tupdated_time");
+        assert!(lines[1].starts_with("1\tgithub\ta\\tb\\nc\\\\d\twork,dev\twork/dev\t"));
+        assert_eq!(lines[2].split('\t').count(), 7);
+
+        let json: serde_json::Value = serde_json::from_str(&render(&entries, ListFormat::Json, false).unwrap()).unwrap();
+        assert_eq!(json[1]["id"], 12);
+        assert_eq!(json[1]["notes"], serde_json::Value::Null);
+        assert_eq!(json[0]["tags"][1], "dev");
+        assert_eq!(json[0]["group"], "work/dev");
+        assert!(json[0].get("password").is_none());
+
+        entries[0].username = Some("alice".to_owned());
+        entries[0].password = Some("pw".to_owned());
+        entries[1].username = Some("bob".to_owned());
+        entries[1].password = Some("pw2".to_owned());
+        let json: serde_json::Value = serde_json::from_str(&render(&entries, ListFormat::Json, true).unwrap()).unwrap();
+        assert_eq!(json[0]["password"], "pw");
+
+        let table = render(&entries, ListFormat::Table, true).unwrap();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("ID  ABOUT"));
+        assert!(lines[1].contains("a b c\\d") && lines[1].ends_with("pw"));
+    }
+}
@@ -0,0 +1,132 @@
+//! 非交互的主密码来源，用于 cron 及测试等无 TTY 的场景
+//!
+//! 各来源依次给出主密码：stdin 及文件按行，环境变量为
+//! [`ENV_MAIN_PASSWORD_KEY`] 及 [`ENV_NEW_MAIN_PASSWORD_KEY`]，
+//! 遂如 mmp 等先校验后设定主密码的流程，第一个为当前主密码，第二个为新主密码
+
+use crate::app::consts::{ENV_MAIN_PASSWORD_KEY, ENV_NEW_MAIN_PASSWORD_KEY};
+use crate::app::errors::AppError;
+use crate::app::secret::SecretString;
+use anyhow::Context;
+use ratatui::crossterm::style::Stylize;
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// 主密码来源
+#[derive(Debug)]
+enum Source {
+    Stdin,
+    File(PathBuf),
+    Env,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Stdin => write!(f, "stdin"),
+            Source::File(path) => write!(f, "'{}'", path.display()),
+            Source::Env => write!(f, "{ENV_MAIN_PASSWORD_KEY} / {ENV_NEW_MAIN_PASSWORD_KEY}"),
+        }
+    }
+}
+
+/// 非交互的主密码来源，给出的主密码无效时调用方应立即失败而非重试
+#[derive(Debug)]
+pub struct ScriptedMainPwd {
+    source: Source,
+    /// 已给出的主密码个数
+    taken: usize,
+    /// 文件来源的各行，首次读取时载入
+    file_lines: Option<VecDeque<SecretString>>,
+}
+
+impl ScriptedMainPwd {
+    /// 按 cli 参数及环境变量确定来源，cli 参数优先，均未给定则 None（即交互输入）
+    ///
+    /// 使用环境变量时提示其可能被其他进程读取
+    pub fn from_args(stdin: bool, file: Option<&Path>) -> Option<Self> {
+        let source = if stdin {
+            Source::Stdin
+        } else if let Some(file) = file {
+            Source::File(file.to_path_buf())
+        } else if std::env::var_os(ENV_MAIN_PASSWORD_KEY).is_some() {
+            let tip = format!(
+                "Warning: reading the main password from {ENV_MAIN_PASSWORD_KEY}, environment variables may be visible to other processes of the same user"
+            );
+            eprintln!("{}", tip.yellow());
+            Source::Env
+        } else {
+            return None;
+        };
+        Some(Self {
+            source,
+            taken: 0,
+            file_lines: None,
+        })
+    }
+
+    /// 是否从 stdin 读取，此时 stdin 不可再用于其他输入
+    pub fn is_stdin(&self) -> bool {
+        matches!(self.source, Source::Stdin)
+    }
+
+    /// 给出下一个主密码，来源已无更多则 Err
+    pub fn next(&mut self) -> anyhow::Result<SecretString> {
+        let next = match &self.source {
+            Source::Stdin => {
+                let mut line = SecretString::default();
+                let n = std::io::stdin().read_line(&mut line)?;
+                (n > 0).then(|| trim_line_end(line))
+            }
+            Source::File(path) => {
+                if self.file_lines.is_none() {
+                    let content = SecretString::from(
+                        std::fs::read_to_string(path)
+                            .with_context(|| format!("Failed to read main password file '{}'", path.display()))?,
+                    );
+                    self.file_lines = Some(
+                        content
+                            .lines()
+                            .map(|l| SecretString::from(l.to_owned()))
+                            .collect(),
+                    );
+                }
+                self.file_lines.as_mut().and_then(VecDeque::pop_front)
+            }
+            Source::Env => {
+                let key = match self.taken {
+                    0 => ENV_MAIN_PASSWORD_KEY,
+                    1 => ENV_NEW_MAIN_PASSWORD_KEY,
+                    _ => "",
+                };
+                std::env::var(key).ok().map(SecretString::from)
+            }
+        };
+        self.taken += 1;
+        Ok(next.ok_or_else(|| AppError::MissingMainPwd(self.source.to_string()))?)
+    }
+}
+
+/// 去除行尾的换行符
+fn trim_line_end(mut line: SecretString) -> SecretString {
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_source_gives_lines_in_order() {
+        let path = std::env::temp_dir().join(format!("pnt-mp-input-test-{}", std::process::id()));
+        std::fs::write(&path, "current pw\r\nnew pw\n").unwrap();
+        let mut input = ScriptedMainPwd::from_args(false, Some(&path)).unwrap();
+        assert_eq!(input.next().unwrap().as_str(), "current pw");
+        assert_eq!(input.next().unwrap().as_str(), "new pw");
+        assert!(input.next().is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
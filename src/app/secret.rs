@@ -0,0 +1,55 @@
+//! 携带秘密（主密码、条目密码、密钥等明文）的类型
+//!
+//! 其内存在 drop 时清零，其 Debug 不输出内容
+
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+use zeroize::Zeroizing;
+
+/// Debug 中替代秘密内容的占位
+pub const REDACTED: &str = "<redacted>";
+
+/// 32 字节的密钥，drop 时清零
+pub type SecretKey = Zeroizing<[u8; 32]>;
+
+/// 秘密字符串，drop 时清零
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct SecretString(Zeroizing<String>);
+
+impl From<String> for SecretString {
+    fn from(s: String) -> Self {
+        Self(Zeroizing::new(s))
+    }
+}
+
+impl Deref for SecretString {
+    type Target = String;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SecretString {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_string_debug_redacted() {
+        let mut secret = SecretString::from("hunter2".to_owned());
+        secret.push('!');
+        assert_eq!(secret.as_str(), "hunter2!");
+        assert!(!format!("{secret:?}").contains("hunter2"));
+    }
+}
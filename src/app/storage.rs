@@ -1,12 +1,18 @@
 use crate::app::context::SecurityContext;
-use crate::app::crypto::{Decrypter, Encrypter};
+use crate::app::crypto::kdf::KdfParams;
+use crate::app::crypto::{Encrypter, MainPwdRecord};
 use crate::app::errors::AppError;
 use anyhow::anyhow;
 use rusqlite::{Connection as sqliteConnection, Connection, Result as SqlResult};
 use std::path::Path;
 
 pub mod entries;
+pub mod groups;
+pub mod history;
 pub mod kv_cfg;
+pub mod migrations;
+pub mod tags;
+pub mod trash;
 
 /// 将 rusqlite::Result<T> 转换为 Option<T>，若查询返回无结果则返回None，若查询返回错误则panic
 fn sql_result_map_to_option<T>(res: SqlResult<T>) -> Option<T> {
@@ -23,35 +29,121 @@ pub struct Storage {
 }
 
 impl Storage {
-    /// 更新主密码，
-    /// 能进入该代码块则旧的主密码已成功验证(因为有old的SecurityContext)，新主密码已加密为b64_s_mph，
+    /// 更新主密码记录及其 KDF 参数与条目加密模式，
+    /// 能进入该代码块则旧的主密码已成功验证(因为有old的SecurityContext)，新主密码已以new_kdf加密为new_record，
     ///
-    /// 该方法会修改所有已知的加密条目的加密为新密码，
+    /// 该方法会修改所有已知的加密条目的加密为新安全上下文的密钥，
     /// 且对db文件进行vacuum操作，移除未使用空间
     /// 这些操作，除了最后的vacuum操作，都在一个事务中进行
     pub fn update_b64_s_mph(
-        &self, new_b64_s_mph: String, old_sec_ctx: SecurityContext, new_sec_ctx: SecurityContext,
+        &self, new_record: &MainPwdRecord, new_kdf: &KdfParams, old_sec_ctx: &SecurityContext,
+        new_sec_ctx: &SecurityContext,
     ) -> anyhow::Result<()> {
         // 1. 遍历所有条目，解密，加密，更新
-        // 2. 更新主密码
+        // 2. 更新主密码记录及 KDF 参数与条目加密模式
         // 3. vacuum操作，移除未使用空间
 
         // 不会嵌套事务，安全 uncheck
         let transaction = self.conn.unchecked_transaction()?;
         // transaction =====================================================
-        let all_ent = self.select_all_entry();
-        self.store_b64_s_mph(&new_b64_s_mph);
-        for ent in all_ent {
-            let id = ent.id;
-            let old_e = old_sec_ctx.decrypt(&ent)?;
-            let new_v_e = new_sec_ctx.encrypt(&old_e)?;
-            self.update_entry(&new_v_e, id);
-        }
+        self.store_main_pwd_record(new_record);
+        self.store_kdf_params(new_kdf);
+        self.store_entry_encrypt_mode(&new_sec_ctx.mode());
+        self.re_encrypt_all_entry(old_sec_ctx, new_sec_ctx)?;
+        transaction.commit()?; // 同步阻塞
+        // transaction =====================================================
+        self.vacuum_db()?; // 同步阻塞
+        Ok(())
+    }
+
+    /// 更新主密码记录及其 KDF 参数，
+    /// 能进入该代码块则已持有数据密钥（主密码或恢复密钥已成功验证），新主密码已以new_kdf包裹该数据密钥为new_record，
+    ///
+    /// 条目以数据密钥加密，遂无需重新加密，
+    /// 该方法会对db文件进行vacuum操作，移除未使用空间（其中可能残留旧的包裹数据密钥）
+    /// 除了最后的vacuum操作，都在一个事务中进行
+    pub fn update_main_pwd(&self, new_record: &MainPwdRecord, new_kdf: &KdfParams) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        self.store_main_pwd_record(new_record);
+        self.store_kdf_params(new_kdf);
         transaction.commit()?; // 同步阻塞
         // transaction =====================================================
         self.vacuum_db()?; // 同步阻塞
         Ok(())
     }
+
+    /// 切换条目加密模式（全加密模式、密文绑定条目、加密算法），
+    /// 能进入该代码块则主密码已成功验证，新旧安全上下文使用相同密钥，仅模式不同，
+    ///
+    /// 该方法会将所有条目以新模式重新加密并记录新模式，
+    /// 且对db文件进行vacuum操作，移除未使用空间（其中可能残留切换前的明文或旧密文）
+    /// 这些操作，除了最后的vacuum操作，都在一个事务中进行
+    pub fn update_entry_encrypt_mode(
+        &self, old_sec_ctx: &SecurityContext, new_sec_ctx: &SecurityContext,
+    ) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        self.store_entry_encrypt_mode(&new_sec_ctx.mode());
+        self.re_encrypt_all_entry(old_sec_ctx, new_sec_ctx)?;
+        transaction.commit()?; // 同步阻塞
+        // transaction =====================================================
+        self.vacuum_db()?; // 同步阻塞
+        Ok(())
+    }
+
+    /// 擦除 data file，删除所有条目及配置（包括主密码记录及被包裹的数据密钥），
+    /// 之后 data file 即未初始化，条目无法再被恢复，
+    /// 表结构并未改变，遂同一事务中重新记录当前结构版本，以免重新打开时重复迁移
+    ///
+    /// 该方法会对db文件进行vacuum操作，移除其中残留的已删除数据
+    pub fn wipe(&self) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        self.conn.execute(r#"DELETE FROM "entry""#, [])?;
+        self.conn.execute(r#"DELETE FROM "entry_tag""#, [])?;
+        self.conn.execute(r#"DELETE FROM "tag""#, [])?;
+        self.conn.execute(r#"DELETE FROM "grp""#, [])?;
+        self.conn.execute(r#"DELETE FROM "entry_history""#, [])?;
+        self.conn.execute(r#"DELETE FROM "cfg""#, [])?;
+        self.store_schema_version(migrations::CURRENT_SCHEMA_VERSION);
+        transaction.commit()?; // 同步阻塞
+        // transaction =====================================================
+        self.vacuum_db()?; // 同步阻塞
+        Ok(())
+    }
+
+    /// 遍历所有条目、条目的历史版本、标签及分组，使用旧安全上下文解密，再使用新安全上下文加密，更新之，
+    /// 该方法不开启事务，应由调用方在事务中调用
+    fn re_encrypt_all_entry(
+        &self, old_sec_ctx: &SecurityContext, new_sec_ctx: &SecurityContext,
+    ) -> anyhow::Result<()> {
+        // 回收站中的条目亦需重新加密
+        let trashed = self.select_all_trashed_entry().into_iter().map(|t| t.entry);
+        for ent in self.select_all_entry().into_iter().chain(trashed) {
+            let old_e = ent.decrypt(&**old_sec_ctx)?;
+            let new_v_e = new_sec_ctx.encrypt((ent.id, &old_e))?;
+            self.update_entry(&new_v_e);
+        }
+        // 历史版本的密文亦与条目id绑定
+        for version in self.select_all_entry_history() {
+            let old_e = version.entry.decrypt(&**old_sec_ctx)?;
+            let new_v_e = new_sec_ctx.encrypt((version.entry.id, &old_e))?;
+            self.update_entry_version(version.id, &new_v_e);
+        }
+        for tag in self.select_all_tag() {
+            let name = old_sec_ctx.decrypt_tag(&tag)?;
+            self.update_tag(&new_sec_ctx.encrypt_tag(tag.id, &name)?);
+        }
+        for group in self.select_all_group() {
+            let name = old_sec_ctx.decrypt_group(&group)?;
+            self.update_group(&new_sec_ctx.encrypt_group(group.id, group.parent, &name)?);
+        }
+        Ok(())
+    }
 }
 
 impl Storage {
@@ -93,11 +185,13 @@ impl Storage {
         }
     }
 
-    /// 指定数据库文件路径，建立连接, 该方法能Ok返回则表一定存在
+    /// 指定数据库文件路径，建立连接, 该方法能Ok返回则表一定存在，
+    /// 且 data file 结构已迁移至当前版本
     pub fn open_file(path: &Path) -> anyhow::Result<Self> {
         let conn = Connection::open(path)?;
         let s = Self { conn };
         s.assert_all_tables_exists()?;
+        s.migrate()?;
         Ok(s)
     }
 
@@ -132,6 +226,52 @@ impl Storage {
         "ct" TEXT NOT NULL DEFAULT (datetime('now', 'localtime')),
         "ut" TEXT NOT NULL DEFAULT (datetime('now', 'localtime')))"#;
 
+    /// 标签表，全加密模式下 name 为密文，遂不以 name 唯一约束
+    const CREATE_TAG_TABLE_SQL: &'static str = r#"
+        CREATE TABLE IF NOT EXISTS "tag" (
+        "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+        "name" TEXT NOT NULL)"#;
+
+    /// 条目与标签的多对多关联表
+    const CREATE_ENTRY_TAG_TABLE_SQL: &'static str = r#"
+        CREATE TABLE IF NOT EXISTS "entry_tag" (
+        "e_id" INTEGER NOT NULL,
+        "t_id" INTEGER NOT NULL,
+        PRIMARY KEY ("e_id", "t_id"))"#;
+
+    /// 分组表（group 为 sql 关键字，遂表名为 grp），parent 为上级分组id，顶级分组为 NULL，
+    /// 同标签表，全加密模式下 name 为密文，遂不以 (parent, name) 唯一约束
+    const CREATE_GROUP_TABLE_SQL: &'static str = r#"
+        CREATE TABLE IF NOT EXISTS "grp" (
+        "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+        "parent" INTEGER,
+        "name" TEXT NOT NULL)"#;
+
+    /// 条目的历史版本表，各列同 entry 表，at 为该版本被替换的时间
+    const CREATE_ENTRY_HISTORY_TABLE_SQL: &'static str = r#"
+        CREATE TABLE IF NOT EXISTS "entry_history" (
+        "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+        "e_id" INTEGER NOT NULL,
+        "about" TEXT NOT NULL,
+        "notes" TEXT,
+        "k" TEXT NOT NULL,
+        "v" TEXT NOT NULL,
+        "ct" TEXT NOT NULL,
+        "ut" TEXT NOT NULL,
+        "at" TEXT NOT NULL DEFAULT (datetime('now', 'localtime')))"#;
+
+    /// 条目所属的分组id，NULL 即不属于任何分组
+    const ADD_ENTRY_GROUP_COLUMN_SQL: &'static str = r#"ALTER TABLE "entry" ADD COLUMN "g_id" INTEGER"#;
+
+    /// 条目移入回收站的时间，NULL 即不在回收站中
+    const ADD_ENTRY_DELETED_TIME_COLUMN_SQL: &'static str = r#"ALTER TABLE "entry" ADD COLUMN "dt" TEXT"#;
+
+    /// 条目的自定义字段，NULL 即没有自定义字段
+    const ADD_ENTRY_FIELDS_COLUMN_SQL: &'static str = r#"ALTER TABLE "entry" ADD COLUMN "f" TEXT"#;
+
+    /// 历史版本的自定义字段，同 entry 表
+    const ADD_ENTRY_HISTORY_FIELDS_COLUMN_SQL: &'static str = r#"ALTER TABLE "entry_history" ADD COLUMN "f" TEXT"#;
+
     /// 断言表 cfg entry 存在，都存在才Ok，该方法应在 [`Storage`] new 阶段调用
     fn assert_all_tables_exists(&self) -> anyhow::Result<()> {
         // 查询 SQLite 系统表以检查表是否存在
@@ -145,10 +285,33 @@ impl Storage {
         }
     }
 
-    /// 若表不存在则创建表
+    /// 若表不存在则创建表，
+    /// 新建的库即从无到有执行所有迁移，遂其结构与旧库迁移后的结构一致
     fn init_tables_if_not_exists(&mut self) -> anyhow::Result<()> {
-        self.conn.execute(Self::CREATE_ENTRY_TABLE_TEMPLATE_SQL, [])?;
-        self.conn.execute(Self::CREATE_INNER_CFG_TABLE_SQL, [])?;
-        Ok(())
+        self.apply_migrations(0, migrations::MIGRATIONS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wipe_then_reopen() {
+        let path = std::env::temp_dir().join(format!("pnt-wipe-test-{}", std::process::id()));
+        let mut s = Storage {
+            conn: Connection::open(&path).unwrap(),
+        };
+        s.init_tables_if_not_exists().unwrap();
+        s.wipe().unwrap();
+        drop(s);
+        // 擦除后表结构仍为当前版本，重新打开不应重复迁移
+        let s = Storage::open_file(&path).unwrap();
+        assert_eq!(
+            s.query_schema_version().unwrap(),
+            Some(migrations::CURRENT_SCHEMA_VERSION)
+        );
+        drop(s);
+        std::fs::remove_file(&path).unwrap();
     }
 }
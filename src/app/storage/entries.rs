@@ -1,11 +1,11 @@
-use crate::app::entry::{EncryptedEntry, ValidEntry};
+use crate::app::entry::{EncryptedEntry, Group, Tag, ValidEntry};
 use crate::app::errors::AppError;
 use crate::app::storage::{Storage, sql_result_map_to_option};
 use chrono::{DateTime, Local};
 use rusqlite::{Result as SqlResult, Row, params};
 
-/// 将 Row 转换为 Entry
-fn row_map_entry(row: &Row) -> SqlResult<EncryptedEntry> {
+/// 将 Row 转换为 Entry，其前8列依次为 id、about、notes、k、v、ct、ut、f
+pub(super) fn row_map_entry(row: &Row) -> SqlResult<EncryptedEntry> {
     let id: u32 = row.get(0)?;
     let about: String = row.get(1)?;
     let notes: Option<String> = row.get(2)?;
@@ -13,6 +13,7 @@ fn row_map_entry(row: &Row) -> SqlResult<EncryptedEntry> {
     let encrypted_password: String = row.get(4)?;
     let created_time: DateTime<Local> = row.get(5)?;
     let updated_time: DateTime<Local> = row.get(6)?;
+    let fields: Option<String> = row.get(7)?;
     Ok(EncryptedEntry {
         id,
         about,
@@ -21,34 +22,54 @@ fn row_map_entry(row: &Row) -> SqlResult<EncryptedEntry> {
         encrypted_password,
         created_time,
         updated_time,
+        tags: Vec::new(),
+        group: None,
+        fields,
     })
 }
 
 impl Storage {
     /// 模板-插入密码的 Sqlite 语句
-    const INSERT_ENTRY_SQL: &'static str = r#"INSERT INTO "entry" ("about", "notes", "k", "v") VALUES (?, ?, ?, ?)"#;
+    const INSERT_ENTRY_SQL: &'static str =
+        r#"INSERT INTO "entry" ("id", "about", "notes", "k", "v", "f") VALUES (?, ?, ?, ?, ?, ?)"#;
     /// 模板-更新实体的 Sqlite 语句
-    const UPDATE_ENTRY_SQL: &'static str =
-        r#"UPDATE "entry" SET "about"=?, "notes"=?, "k"=?, "v"=?, "ut"=datetime('now', 'localtime') WHERE "id"=?"#;
+    const UPDATE_ENTRY_SQL: &'static str = r#"UPDATE "entry" SET "about"=?, "notes"=?, "k"=?, "v"=?, "f"=?,
+        "ut"=datetime('now', 'localtime') WHERE "id"=?"#;
+    /// 模板-查询条目的 Sqlite 语句，列顺序见 [`row_map_entry`]
+    const SELECT_ENTRY_SQL: &'static str =
+        r#"SELECT "id", "about", "notes", "k", "v", "ct", "ut", "f" FROM "entry""#;
     /// 模板-删除实体的 Sqlite 语句
     const DELETE_ENTRY_SQL: &'static str = r#"DELETE FROM "entry" WHERE "id"=?"#;
 
-    /// 插入一条密码记录
+    /// 模板-下一个条目id 的 Sqlite 语句，entry 表 id 为 AUTOINCREMENT，已删除条目的 id 不会被复用
+    const NEXT_ENTRY_ID_SQL: &'static str =
+        r#"SELECT COALESCE((SELECT "seq" FROM "sqlite_sequence" WHERE "name"='entry'), 0) + 1"#;
+
+    /// 分配新建条目的id，条目密文与id绑定，遂须在加密前分配
+    pub fn next_entry_id(&self) -> u32 {
+        let r = self.conn.query_row(Self::NEXT_ENTRY_ID_SQL, [], |row| row.get(0));
+        sql_result_map_to_option(r).unwrap_or_else(|| panic!("{}", AppError::DataCorrupted))
+    }
+
+    /// 插入一条密码记录，其 id 应由 [`Storage::next_entry_id`] 分配
     pub fn insert_entry(&self, insert_entry: &ValidEntry) {
         self.conn
             .execute(
                 Self::INSERT_ENTRY_SQL,
                 params![
+                    insert_entry.id,
                     insert_entry.about,
                     insert_entry.notes,
                     insert_entry.encrypted_username,
                     insert_entry.encrypted_password,
+                    insert_entry.fields,
                 ],
             )
             .expect("Failed to insert entry");
     }
-    /// 更新一条密码记录
-    pub fn update_entry(&self, update_entry: &ValidEntry, id: u32) {
+    /// 更新一条密码记录，不归档更新前的版本，用于重新加密，
+    /// 用户的修改见 [`Storage::update_entry_keep_history`]
+    pub fn update_entry(&self, update_entry: &ValidEntry) {
         self.conn
             .execute(
                 Self::UPDATE_ENTRY_SQL,
@@ -57,40 +78,87 @@ impl Storage {
                     update_entry.notes,
                     update_entry.encrypted_username,
                     update_entry.encrypted_password,
-                    id // where
+                    update_entry.fields,
+                    update_entry.id // where
                 ],
             )
             .expect("Failed to update entry");
     }
 
-    /// 删除一条密码记录
+    /// 插入用户新建的条目，并设定其标签及分组（参数见 [`Storage::store_entry_tags`]、
+    /// [`Storage::store_entry_group`]），这些操作在一个事务中进行
+    pub fn insert_entry_with_tags_and_group(
+        &self, insert_entry: &ValidEntry, new_tags: &[Tag], t_ids: &[u32], new_groups: &[Group], g_id: Option<u32>,
+    ) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        self.insert_entry(insert_entry);
+        self.replace_entry_tags(insert_entry.id, new_tags, t_ids)?;
+        self.replace_entry_group(insert_entry.id, new_groups, g_id)?;
+        transaction.commit()?;
+        // transaction =====================================================
+        Ok(())
+    }
+
+    /// 同 [`Storage::update_entry_keep_history`]，并设定条目的标签及分组，这些操作在一个事务中进行
+    pub fn update_entry_with_tags_and_group(
+        &self, update_entry: &ValidEntry, keep: u32, new_tags: &[Tag], t_ids: &[u32], new_groups: &[Group],
+        g_id: Option<u32>,
+    ) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        self.archive_and_update_entry(update_entry, keep)?;
+        self.replace_entry_tags(update_entry.id, new_tags, t_ids)?;
+        self.replace_entry_group(update_entry.id, new_groups, g_id)?;
+        transaction.commit()?;
+        // transaction =====================================================
+        Ok(())
+    }
+
+    /// 永久删除一条密码记录及其历史版本、标签关联，及因此不再使用的分组，
+    /// 用户的删除先移入回收站，见 [`Storage::trash_entry`]
     pub fn delete_entry(&self, entry_id: u32) {
         self.conn
             .execute(Self::DELETE_ENTRY_SQL, params![entry_id])
             .expect("Failed to delete entry");
+        self.delete_entry_history(entry_id);
+        self.delete_entry_tags(entry_id);
+        self.delete_unused_group().expect("Failed to delete group");
     }
-    /// 通过id查询一条密码记录
+    /// 通过id查询一条密码记录，回收站中的除外
     pub fn select_entry_by_id(&self, id: u32) -> Option<EncryptedEntry> {
-        let r = self
-            .conn
-            .query_one("SELECT * FROM entry WHERE id = ?", params![id], row_map_entry);
+        let sql = format!(r#"{} WHERE "id"=? AND "dt" IS NULL"#, Self::SELECT_ENTRY_SQL);
+        let r = self.conn.query_one(&sql, params![id], row_map_entry);
         sql_result_map_to_option(r)
     }
-    /// 通过about模糊查询
+    /// 通过about模糊查询，回收站中的除外，
+    /// `like` 按字面匹配，其中的 `%`、`_` 及 `\` 即转义，不作通配符
     pub fn select_entry_by_about_like(&self, like: &str) -> Vec<EncryptedEntry> {
-        let nl = format!("%{}%", like.to_ascii_lowercase()); // 左右
-        let mut stmt = self.conn.prepare("SELECT * FROM entry WHERE LOWER(about) LIKE ?").unwrap();
+        let escaped = like
+            .to_ascii_lowercase()
+            .replace('\\', r"\\")
+            .replace('%', r"\%")
+            .replace('_', r"\_");
+        let nl = format!("%{escaped}%"); // 左右
+        let sql = format!(
+            r#"{} WHERE LOWER("about") LIKE ? ESCAPE '\' AND "dt" IS NULL"#,
+            Self::SELECT_ENTRY_SQL
+        );
+        let mut stmt = self.conn.prepare(&sql).unwrap();
         let rows = stmt.query_map([nl], row_map_entry).expect("Failed to select entry");
         rows.filter_map(sql_result_map_to_option).collect()
     }
-    /// 查询所有entry
+    /// 查询所有entry，回收站中的除外
     pub fn select_all_entry(&self) -> Vec<EncryptedEntry> {
-        let mut stmt = self.conn.prepare("SELECT * FROM entry").unwrap();
+        let sql = format!(r#"{} WHERE "dt" IS NULL"#, Self::SELECT_ENTRY_SQL);
+        let mut stmt = self.conn.prepare(&sql).unwrap();
         let rows = stmt.query_map([], row_map_entry).expect("Failed to select entry");
         rows.filter_map(sql_result_map_to_option).collect()
     }
 
-    /// 查询entry数量
+    /// 查询entry数量，包括回收站中的
     pub fn select_entry_count(&self) -> u32 {
         let r = self.conn.query_row("SELECT COUNT(*) FROM entry", [], |row| row.get(0));
         sql_result_map_to_option(r).unwrap_or_else(|| panic!("{}", AppError::DataCorrupted)) // 一定有值，因为表已初始化，若无则说明被破坏，直接panic
@@ -101,18 +169,21 @@ impl Storage {
 mod tests {
     use super::*;
     use crate::app::storage::Storage;
+    use chrono::Timelike;
     #[test]
     fn test_db() {
         let db = Storage::open_in_memory().unwrap();
         let insert_e = ValidEntry {
+            id: db.next_entry_id(),
             about: String::from("test"),
             notes: None,
             encrypted_username: String::from("test"),
             encrypted_password: String::from("test"),
+            fields: None,
         };
 
         // 精确到秒可能无意义
-        let now: DateTime<Local> = DateTime::from(Local::now());
+        let now: DateTime<Local> = Local::now().with_nanosecond(0).unwrap();
         // select
         db.insert_entry(&insert_e); // append ct, ut
         let vec = db.select_all_entry();
@@ -131,12 +202,14 @@ mod tests {
         other_entry.notes = Some(String::from("test"));
         let upd_entry = other_entry.clone();
         let v_e = ValidEntry {
+            id: upd_entry.id,
             about: upd_entry.about,
             notes: upd_entry.notes,
             encrypted_username: upd_entry.encrypted_username,
             encrypted_password: upd_entry.encrypted_password,
+            fields: Some(String::from("[]")),
         };
-        db.update_entry(&v_e, other_entry.id);
+        db.update_entry(&v_e);
         let after_update_query_by_id_one = db.select_entry_by_id(entry.id);
         assert!(after_update_query_by_id_one.is_some());
         let after_update = after_update_query_by_id_one.unwrap();
@@ -147,11 +220,16 @@ mod tests {
         assert_eq!(after_update.created_time, other_entry.created_time);
         assert!(after_update.updated_time >= now);
         assert_ne!(after_update.notes, entry.notes);
+        assert_eq!(after_update.fields.as_deref(), Some("[]"));
         // assert delete
+        let insert_e = ValidEntry {
+            id: db.next_entry_id(),
+            ..insert_e
+        };
         db.insert_entry(&insert_e);
         let vec2 = db.select_all_entry();
         assert_eq!(vec2.len(), 2);
-        assert_ne!(vec2.get(0).unwrap().id, vec2.get(1).unwrap().id);
+        assert_ne!(vec2.first().unwrap().id, vec2.get(1).unwrap().id);
 
         let mut db_count = vec2.len();
         for x in &vec2 {
@@ -160,5 +238,56 @@ mod tests {
             assert_eq!(vec3.len(), db_count - 1);
             db_count -= 1;
         }
+        // 已删除条目的 id 不复用
+        assert!(db.next_entry_id() > insert_e.id);
+    }
+
+    #[test]
+    fn test_select_entry_by_about_like_literal() {
+        let db = Storage::open_in_memory().unwrap();
+        for about in ["100% off", "1000 off", "a_b", "axb", r"c\d"] {
+            db.insert_entry(&ValidEntry {
+                id: db.next_entry_id(),
+                about: String::from(about),
+                notes: None,
+                encrypted_username: String::from("test"),
+                encrypted_password: String::from("test"),
+                fields: None,
+            });
+        }
+        let abouts = |like: &str| -> Vec<String> {
+            db.select_entry_by_about_like(like).into_iter().map(|e| e.about).collect()
+        };
+        assert_eq!(abouts("0%"), vec!["100% off"]);
+        assert_eq!(abouts("a_"), vec!["a_b"]);
+        assert_eq!(abouts(r"c\"), vec![r"c\d"]);
+        assert_eq!(abouts("OFF").len(), 2);
+    }
+
+    #[test]
+    fn test_insert_entry_with_tags_and_group_atomic() {
+        let db = Storage::open_in_memory().unwrap();
+        let insert_e = ValidEntry {
+            id: db.next_entry_id(),
+            about: String::from("test"),
+            notes: None,
+            encrypted_username: String::from("test"),
+            encrypted_password: String::from("test"),
+            fields: None,
+        };
+        let tags = [Tag { id: db.next_tag_id(), name: String::from("work") }];
+        let t_ids = [tags[0].id];
+        let groups = [Group { id: db.next_group_id(), parent: None, name: String::from("work") }];
+        let g_id = Some(groups[0].id);
+        // 分组id重复即插入失败，已插入的条目及标签亦须回滚
+        let dup = [groups[0].clone(), groups[0].clone()];
+        assert!(db.insert_entry_with_tags_and_group(&insert_e, &tags, &t_ids, &dup, g_id).is_err());
+        assert!(db.select_all_entry().is_empty());
+        assert!(db.select_all_tag().is_empty());
+        assert!(db.select_all_group().is_empty());
+
+        db.insert_entry_with_tags_and_group(&insert_e, &tags, &t_ids, &groups, g_id).unwrap();
+        assert_eq!(db.select_entry_tags(insert_e.id).len(), 1);
+        assert_eq!(db.select_all_entry_group(), vec![(insert_e.id, groups[0].id)]);
     }
 }
@@ -0,0 +1,131 @@
+use crate::app::entry::Group;
+use crate::app::errors::AppError;
+use crate::app::storage::{Storage, sql_result_map_to_option};
+use rusqlite::{Result as SqlResult, Row, params};
+
+/// 将 Row 转换为 Group
+fn row_map_group(row: &Row) -> SqlResult<Group> {
+    Ok(Group {
+        id: row.get(0)?,
+        parent: row.get(1)?,
+        name: row.get(2)?,
+    })
+}
+
+impl Storage {
+    /// 模板-下一个分组id 的 Sqlite 语句，grp 表 id 为 AUTOINCREMENT，已删除分组的 id 不会被复用
+    const NEXT_GROUP_ID_SQL: &'static str =
+        r#"SELECT COALESCE((SELECT "seq" FROM "sqlite_sequence" WHERE "name"='grp'), 0) + 1"#;
+    /// 模板-删除既无条目也无下级分组的分组的 Sqlite 语句
+    const DELETE_UNUSED_GROUP_SQL: &'static str = r#"
+        DELETE FROM "grp"
+        WHERE "id" NOT IN (SELECT "g_id" FROM "entry" WHERE "g_id" IS NOT NULL)
+        AND "id" NOT IN (SELECT "parent" FROM "grp" WHERE "parent" IS NOT NULL)"#;
+
+    /// 分配新建分组的id，全加密模式下分组名密文与id绑定，遂须在加密前分配，
+    /// 同时新建多个分组时，其余的id依次递增
+    pub fn next_group_id(&self) -> u32 {
+        let r = self.conn.query_row(Self::NEXT_GROUP_ID_SQL, [], |row| row.get(0));
+        sql_result_map_to_option(r).unwrap_or_else(|| panic!("{}", AppError::DataCorrupted))
+    }
+
+    /// 查询所有分组
+    pub fn select_all_group(&self) -> Vec<Group> {
+        let mut stmt = self
+            .conn
+            .prepare(r#"SELECT "id", "parent", "name" FROM "grp""#)
+            .unwrap();
+        let rows = stmt.query_map([], row_map_group).expect("Failed to select group");
+        rows.filter_map(sql_result_map_to_option).collect()
+    }
+
+    /// 查询所有属于某分组的条目，返回 (条目id, 分组id)
+    pub fn select_all_entry_group(&self) -> Vec<(u32, u32)> {
+        let mut stmt = self
+            .conn
+            .prepare(r#"SELECT "id", "g_id" FROM "entry" WHERE "g_id" IS NOT NULL"#)
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("Failed to select entry group");
+        rows.filter_map(sql_result_map_to_option).collect()
+    }
+
+    /// 更新一个分组的名称
+    pub fn update_group(&self, group: &Group) {
+        self.conn
+            .execute(r#"UPDATE "grp" SET "name"=? WHERE "id"=?"#, params![group.name, group.id])
+            .expect("Failed to update group");
+    }
+
+    /// 将条目移至给定分组，`g_id` 为 None 即移出所有分组，
+    /// `new_groups` 为需新建的分组（其id应由 [`Storage::next_group_id`] 分配，上级分组在前），
+    /// 既无条目也无下级分组的分组即被删除，这些操作在一个事务中进行
+    pub fn store_entry_group(&self, e_id: u32, new_groups: &[Group], g_id: Option<u32>) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        self.replace_entry_group(e_id, new_groups, g_id)?;
+        transaction.commit()?;
+        // transaction =====================================================
+        Ok(())
+    }
+
+    /// 同 [`Storage::store_entry_group`]，该方法不开启事务，应由调用方在事务中调用
+    pub(super) fn replace_entry_group(&self, e_id: u32, new_groups: &[Group], g_id: Option<u32>) -> SqlResult<()> {
+        for group in new_groups {
+            self.conn.execute(
+                r#"INSERT INTO "grp" ("id", "parent", "name") VALUES (?, ?, ?)"#,
+                params![group.id, group.parent, group.name],
+            )?;
+        }
+        self.conn
+            .execute(r#"UPDATE "entry" SET "g_id"=? WHERE "id"=?"#, params![g_id, e_id])?;
+        self.delete_unused_group()
+    }
+
+    /// 删除既无条目也无下级分组的分组，
+    /// 删除一个分组可能使其上级分组也不再使用，遂重复至没有可删除的分组
+    pub(super) fn delete_unused_group(&self) -> SqlResult<()> {
+        while self.conn.execute(Self::DELETE_UNUSED_GROUP_SQL, [])? > 0 {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::entry::ValidEntry;
+
+    #[test]
+    fn test_entry_group() {
+        let db = Storage::open_in_memory().unwrap();
+        for _ in 0..2 {
+            db.insert_entry(&ValidEntry {
+                id: db.next_entry_id(),
+                about: String::from("test"),
+                notes: None,
+                encrypted_username: String::from("test"),
+                encrypted_password: String::from("test"),
+                fields: None,
+            });
+        }
+        // work/aws
+        let g1 = db.next_group_id();
+        let work = Group { id: g1, parent: None, name: String::from("work") };
+        let aws = Group { id: g1 + 1, parent: Some(g1), name: String::from("aws") };
+        db.store_entry_group(1, &[work.clone(), aws.clone()], Some(aws.id)).unwrap();
+        db.store_entry_group(2, &[], Some(work.id)).unwrap();
+        assert_eq!(db.select_all_entry_group(), vec![(1, aws.id), (2, work.id)]);
+        assert_eq!(db.next_group_id(), g1 + 2);
+
+        // 上级分组仍有下级分组，不被删除
+        db.store_entry_group(2, &[], None).unwrap();
+        assert_eq!(db.select_all_group().len(), 2);
+        // 下级分组不再使用，其上级分组亦被删除
+        db.delete_entry(1);
+        assert!(db.select_all_group().is_empty());
+        assert!(db.select_all_entry_group().is_empty());
+        assert_eq!(db.next_group_id(), g1 + 2);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::app::entry::{EncryptedEntry, EntryVersion, ValidEntry};
+use crate::app::storage::{Storage, sql_result_map_to_option};
+use rusqlite::{Result as SqlResult, Row, params};
+
+/// 将 Row 转换为 EntryVersion
+fn row_map_version(row: &Row) -> SqlResult<EntryVersion> {
+    Ok(EntryVersion {
+        id: row.get(0)?,
+        entry: EncryptedEntry {
+            id: row.get(1)?,
+            about: row.get(2)?,
+            notes: row.get(3)?,
+            encrypted_username: row.get(4)?,
+            encrypted_password: row.get(5)?,
+            created_time: row.get(6)?,
+            updated_time: row.get(7)?,
+            tags: Vec::new(),
+            group: None,
+            fields: row.get(8)?,
+        },
+        archived_time: row.get(9)?,
+    })
+}
+
+impl Storage {
+    /// 模板-查询历史版本的 Sqlite 语句，新的版本在前
+    const SELECT_ENTRY_HISTORY_SQL: &'static str = r#"
+        SELECT "id", "e_id", "about", "notes", "k", "v", "ct", "ut", "f", "at"
+        FROM "entry_history""#;
+    /// 模板-将条目的当前版本归档至历史版本的 Sqlite 语句
+    const ARCHIVE_ENTRY_SQL: &'static str = r#"
+        INSERT INTO "entry_history" ("e_id", "about", "notes", "k", "v", "ct", "ut", "f")
+        SELECT "id", "about", "notes", "k", "v", "ct", "ut", "f" FROM "entry" WHERE "id"=?"#;
+    /// 模板-以历史版本覆盖条目当前版本的 Sqlite 语句，其密文与条目id绑定，遂可直接复制
+    const RESTORE_ENTRY_SQL: &'static str = r#"
+        UPDATE "entry" SET ("about", "notes", "k", "v", "f") =
+        (SELECT "about", "notes", "k", "v", "f" FROM "entry_history" WHERE "id"=?1),
+        "ut"=datetime('now', 'localtime')
+        WHERE "id"=?2"#;
+    /// 模板-每个条目仅保留最新的若干历史版本的 Sqlite 语句
+    const PRUNE_ENTRY_HISTORY_SQL: &'static str = r#"
+        DELETE FROM "entry_history" WHERE "id" IN (
+        SELECT "id" FROM (
+        SELECT "id", ROW_NUMBER() OVER (PARTITION BY "e_id" ORDER BY "id" DESC) AS "n" FROM "entry_history")
+        WHERE "n" > ?)"#;
+
+    /// 更新一条密码记录，其更新前的版本归档为历史版本，
+    /// 每个条目仅保留最新的 `keep` 个历史版本，`keep` 为 0 即不保留，
+    /// 这些操作在一个事务中进行
+    pub fn update_entry_keep_history(&self, update_entry: &ValidEntry, keep: u32) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        self.archive_and_update_entry(update_entry, keep)?;
+        transaction.commit()?;
+        // transaction =====================================================
+        Ok(())
+    }
+
+    /// 同 [`Storage::update_entry_keep_history`]，该方法不开启事务，应由调用方在事务中调用
+    pub(super) fn archive_and_update_entry(&self, update_entry: &ValidEntry, keep: u32) -> SqlResult<()> {
+        if keep > 0 {
+            self.conn.execute(Self::ARCHIVE_ENTRY_SQL, params![update_entry.id])?;
+        }
+        self.update_entry(update_entry);
+        self.prune_entry_history(keep)
+    }
+
+    /// 查询一个条目的所有历史版本，新的版本在前
+    pub fn select_entry_history(&self, e_id: u32) -> Vec<EntryVersion> {
+        let sql = format!(r#"{} WHERE "e_id"=? ORDER BY "id" DESC"#, Self::SELECT_ENTRY_HISTORY_SQL);
+        let mut stmt = self.conn.prepare(&sql).unwrap();
+        let rows = stmt
+            .query_map([e_id], row_map_version)
+            .expect("Failed to select entry history");
+        rows.filter_map(sql_result_map_to_option).collect()
+    }
+
+    /// 查询所有条目的所有历史版本
+    pub fn select_all_entry_history(&self) -> Vec<EntryVersion> {
+        let mut stmt = self.conn.prepare(Self::SELECT_ENTRY_HISTORY_SQL).unwrap();
+        let rows = stmt
+            .query_map([], row_map_version)
+            .expect("Failed to select entry history");
+        rows.filter_map(sql_result_map_to_option).collect()
+    }
+
+    /// 以重新加密的密文更新一个历史版本，其 id 为历史版本的id
+    pub fn update_entry_version(&self, h_id: u32, version: &ValidEntry) {
+        self.conn
+            .execute(
+                r#"UPDATE "entry_history" SET "about"=?, "notes"=?, "k"=?, "v"=?, "f"=? WHERE "id"=?"#,
+                params![
+                    version.about,
+                    version.notes,
+                    version.encrypted_username,
+                    version.encrypted_password,
+                    version.fields,
+                    h_id
+                ],
+            )
+            .expect("Failed to update entry history");
+    }
+
+    /// 将条目恢复为给定的历史版本，
+    /// 条目的当前版本归档为历史版本（遂恢复亦可撤销），被恢复的历史版本即删除，
+    /// 每个条目仅保留最新的 `keep` 个历史版本，这些操作在一个事务中进行，
+    /// 返回所属条目的id，历史版本不存在则 None
+    pub fn restore_entry_version(&self, h_id: u32, keep: u32) -> anyhow::Result<Option<u32>> {
+        let r = self.conn.query_row(
+            r#"SELECT "e_id" FROM "entry_history" WHERE "id"=?"#,
+            [h_id],
+            |row| row.get(0),
+        );
+        let Some(e_id) = sql_result_map_to_option(r) else {
+            return Ok(None);
+        };
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        if keep > 0 {
+            self.conn.execute(Self::ARCHIVE_ENTRY_SQL, params![e_id])?;
+        }
+        self.conn.execute(Self::RESTORE_ENTRY_SQL, params![h_id, e_id])?;
+        self.conn
+            .execute(r#"DELETE FROM "entry_history" WHERE "id"=?"#, params![h_id])?;
+        self.prune_entry_history(keep)?;
+        transaction.commit()?;
+        // transaction =====================================================
+        Ok(Some(e_id))
+    }
+
+    /// 每个条目仅保留最新的 `keep` 个历史版本，删除其余的
+    pub fn prune_entry_history(&self, keep: u32) -> SqlResult<()> {
+        self.conn.execute(Self::PRUNE_ENTRY_HISTORY_SQL, params![keep])?;
+        Ok(())
+    }
+
+    /// 删除条目的所有历史版本
+    pub(super) fn delete_entry_history(&self, e_id: u32) {
+        self.conn
+            .execute(r#"DELETE FROM "entry_history" WHERE "e_id"=?"#, params![e_id])
+            .expect("Failed to delete entry history");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_entry(id: u32, password: &str) -> ValidEntry {
+        ValidEntry {
+            id,
+            about: String::from("test"),
+            notes: None,
+            encrypted_username: String::from("test"),
+            encrypted_password: password.to_owned(),
+            fields: None,
+        }
+    }
+
+    #[test]
+    fn test_entry_history() {
+        let db = Storage::open_in_memory().unwrap();
+        let e_id = db.next_entry_id();
+        db.insert_entry(&valid_entry(e_id, "p0"));
+        for i in 1..=4 {
+            db.update_entry_keep_history(&valid_entry(e_id, &format!("p{i}")), 3).unwrap();
+        }
+        // 仅保留最新的3个，新的在前
+        let history = db.select_entry_history(e_id);
+        let passwords: Vec<_> = history.iter().map(|v| v.entry.encrypted_password.as_str()).collect();
+        assert_eq!(passwords, ["p3", "p2", "p1"]);
+        assert!(history.iter().all(|v| v.entry.id == e_id));
+
+        // 恢复 p2，当前的 p4 被归档
+        assert_eq!(db.restore_entry_version(history[1].id, 3).unwrap(), Some(e_id));
+        assert_eq!(db.select_entry_by_id(e_id).unwrap().encrypted_password, "p2");
+        let passwords: Vec<_> = db
+            .select_entry_history(e_id)
+            .into_iter()
+            .map(|v| v.entry.encrypted_password)
+            .collect();
+        assert_eq!(passwords, ["p4", "p3", "p1"]);
+        assert_eq!(db.restore_entry_version(history[1].id, 3).unwrap(), None);
+
+        // 不保留历史版本
+        db.update_entry_keep_history(&valid_entry(e_id, "p5"), 0).unwrap();
+        assert!(db.select_entry_history(e_id).is_empty());
+        db.update_entry_keep_history(&valid_entry(e_id, "p6"), 3).unwrap();
+        db.delete_entry(e_id);
+        assert!(db.select_all_entry_history().is_empty());
+    }
+}
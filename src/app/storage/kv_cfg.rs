@@ -1,4 +1,11 @@
+use crate::app::consts::{DEFAULT_HISTORY_KEEP, DEFAULT_TRASH_PURGE_DAYS};
+use crate::app::crypto::MainPwdRecord;
+use crate::app::crypto::entry_secret::EntryEncryptMode;
+use crate::app::crypto::envelope::CipherAlg;
+use crate::app::crypto::kdf::KdfParams;
 use crate::app::errors::AppError;
+use crate::app::failed_unlock::FailedUnlock;
+use crate::app::generator::PasswordPolicy;
 use crate::app::storage::{Storage, sql_result_map_to_option};
 use anyhow::Context;
 use bitflags::bitflags;
@@ -9,6 +16,10 @@ bitflags! {
         /// 运行立即需要密码
         const VERIFY_ON_LAUNCH = 0b0000_0001;
         const IMMEDIATE_LOCK_SCREEN = 0b0000_0010;
+        /// 主密码校验失败次数达到上限时擦除 data file（否则仅锁定）
+        const WIPE_ON_MAX_FAILED_UNLOCK = 0b0000_0100;
+        /// 详情及编辑页面打开时即显示密码明文（否则掩码）
+        const REVEAL_PASSWORD = 0b0000_1000;
         const _ = 0b1000_0000;
         // ... 预留其他
     }
@@ -23,9 +34,14 @@ impl Storage {
     }
 
     /// 存储给定的新值盐-主密码，存在则更新，不存在则插入
-    pub fn store_b64_s_mph(&self, b64_salt_mph: &str) {
+    fn store_b64_s_mph(&self, b64_salt_mph: &str) {
         self.save_cfg(Self::KV_CFG_MAIN_PASS_KEY, b64_salt_mph)
     }
+    /// 存储主密码记录，即盐-主密码及被主密钥包裹的数据密钥，二者须一同变更
+    pub fn store_main_pwd_record(&self, record: &MainPwdRecord) {
+        self.store_b64_s_mph(&record.b64_s_mph);
+        self.store_wrapped_data_key(&record.wrapped_key);
+    }
     /// 检查是否未init mph
     ///
     /// 没有mph但有条目：这种情况说明数据文件被人为手动修改，非法情况，Err
@@ -41,6 +57,158 @@ impl Storage {
         }
     }
 
+    /// 主密码 KDF 算法及参数
+    const KV_CFG_KDF_KEY: &'static str = "kdf";
+    /// 查找主密码 KDF 参数，不存在即为默认参数（早于该配置的 data file 均使用默认参数）
+    ///
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_kdf_params(&self) -> Result<KdfParams, AppError> {
+        self.select_cfg_v_by_key(Self::KV_CFG_KDF_KEY)
+            .map_or(Ok(KdfParams::default()), |s| s.parse())
+    }
+    /// 存储主密码 KDF 参数，
+    /// 该值须与 mp 一同变更，见 [`Storage::update_b64_s_mph`]
+    pub fn store_kdf_params(&self, kdf: &KdfParams) {
+        self.save_cfg(Self::KV_CFG_KDF_KEY, &kdf.to_string())
+    }
+
+    /// 被主密钥包裹的数据密钥
+    const WRAPPED_DATA_KEY_KEY: &'static str = "wk";
+    /// 查找被主密钥包裹的数据密钥，不存在即早于数据密钥的 data file（条目以主密钥派生的子密钥加密）
+    pub fn query_wrapped_data_key(&self) -> Option<String> {
+        self.select_cfg_v_by_key(Self::WRAPPED_DATA_KEY_KEY)
+    }
+    /// 存储被主密钥包裹的数据密钥，
+    /// 该值须与 mp 一同变更，见 [`Storage::store_main_pwd_record`]
+    fn store_wrapped_data_key(&self, wrapped_key: &str) {
+        self.save_cfg(Self::WRAPPED_DATA_KEY_KEY, wrapped_key)
+    }
+
+    /// 被恢复密钥包裹的数据密钥
+    const RECOVERY_WRAPPED_DATA_KEY_KEY: &'static str = "rk";
+    /// 查找被恢复密钥包裹的数据密钥，不存在即 data file 没有恢复密钥
+    pub fn query_recovery_wrapped_data_key(&self) -> Option<String> {
+        self.select_cfg_v_by_key(Self::RECOVERY_WRAPPED_DATA_KEY_KEY)
+    }
+    /// 存储被恢复密钥包裹的数据密钥，存在则替换（即旧恢复密钥失效）
+    pub fn store_recovery_wrapped_data_key(&self, wrapped_key: &str) {
+        self.save_cfg(Self::RECOVERY_WRAPPED_DATA_KEY_KEY, wrapped_key)
+    }
+
+    /// 密钥文件标志，存在即表示主密钥派生需密钥文件
+    const KEYFILE_KEY: &'static str = "kf";
+    /// 查找 data file 是否要求密钥文件，不存在即为false
+    ///
+    /// 若人为修改db文件导致值非预期，则Err报告数据已损坏
+    pub fn query_keyfile_required(&self) -> Result<bool, AppError> {
+        match self.select_cfg_v_by_key(Self::KEYFILE_KEY).as_deref() {
+            None => Ok(false),
+            Some("1") => Ok(true),
+            Some(_) => Err(AppError::DataCorrupted),
+        }
+    }
+    /// 存储要求密钥文件标志，仅于 init 时存储，因为false为默认值，遂走delete逻辑
+    pub fn store_keyfile_required(&self, keyfile_required: bool) {
+        if keyfile_required {
+            self.save_cfg(Self::KEYFILE_KEY, "1")
+        } else {
+            self.delete_cfg(Self::KEYFILE_KEY)
+        }
+    }
+
+    /// data file 结构版本
+    const KV_CFG_SCHEMA_VERSION_KEY: &'static str = "sv";
+    /// 查找 data file 结构版本，不存在则返回Ok(None)（即早于结构版本化的 data file）
+    ///
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_schema_version(&self) -> anyhow::Result<Option<u32>> {
+        self.select_cfg_v_by_key(Self::KV_CFG_SCHEMA_VERSION_KEY)
+            .map(|s| s.parse::<u32>())
+            .transpose()
+            .with_context(|| AppError::DataCorrupted)
+    }
+    /// 存储 data file 结构版本
+    pub fn store_schema_version(&self, version: u32) {
+        self.save_cfg(Self::KV_CFG_SCHEMA_VERSION_KEY, &version.to_string())
+    }
+
+    /// 全加密模式标志，存在即表示 about 及 notes 亦加密
+    const FULL_ENCRYPT_KEY: &'static str = "fe";
+    /// 查找 data file 是否为全加密模式，不存在即为false
+    ///
+    /// 若人为修改db文件导致值非预期，则Err报告数据已损坏
+    pub fn query_full_encrypt(&self) -> Result<bool, AppError> {
+        match self.select_cfg_v_by_key(Self::FULL_ENCRYPT_KEY).as_deref() {
+            None => Ok(false),
+            Some("1") => Ok(true),
+            Some(_) => Err(AppError::DataCorrupted),
+        }
+    }
+    /// 存储全加密模式标志，因为false为默认值，遂走delete逻辑
+    fn store_full_encrypt(&self, full_encrypt: bool) {
+        if full_encrypt {
+            self.save_cfg(Self::FULL_ENCRYPT_KEY, "1")
+        } else {
+            self.delete_cfg(Self::FULL_ENCRYPT_KEY)
+        }
+    }
+
+    /// 条目密文绑定标志，存在即表示所有密文均以条目 id 及字段标签为关联数据
+    const ENTRY_BOUND_KEY: &'static str = "ead";
+    /// 查找 data file 的条目密文是否已绑定条目，不存在即为false（早于该特性的 data file）
+    ///
+    /// 若人为修改db文件导致值非预期，则Err报告数据已损坏
+    pub fn query_entry_bound(&self) -> Result<bool, AppError> {
+        match self.select_cfg_v_by_key(Self::ENTRY_BOUND_KEY).as_deref() {
+            None => Ok(false),
+            Some("1") => Ok(true),
+            Some(_) => Err(AppError::DataCorrupted),
+        }
+    }
+    /// 存储条目密文已绑定标志，该标志一经存储不再移除
+    fn store_entry_bound(&self, entry_bound: bool) {
+        if entry_bound {
+            self.save_cfg(Self::ENTRY_BOUND_KEY, "1")
+        }
+    }
+
+    /// 新写入密文使用的加密算法
+    const CIPHER_ALG_KEY: &'static str = "ca";
+    /// 查找新写入密文使用的加密算法，不存在即为 AES-256-GCM（早于该配置的 data file）
+    ///
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_cipher_alg(&self) -> Result<CipherAlg, AppError> {
+        self.select_cfg_v_by_key(Self::CIPHER_ALG_KEY)
+            .map_or(Ok(CipherAlg::default()), |s| {
+                s.parse().map_err(|_| AppError::DataCorrupted)
+            })
+    }
+    /// 存储新写入密文使用的加密算法，因为 AES-256-GCM 为默认值，遂走delete逻辑
+    fn store_cipher_alg(&self, cipher: CipherAlg) {
+        if cipher == CipherAlg::default() {
+            self.delete_cfg(Self::CIPHER_ALG_KEY)
+        } else {
+            self.save_cfg(Self::CIPHER_ALG_KEY, cipher.name())
+        }
+    }
+
+    /// 查找 data file 的条目加密模式
+    pub fn query_entry_encrypt_mode(&self) -> Result<EntryEncryptMode, AppError> {
+        Ok(EntryEncryptMode {
+            full_encrypt: self.query_full_encrypt()?,
+            entry_bound: self.query_entry_bound()?,
+            cipher: self.query_cipher_alg()?,
+        })
+    }
+    /// 存储条目加密模式
+    ///
+    /// 该方法仅存储模式，条目的重新加密见 [`Storage::update_entry_encrypt_mode`]
+    pub fn store_entry_encrypt_mode(&self, mode: &EntryEncryptMode) {
+        self.store_full_encrypt(mode.full_encrypt);
+        self.store_entry_bound(mode.entry_bound);
+        self.store_cipher_alg(mode.cipher);
+    }
+
     /// auto re-lock idle sec
     const AUTO_RE_LOCK_IDLE_SEC: &'static str = "ars";
     /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
@@ -79,6 +247,161 @@ impl Storage {
         }
     }
 
+    /// clipboard clear sec
+    const CLIPBOARD_CLEAR_SEC: &'static str = "ccs";
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_cfg_clipboard_clear_sec(&self) -> anyhow::Result<Option<u32>> {
+        self.select_cfg_v_by_key(Self::CLIPBOARD_CLEAR_SEC)
+            .map(|s| s.parse::<u32>())
+            .transpose()
+            .with_context(|| AppError::DataCorrupted)
+    }
+    /// 保存 clipboard_clear_sec 配置
+    /// 因为0（不清除）为默认值，遂走delete逻辑
+    pub fn store_cfg_clipboard_clear_sec(&self, clipboard_clear_sec: u32) {
+        if clipboard_clear_sec == 0 {
+            self.delete_cfg(Self::CLIPBOARD_CLEAR_SEC)
+        } else {
+            self.save_cfg(Self::CLIPBOARD_CLEAR_SEC, &clipboard_clear_sec.to_string())
+        }
+    }
+
+    /// reveal password sec
+    const REVEAL_PASSWORD_SEC: &'static str = "rps";
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_cfg_reveal_password_sec(&self) -> anyhow::Result<Option<u32>> {
+        self.select_cfg_v_by_key(Self::REVEAL_PASSWORD_SEC)
+            .map(|s| s.parse::<u32>())
+            .transpose()
+            .with_context(|| AppError::DataCorrupted)
+    }
+    /// 保存 reveal_password_sec 配置
+    /// 因为0（不重新掩码）为默认值，遂走delete逻辑
+    pub fn store_cfg_reveal_password_sec(&self, reveal_password_sec: u32) {
+        if reveal_password_sec == 0 {
+            self.delete_cfg(Self::REVEAL_PASSWORD_SEC)
+        } else {
+            self.save_cfg(Self::REVEAL_PASSWORD_SEC, &reveal_password_sec.to_string())
+        }
+    }
+
+    /// max failed unlock
+    const MAX_FAILED_UNLOCK: &'static str = "mfu";
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_cfg_max_failed_unlock(&self) -> anyhow::Result<Option<u32>> {
+        self.select_cfg_v_by_key(Self::MAX_FAILED_UNLOCK)
+            .map(|s| s.parse::<u32>())
+            .transpose()
+            .with_context(|| AppError::DataCorrupted)
+    }
+    /// 保存 max_failed_unlock 配置
+    /// 因为0（不限制）为默认值，遂走delete逻辑
+    pub fn store_cfg_max_failed_unlock(&self, max_failed_unlock: u32) {
+        if max_failed_unlock == 0 {
+            self.delete_cfg(Self::MAX_FAILED_UNLOCK)
+        } else {
+            self.save_cfg(Self::MAX_FAILED_UNLOCK, &max_failed_unlock.to_string())
+        }
+    }
+
+    /// history keep
+    const HISTORY_KEEP: &'static str = "hk";
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_cfg_history_keep(&self) -> anyhow::Result<Option<u32>> {
+        self.select_cfg_v_by_key(Self::HISTORY_KEEP)
+            .map(|s| s.parse::<u32>())
+            .transpose()
+            .with_context(|| AppError::DataCorrupted)
+    }
+    /// 保存 history_keep 配置
+    /// 因为 [`DEFAULT_HISTORY_KEEP`] 为默认值，遂其走delete逻辑，0（不保留）亦需存储
+    pub fn store_cfg_history_keep(&self, history_keep: u32) {
+        if history_keep == DEFAULT_HISTORY_KEEP {
+            self.delete_cfg(Self::HISTORY_KEEP)
+        } else {
+            self.save_cfg(Self::HISTORY_KEEP, &history_keep.to_string())
+        }
+    }
+
+    /// trash purge days
+    const TRASH_PURGE_DAYS: &'static str = "tpd";
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_cfg_trash_purge_days(&self) -> anyhow::Result<Option<u32>> {
+        self.select_cfg_v_by_key(Self::TRASH_PURGE_DAYS)
+            .map(|s| s.parse::<u32>())
+            .transpose()
+            .with_context(|| AppError::DataCorrupted)
+    }
+    /// 保存 trash_purge_days 配置
+    /// 因为 [`DEFAULT_TRASH_PURGE_DAYS`] 为默认值，遂其走delete逻辑，0（不自动删除）亦需存储
+    pub fn store_cfg_trash_purge_days(&self, trash_purge_days: u32) {
+        if trash_purge_days == DEFAULT_TRASH_PURGE_DAYS {
+            self.delete_cfg(Self::TRASH_PURGE_DAYS)
+        } else {
+            self.save_cfg(Self::TRASH_PURGE_DAYS, &trash_purge_days.to_string())
+        }
+    }
+
+    /// 主密码校验失败记录
+    const FAILED_UNLOCK_KEY: &'static str = "fu";
+    /// 查找主密码校验失败记录，不存在即无失败
+    ///
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_failed_unlock(&self) -> Result<FailedUnlock, AppError> {
+        self.select_cfg_v_by_key(Self::FAILED_UNLOCK_KEY)
+            .map_or(Ok(FailedUnlock::default()), |s| s.parse())
+    }
+    /// 存储主密码校验失败记录，因为无失败为默认值，遂走delete逻辑
+    pub fn store_failed_unlock(&self, failed: &FailedUnlock) {
+        if failed.count == 0 {
+            self.delete_cfg(Self::FAILED_UNLOCK_KEY)
+        } else {
+            self.save_cfg(Self::FAILED_UNLOCK_KEY, &failed.to_string())
+        }
+    }
+
+    /// 具名密码生成策略 key 前缀，完整 key 形如 `gp.<name>`
+    const GEN_POLICY_KEY_PREFIX: &'static str = "gp.";
+    /// 查找给定名称的密码生成策略，不存在即 Ok(None)
+    ///
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_gen_policy(&self, name: &str) -> Result<Option<PasswordPolicy>, AppError> {
+        self.select_cfg_v_by_key(&format!("{}{}", Self::GEN_POLICY_KEY_PREFIX, name))
+            .map(|s| s.parse())
+            .transpose()
+    }
+    /// 查找所有具名密码生成策略，按名称排序
+    ///
+    /// 若人为修改db文件导致 FromStr parse失败，则Err报告数据已损坏
+    pub fn query_gen_policies(&self) -> Result<Vec<(String, PasswordPolicy)>, AppError> {
+        let mut stmt = self
+            .conn
+            .prepare(Self::SELECT_INNER_CFG_BY_PREFIX_SQL)
+            .expect("Failed to prepare sql");
+        let rows = stmt
+            .query_map(params![format!("{}%", Self::GEN_POLICY_KEY_PREFIX)], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .expect("Failed to query cfg");
+        rows.map(|row| {
+            let (k, v) = row.map_err(|_| AppError::DataCorrupted)?;
+            let name = k[Self::GEN_POLICY_KEY_PREFIX.len()..].to_owned();
+            Ok((name, v.parse()?))
+        })
+        .collect()
+    }
+    /// 存储给定名称的密码生成策略，存在则替换
+    pub fn store_gen_policy(&self, name: &str, policy: &PasswordPolicy) {
+        self.save_cfg(&format!("{}{}", Self::GEN_POLICY_KEY_PREFIX, name), &policy.to_string())
+    }
+    /// 删除给定名称的密码生成策略，返回其是否存在
+    pub fn delete_gen_policy(&self, name: &str) -> bool {
+        let key = format!("{}{}", Self::GEN_POLICY_KEY_PREFIX, name);
+        let exists = self.select_cfg_v_by_key(&key).is_some();
+        self.delete_cfg(&key);
+        exists
+    }
+
     /// bit flag key
     const BIT_FLAG_CFG_ID: &'static str = "bf";
     /// 查找 bit flag cfg值，如不存在，则返回Ok(None)
@@ -109,6 +432,10 @@ impl Storage {
     /// 模板-查找内部配置 sql
     const SELECT_INNER_CFG_SQL: &'static str = r#"SELECT "v" FROM "cfg" WHERE "k"=?"#;
 
+    /// 模板-按 key 前缀查找内部配置 sql，`_` 为 LIKE 通配符，遂前缀中不应含之
+    const SELECT_INNER_CFG_BY_PREFIX_SQL: &'static str =
+        r#"SELECT "k", "v" FROM "cfg" WHERE "k" LIKE ? ORDER BY "k""#;
+
     /// 模板-插入内部配置 sql
     const SAVE_INNER_CFG_SQL: &'static str = r#"INSERT OR REPLACE INTO "cfg" ("k", "v") VALUES (?, ?)"#;
 
@@ -0,0 +1,270 @@
+//! data file 结构版本（schema version）及其前向迁移
+//!
+//! 结构版本存储在 cfg 表中，早于结构版本化的 data file 没有该值，视为版本 1
+
+use crate::app::errors::AppError;
+use crate::app::storage::Storage;
+use rusqlite::Connection;
+
+/// 当前程序支持的 data file 结构版本，新建的 data file 即为该版本
+pub const CURRENT_SCHEMA_VERSION: u32 = 13;
+
+/// 没有结构版本记录的 data file 的版本
+const UNVERSIONED_SCHEMA_VERSION: u32 = 1;
+
+/// 一次前向迁移，将 data file 结构从 `version - 1` 升级到 `version`
+pub struct Migration {
+    pub version: u32,
+    pub up: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+/// 按版本号升序排列的所有迁移，
+/// 结构变化时应在末尾追加迁移并同步递增 [`CURRENT_SCHEMA_VERSION`]
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        up: v1_create_base_tables,
+    },
+    Migration {
+        version: 2,
+        up: v2_full_encrypt_mode,
+    },
+    Migration {
+        version: 3,
+        up: v3_kdf_params,
+    },
+    Migration {
+        version: 4,
+        up: v4_entry_bound_ciphertext,
+    },
+    Migration {
+        version: 5,
+        up: v5_ciphertext_envelope,
+    },
+    Migration {
+        version: 6,
+        up: v6_subkey_mac_verifier,
+    },
+    Migration {
+        version: 7,
+        up: v7_keyfile,
+    },
+    Migration {
+        version: 8,
+        up: v8_wrapped_data_key,
+    },
+    Migration {
+        version: 9,
+        up: v9_tags,
+    },
+    Migration {
+        version: 10,
+        up: v10_groups,
+    },
+    Migration {
+        version: 11,
+        up: v11_entry_history,
+    },
+    Migration {
+        version: 12,
+        up: v12_entry_trash,
+    },
+    Migration {
+        version: 13,
+        up: v13_custom_fields,
+    },
+];
+
+/// v1: cfg 表及 entry 表
+fn v1_create_base_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(Storage::CREATE_ENTRY_TABLE_TEMPLATE_SQL, [])?;
+    conn.execute(Storage::CREATE_INNER_CFG_TABLE_SQL, [])?;
+    Ok(())
+}
+
+/// v2: 全加密模式（cfg `fe`），about 及 notes 可能为密文，
+/// 表结构无变化，仅使不识别该模式的旧版本程序拒绝读写
+fn v2_full_encrypt_mode(_conn: &Connection) -> rusqlite::Result<()> {
+    Ok(())
+}
+
+/// v3: 主密码 KDF 参数（cfg `kdf`），缺省即为原默认参数，
+/// 表结构无变化，仅使以固定参数派生密钥的旧版本程序拒绝读写
+fn v3_kdf_params(_conn: &Connection) -> rusqlite::Result<()> {
+    Ok(())
+}
+
+/// v4: 条目密文绑定条目（cfg `ead`），
+/// 重新加密需主密码派生的密钥，遂不在此进行，而是在解锁时进行（见 `PntContext::hold_security_context`），
+/// 表结构无变化，仅使不以关联数据解密的旧版本程序拒绝读写
+fn v4_entry_bound_ciphertext(_conn: &Connection) -> rusqlite::Result<()> {
+    Ok(())
+}
+
+/// v5: 自描述的密文信封及可选的加密算法（cfg `ca`），无信封的旧密文仍可解密，
+/// 表结构无变化，仅使不识别信封的旧版本程序拒绝读写
+fn v5_ciphertext_envelope(_conn: &Connection) -> rusqlite::Result<()> {
+    Ok(())
+}
+
+/// v6: 条目以主密钥派生的子密钥加密，主密码校验段（cfg `mp`）为子密钥派生的校验值，
+/// 与 v4 相同，重新加密在解锁时进行，
+/// 表结构无变化，仅使以 KDF 输出直接加密的旧版本程序拒绝读写
+fn v6_subkey_mac_verifier(_conn: &Connection) -> rusqlite::Result<()> {
+    Ok(())
+}
+
+/// v7: 可选的密钥文件（cfg `kf`），其摘要参与主密钥派生，
+/// 表结构无变化，仅使不识别密钥文件的旧版本程序拒绝读写（否则其仅会提示主密码错误）
+fn v7_keyfile(_conn: &Connection) -> rusqlite::Result<()> {
+    Ok(())
+}
+
+/// v8: 条目以随机的数据密钥加密，其被主密钥包裹（cfg `wk`）及可选地被恢复密钥包裹（cfg `rk`），
+/// 表结构无变化，早于该版本的 data file 于首次解锁时存储被包裹的数据密钥，条目无需重新加密
+fn v8_wrapped_data_key(_conn: &Connection) -> rusqlite::Result<()> {
+    Ok(())
+}
+
+/// v9: 条目标签，tag 表及条目与标签的关联表 entry_tag
+fn v9_tags(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(Storage::CREATE_TAG_TABLE_SQL, [])?;
+    conn.execute(Storage::CREATE_ENTRY_TAG_TABLE_SQL, [])?;
+    Ok(())
+}
+
+/// v10: 分组，grp 表及 entry 表的所属分组列 g_id，已有条目不属于任何分组
+fn v10_groups(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(Storage::CREATE_GROUP_TABLE_SQL, [])?;
+    conn.execute(Storage::ADD_ENTRY_GROUP_COLUMN_SQL, [])?;
+    Ok(())
+}
+
+/// v11: 条目的历史版本，entry_history 表，此前的更新没有历史版本
+fn v11_entry_history(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(Storage::CREATE_ENTRY_HISTORY_TABLE_SQL, [])?;
+    Ok(())
+}
+
+/// v12: 回收站，entry 表增加删除时间列，此前删除的条目已不存在
+fn v12_entry_trash(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(Storage::ADD_ENTRY_DELETED_TIME_COLUMN_SQL, [])?;
+    Ok(())
+}
+
+/// v13: 条目的自定义字段，entry 表及 entry_history 表增加自定义字段列，已有条目没有自定义字段
+fn v13_custom_fields(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(Storage::ADD_ENTRY_FIELDS_COLUMN_SQL, [])?;
+    conn.execute(Storage::ADD_ENTRY_HISTORY_FIELDS_COLUMN_SQL, [])?;
+    Ok(())
+}
+
+impl Storage {
+    /// 检查 data file 结构版本，
+    /// 若低于当前版本，则执行缺失的迁移，
+    /// 若高于当前版本（由更新版本的 pnt 写入），则 Err [`AppError::DataFileTooNew`]
+    pub(super) fn migrate(&self) -> anyhow::Result<()> {
+        let found = self
+            .query_schema_version()?
+            .unwrap_or(UNVERSIONED_SCHEMA_VERSION);
+        if found > CURRENT_SCHEMA_VERSION {
+            Err(AppError::DataFileTooNew {
+                found,
+                supported: CURRENT_SCHEMA_VERSION,
+            })?
+        }
+        if found < CURRENT_SCHEMA_VERSION {
+            self.apply_migrations(found, MIGRATIONS)?;
+        }
+        Ok(())
+    }
+
+    /// 在一个事务中顺序执行版本大于 `from` 的所有迁移，并记录迁移后的结构版本，
+    /// 任一迁移失败则整体回滚，data file 保持原样
+    pub(super) fn apply_migrations(
+        &self, from: u32, migrations: &[Migration],
+    ) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        let mut version = from;
+        for migration in migrations.iter().filter(|m| m.version > from) {
+            (migration.up)(&self.conn)?;
+            version = migration.version;
+        }
+        self.store_schema_version(version);
+        transaction.commit()?;
+        // transaction =====================================================
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 早于结构版本化的 data file：只有 cfg 及 entry 表，没有结构版本记录
+    fn unversioned_storage() -> Storage {
+        let s = Storage {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        v1_create_base_tables(&s.conn).unwrap();
+        s
+    }
+
+    #[test]
+    fn test_migrations_ordered() {
+        let versions: Vec<_> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert!(versions.windows(2).all(|w| w[0] + 1 == w[1]));
+        assert_eq!(versions.last(), Some(&CURRENT_SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_migrate() {
+        let s = Storage::open_in_memory().unwrap();
+        assert_eq!(
+            s.query_schema_version().unwrap(),
+            Some(CURRENT_SCHEMA_VERSION)
+        );
+
+        // 旧 data file 迁移：追加一个测试迁移
+        fn v2_create_test_table(conn: &Connection) -> rusqlite::Result<()> {
+            conn.execute(
+                r#"CREATE TABLE "t" ("id" INTEGER NOT NULL PRIMARY KEY)"#,
+                [],
+            )?;
+            Ok(())
+        }
+        let migrations = [
+            Migration {
+                version: 1,
+                up: v1_create_base_tables,
+            },
+            Migration {
+                version: 2,
+                up: v2_create_test_table,
+            },
+        ];
+        let s = unversioned_storage();
+        assert_eq!(s.query_schema_version().unwrap(), None);
+        s.apply_migrations(UNVERSIONED_SCHEMA_VERSION, &migrations)
+            .unwrap();
+        assert_eq!(s.query_schema_version().unwrap(), Some(2));
+        let count: u32 = s
+            .conn
+            .query_row(r#"SELECT COUNT(*) FROM "t""#, [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_migrate_too_new() {
+        let s = unversioned_storage();
+        s.store_schema_version(CURRENT_SCHEMA_VERSION + 1);
+        let err = s.migrate().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<AppError>(),
+            Some(AppError::DataFileTooNew { .. })
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::app::entry::Tag;
+use crate::app::errors::AppError;
+use crate::app::storage::{Storage, sql_result_map_to_option};
+use rusqlite::{Result as SqlResult, Row, params};
+
+/// 将 Row 转换为 Tag
+fn row_map_tag(row: &Row) -> SqlResult<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+    })
+}
+
+impl Storage {
+    /// 模板-下一个标签id 的 Sqlite 语句，tag 表 id 为 AUTOINCREMENT，已删除标签的 id 不会被复用
+    const NEXT_TAG_ID_SQL: &'static str =
+        r#"SELECT COALESCE((SELECT "seq" FROM "sqlite_sequence" WHERE "name"='tag'), 0) + 1"#;
+    /// 模板-删除不再被任何条目关联的标签的 Sqlite 语句
+    const DELETE_UNUSED_TAG_SQL: &'static str =
+        r#"DELETE FROM "tag" WHERE "id" NOT IN (SELECT "t_id" FROM "entry_tag")"#;
+
+    /// 分配新建标签的id，全加密模式下标签名密文与id绑定，遂须在加密前分配，
+    /// 同时新建多个标签时，其余的id依次递增
+    pub fn next_tag_id(&self) -> u32 {
+        let r = self.conn.query_row(Self::NEXT_TAG_ID_SQL, [], |row| row.get(0));
+        sql_result_map_to_option(r).unwrap_or_else(|| panic!("{}", AppError::DataCorrupted))
+    }
+
+    /// 查询所有标签
+    pub fn select_all_tag(&self) -> Vec<Tag> {
+        let mut stmt = self.conn.prepare(r#"SELECT "id", "name" FROM "tag""#).unwrap();
+        let rows = stmt.query_map([], row_map_tag).expect("Failed to select tag");
+        rows.filter_map(sql_result_map_to_option).collect()
+    }
+
+    /// 查询所有条目的标签，返回 (条目id, 标签)
+    pub fn select_all_entry_tag(&self) -> Vec<(u32, Tag)> {
+        let mut stmt = self
+            .conn
+            .prepare(r#"SELECT et."e_id", t."id", t."name" FROM "entry_tag" et JOIN "tag" t ON t."id" = et."t_id""#)
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, Tag { id: row.get(1)?, name: row.get(2)? })))
+            .expect("Failed to select entry tag");
+        rows.filter_map(sql_result_map_to_option).collect()
+    }
+
+    /// 查询一个条目的标签
+    pub fn select_entry_tags(&self, e_id: u32) -> Vec<Tag> {
+        let mut stmt = self
+            .conn
+            .prepare(r#"SELECT t."id", t."name" FROM "entry_tag" et JOIN "tag" t ON t."id" = et."t_id" WHERE et."e_id" = ?"#)
+            .unwrap();
+        let rows = stmt.query_map([e_id], row_map_tag).expect("Failed to select entry tag");
+        rows.filter_map(sql_result_map_to_option).collect()
+    }
+
+    /// 更新一个标签的名称
+    pub fn update_tag(&self, tag: &Tag) {
+        self.conn
+            .execute(r#"UPDATE "tag" SET "name"=? WHERE "id"=?"#, params![tag.name, tag.id])
+            .expect("Failed to update tag");
+    }
+
+    /// 以给定标签替换条目的所有标签，
+    /// `new_tags` 为需新建的标签（其id应由 [`Storage::next_tag_id`] 分配），
+    /// `t_ids` 为条目的所有标签的id（包括新建的），
+    /// 不再被任何条目关联的标签即被删除，这些操作在一个事务中进行
+    pub fn store_entry_tags(&self, e_id: u32, new_tags: &[Tag], t_ids: &[u32]) -> anyhow::Result<()> {
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        self.replace_entry_tags(e_id, new_tags, t_ids)?;
+        transaction.commit()?;
+        // transaction =====================================================
+        Ok(())
+    }
+
+    /// 同 [`Storage::store_entry_tags`]，该方法不开启事务，应由调用方在事务中调用
+    pub(super) fn replace_entry_tags(&self, e_id: u32, new_tags: &[Tag], t_ids: &[u32]) -> SqlResult<()> {
+        for tag in new_tags {
+            self.conn
+                .execute(r#"INSERT INTO "tag" ("id", "name") VALUES (?, ?)"#, params![tag.id, tag.name])?;
+        }
+        self.conn
+            .execute(r#"DELETE FROM "entry_tag" WHERE "e_id"=?"#, params![e_id])?;
+        for t_id in t_ids {
+            self.conn.execute(
+                r#"INSERT OR IGNORE INTO "entry_tag" ("e_id", "t_id") VALUES (?, ?)"#,
+                params![e_id, t_id],
+            )?;
+        }
+        self.conn.execute(Self::DELETE_UNUSED_TAG_SQL, [])?;
+        Ok(())
+    }
+
+    /// 删除条目的所有标签关联，及不再被任何条目关联的标签
+    pub(super) fn delete_entry_tags(&self, e_id: u32) {
+        self.conn
+            .execute(r#"DELETE FROM "entry_tag" WHERE "e_id"=?"#, params![e_id])
+            .expect("Failed to delete entry tag");
+        self.conn
+            .execute(Self::DELETE_UNUSED_TAG_SQL, [])
+            .expect("Failed to delete tag");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::entry::ValidEntry;
+
+    #[test]
+    fn test_entry_tags() {
+        let db = Storage::open_in_memory().unwrap();
+        for _ in 0..2 {
+            db.insert_entry(&ValidEntry {
+                id: db.next_entry_id(),
+                about: String::from("test"),
+                notes: None,
+                encrypted_username: String::from("test"),
+                encrypted_password: String::from("test"),
+                fields: None,
+            });
+        }
+        let t1 = db.next_tag_id();
+        let work = Tag { id: t1, name: String::from("work") };
+        let mail = Tag { id: t1 + 1, name: String::from("mail") };
+        db.store_entry_tags(1, &[work.clone(), mail.clone()], &[work.id, mail.id]).unwrap();
+        db.store_entry_tags(2, &[], &[work.id]).unwrap();
+        assert_eq!(db.next_tag_id(), t1 + 2);
+        assert_eq!(db.select_entry_tags(1).len(), 2);
+        assert_eq!(db.select_all_entry_tag().len(), 3);
+
+        // 不再关联的标签被删除
+        db.store_entry_tags(1, &[], &[work.id]).unwrap();
+        assert_eq!(db.select_all_tag(), vec![work.clone()]);
+        db.delete_entry(1);
+        assert_eq!(db.select_all_entry_tag(), vec![(2, work)]);
+        db.delete_entry(2);
+        assert!(db.select_all_tag().is_empty());
+        // 已删除标签的 id 不复用
+        assert_eq!(db.next_tag_id(), t1 + 2);
+    }
+}
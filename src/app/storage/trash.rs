@@ -0,0 +1,136 @@
+use crate::app::entry::TrashedEntry;
+use crate::app::storage::entries::row_map_entry;
+use crate::app::storage::{Storage, sql_result_map_to_option};
+use rusqlite::{Result as SqlResult, Row, params};
+
+/// 将 Row 转换为 TrashedEntry，其列前8个同 [`row_map_entry`]，第9个为删除时间
+fn row_map_trashed_entry(row: &Row) -> SqlResult<TrashedEntry> {
+    Ok(TrashedEntry {
+        entry: row_map_entry(row)?,
+        deleted_time: row.get(8)?,
+    })
+}
+
+impl Storage {
+    /// 模板-查询回收站中条目的 Sqlite 语句
+    const SELECT_TRASHED_ENTRY_SQL: &'static str = r#"
+        SELECT "id", "about", "notes", "k", "v", "ct", "ut", "f", "dt"
+        FROM "entry" WHERE "dt" IS NOT NULL"#;
+
+    /// 将一条密码记录移入回收站，返回其是否存在（且尚未在回收站中）
+    pub fn trash_entry(&self, entry_id: u32) -> bool {
+        self.conn
+            .execute(
+                r#"UPDATE "entry" SET "dt"=datetime('now', 'localtime') WHERE "id"=? AND "dt" IS NULL"#,
+                params![entry_id],
+            )
+            .expect("Failed to trash entry")
+            > 0
+    }
+
+    /// 将回收站中的一条密码记录恢复，返回其是否在回收站中
+    pub fn restore_trashed_entry(&self, entry_id: u32) -> bool {
+        self.conn
+            .execute(
+                r#"UPDATE "entry" SET "dt"=NULL WHERE "id"=? AND "dt" IS NOT NULL"#,
+                params![entry_id],
+            )
+            .expect("Failed to restore entry")
+            > 0
+    }
+
+    /// 查询回收站中的所有条目，最近删除的在前
+    pub fn select_all_trashed_entry(&self) -> Vec<TrashedEntry> {
+        let sql = format!(r#"{} ORDER BY "dt" DESC, "id" DESC"#, Self::SELECT_TRASHED_ENTRY_SQL);
+        let mut stmt = self.conn.prepare(&sql).unwrap();
+        let rows = stmt
+            .query_map([], row_map_trashed_entry)
+            .expect("Failed to select trashed entry");
+        rows.filter_map(sql_result_map_to_option).collect()
+    }
+
+    /// 通过id查询回收站中的一条密码记录
+    pub fn select_trashed_entry_by_id(&self, id: u32) -> Option<TrashedEntry> {
+        let sql = format!(r#"{} AND "id"=?"#, Self::SELECT_TRASHED_ENTRY_SQL);
+        let r = self.conn.query_one(&sql, params![id], row_map_trashed_entry);
+        sql_result_map_to_option(r)
+    }
+
+    /// 永久删除回收站中超过 `days` 天的条目，`days` 为 0 即不自动清除，
+    /// 这些删除在一个事务中进行，返回删除的条目个数
+    pub fn purge_expired_trash(&self, days: u32) -> anyhow::Result<usize> {
+        if days == 0 {
+            return Ok(0);
+        }
+        let ids: Vec<u32> = {
+            let mut stmt = self.conn.prepare(
+                r#"SELECT "id" FROM "entry" WHERE "dt" <= datetime('now', 'localtime', ?)"#,
+            )?;
+            let rows = stmt.query_map([format!("-{days} days")], |row| row.get(0))?;
+            rows.filter_map(sql_result_map_to_option).collect()
+        };
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        // 不会嵌套事务，安全 uncheck
+        let transaction = self.conn.unchecked_transaction()?;
+        // transaction =====================================================
+        for id in &ids {
+            self.delete_entry(*id);
+        }
+        transaction.commit()?;
+        // transaction =====================================================
+        Ok(ids.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::entry::ValidEntry;
+
+    fn insert(db: &Storage) -> u32 {
+        let id = db.next_entry_id();
+        db.insert_entry(&ValidEntry {
+            id,
+            about: String::from("test"),
+            notes: None,
+            encrypted_username: String::from("test"),
+            encrypted_password: String::from("test"),
+            fields: None,
+        });
+        id
+    }
+
+    #[test]
+    fn test_trash() {
+        let db = Storage::open_in_memory().unwrap();
+        let (a, b) = (insert(&db), insert(&db));
+        assert!(db.trash_entry(a));
+        assert!(!db.trash_entry(a));
+        // 回收站中的条目不再被查询到
+        assert_eq!(db.select_all_entry().len(), 1);
+        assert!(db.select_entry_by_id(a).is_none());
+        assert!(db.select_entry_by_about_like("test").iter().all(|e| e.id == b));
+        let trashed = db.select_all_trashed_entry();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].entry.id, a);
+        assert!(db.select_trashed_entry_by_id(b).is_none());
+
+        // 恢复
+        assert!(db.restore_trashed_entry(a));
+        assert!(!db.restore_trashed_entry(a));
+        assert!(db.select_entry_by_id(a).is_some());
+
+        // 未超过天数不清除，将删除时间提前后清除
+        db.trash_entry(b);
+        assert_eq!(db.purge_expired_trash(0).unwrap(), 0);
+        assert_eq!(db.purge_expired_trash(30).unwrap(), 0);
+        db.conn
+            .execute(r#"UPDATE "entry" SET "dt"=datetime('now', 'localtime', '-31 days') WHERE "id"=?"#, [b])
+            .unwrap();
+        assert_eq!(db.purge_expired_trash(30).unwrap(), 1);
+        assert!(db.select_all_trashed_entry().is_empty());
+        assert_eq!(db.select_entry_count(), 1);
+    }
+}
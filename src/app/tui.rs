@@ -8,7 +8,8 @@ mod ui;
 
 use std::collections::HashMap;
 use crate::app::cfg::InnerCfg;
-use crate::app::consts::{APP_NAME, APP_NAME_AND_VERSION};
+use crate::app::clipboard::{self, Copied};
+use crate::app::consts::{APP_NAME, APP_NAME_AND_VERSION, TRASH_UNDO_SEC};
 use crate::app::context::PntContext;
 use crate::app::entry::EncryptedEntry;
 use crate::app::tui::colors::{CL_DD_WHITE, CL_DDD_WHITE, CL_LL_BLACK};
@@ -57,7 +58,7 @@ impl TUIApp {
 /// 新建 tui
 fn new_runtime(pnt_context: PntContext) -> anyhow::Result<TUIApp> {
     // 先载入
-    let vec_all_entry = pnt_context.storage.select_all_entry();
+    let vec_all_entry = pnt_context.select_all_listing_entry()?;
     let mut hot_msg = HotMsg::new();
     // tui 情况下 处理 要求立即密码的情况
     let screen = if pnt_context.is_need_mp_on_run() {
@@ -92,6 +93,8 @@ fn new_runtime(pnt_context: PntContext) -> anyhow::Result<TUIApp> {
         screen,
         back_screen: Vec::with_capacity(10),
         idle_tick: IdleTick::new(&pnt_context.cfg.inner_cfg),
+        clipboard_clear: ClipboardClear::new(&pnt_context.cfg.inner_cfg),
+        trash_undo: TrashUndo::default(),
         context: pnt_context,
         bottom_right_state: BottomRightState::new(),
         hot_msg,
@@ -114,6 +117,10 @@ pub struct TUIApp {
     event_queue: EventQueue,
     /// 闲置tick计数，tick每秒一次
     idle_tick: IdleTick,
+    /// 复制到剪贴板的内容，超时、重新锁定或退出时清除之
+    clipboard_clear: ClipboardClear,
+    /// 最近移入回收站的条目，短时间内可撤销
+    trash_undo: TrashUndo,
     /// 简单的 state info 信息，供页面渲染层显示，该字段面向渲染,
     /// 表示 ‘当前光标位置 /总数’
     bottom_right_state: BottomRightState,
@@ -296,3 +303,117 @@ impl IdleTick {
         self.idle_tick_count > self.auto_close_idle_sec
     }
 }
+
+/// TUI 复制到剪贴板的内容，
+/// 超过配置的秒数、重新锁定或退出时清除之（若剪贴板仍为该内容）
+struct ClipboardClear {
+    copied: Option<Copied>,
+    /// 距超时清除的剩余秒数，响应tick自减
+    countdown: u32,
+    /// 0表示不因超时清除
+    clear_sec: u32,
+}
+
+impl ClipboardClear {
+    fn new(inner_cfg: &InnerCfg) -> Self {
+        Self {
+            copied: None,
+            countdown: 0,
+            clear_sec: inner_cfg.clipboard_clear_sec.unwrap_or(0),
+        }
+    }
+
+    /// 记录新复制的内容，重新开始计时
+    fn hold(&mut self, copied: Copied) {
+        self.copied = Some(copied);
+        self.countdown = self.clear_sec;
+    }
+
+    /// 每次tick调用之，返回是否已到超时清除的时间
+    fn tick(&mut self) -> bool {
+        if self.copied.is_none() || self.clear_sec == 0 {
+            return false;
+        }
+        self.countdown = self.countdown.saturating_sub(1);
+        self.countdown == 0
+    }
+
+    /// 若剪贴板仍为复制的内容则清除之，返回是否清除，
+    /// 剪贴板不可用等失败时视为未清除，不中断TUI
+    fn clear(&mut self) -> bool {
+        self.copied
+            .take()
+            .is_some_and(|copied| clipboard::clear_if_unchanged(&copied).unwrap_or(false))
+    }
+}
+
+/// 最近移入回收站的条目，[`TRASH_UNDO_SEC`] 秒内可撤销
+#[derive(Default)]
+struct TrashUndo {
+    e_id: Option<u32>,
+    /// 距不可撤销的剩余秒数，响应tick自减
+    countdown: u8,
+}
+
+impl TrashUndo {
+    /// 记录新移入回收站的条目，重新开始计时
+    fn hold(&mut self, e_id: u32) {
+        self.e_id = Some(e_id);
+        self.countdown = TRASH_UNDO_SEC;
+    }
+
+    /// 每次tick调用之，超时即不可撤销
+    fn tick(&mut self) {
+        self.countdown = self.countdown.saturating_sub(1);
+        if self.countdown == 0 {
+            self.e_id = None;
+        }
+    }
+
+    /// 取出可撤销的条目id，超时或已撤销则 None
+    fn take(&mut self) -> Option<u32> {
+        self.e_id.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::clipboard::ClipboardBackend;
+
+    #[test]
+    fn test_clipboard_clear_countdown() {
+        let mut inner_cfg = InnerCfg::default();
+        let mut disabled = ClipboardClear::new(&inner_cfg);
+        disabled.hold(Copied::new(ClipboardBackend::Osc52, "secret"));
+        assert!(!(0..100).any(|_| disabled.tick()));
+
+        inner_cfg.clipboard_clear_sec = Some(3);
+        let mut clear = ClipboardClear::new(&inner_cfg);
+        assert!(!clear.tick());
+        clear.hold(Copied::new(ClipboardBackend::Osc52, "secret"));
+        assert!(!clear.tick());
+        assert!(!clear.tick());
+        // 再次复制重新计时
+        clear.hold(Copied::new(ClipboardBackend::Osc52, "other"));
+        assert!(!clear.tick());
+        assert!(!clear.tick());
+        assert!(clear.tick());
+        // 清除后（该处不访问系统剪贴板）不再计时
+        clear.copied = None;
+        assert!(!clear.tick());
+    }
+
+    #[test]
+    fn test_trash_undo_window() {
+        let mut undo = TrashUndo::default();
+        assert_eq!(undo.take(), None);
+        undo.hold(1);
+        (1..TRASH_UNDO_SEC).for_each(|_| undo.tick());
+        assert_eq!(undo.take(), Some(1));
+        assert_eq!(undo.take(), None);
+        undo.hold(2);
+        (0..TRASH_UNDO_SEC).for_each(|_| undo.tick());
+        assert_eq!(undo.take(), None);
+    }
+}
@@ -19,6 +19,7 @@ pub const CL_L_BLACK: Color = Color::from_u32(0x303030);
 pub const CL_LL_BLACK: Color = Color::from_u32(0x404040);
 
 #[cfg(test)]
+#[allow(dead_code)]
 pub const CL_LLL_BLACK: Color = Color::from_u32(0x555555);
 
 pub const CL_RED: Color = Color::Red;
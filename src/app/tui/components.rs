@@ -3,32 +3,40 @@
 use crate::app::context::PntContext;
 use crate::app::entry::{EncryptedEntry, InputEntry};
 use crate::app::tui::TUIApp;
-use crate::app::tui::components::states::{Editing, EditingState, HomePageV1State, VerifyMPHState};
+use crate::app::tui::components::states::{
+    EditingState, HistoryState, HomePageV1State, PasswordReveal, TrashState, VerifyMPHState,
+};
 use crate::app::tui::components::yn::YNState;
-use crate::app::tui::events::Action;
+use crate::app::tui::events::{Action, CopyField};
 use crate::app::tui::intents::ScreenIntent;
 use crate::app::tui::intents::ScreenIntent::{
-    ToDeleteYNOption, ToDetail, ToEditing, ToHelp, ToSaveYNOption,
+    ToDeleteYNOption, ToDetail, ToEditing, ToHelp, ToHistory, ToPurgeYNOption, ToRestoreYNOption, ToSaveYNOption,
+    ToTrash,
 };
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::app::tui::colors::{CL_AK, CL_D_YELLOW};
+use crate::app::tui::colors::CL_D_YELLOW;
 use ratatui::layout::Alignment;
 use ratatui::widgets::ListState;
 
+pub(crate) mod group_tree;
 pub(crate) mod states;
 pub(crate) mod yn;
 
 /// 当前屏幕
 pub enum Screen {
     /// 当前光标指向哪个，因为可能一个元素都没有，所以为 option, 所有元素在entries中
-    HomePageV1(HomePageV1State),
+    HomePageV1(Box<HomePageV1State>),
     /// f1 help, list state 为行光标状态
     Help(ListState),
     /// 某详情, u32 为 id
-    Details(InputEntry, u32),
+    Details(InputEntry, u32, PasswordReveal),
+    /// 某条目的历史版本
+    History(Box<HistoryState>),
+    /// 回收站
+    Trash(Box<TrashState>),
     /// 编辑窗口
-    Edit(EditingState),
+    Edit(Box<EditingState>),
     /// y/n 弹窗
     YNOption(YNState),
     /// 要求键入主密码的窗口，载荷主密码输入string和准备进入的页面
@@ -44,12 +52,24 @@ impl Screen {
     }
 
     /// 新建编辑页面
-    pub fn new_edit_updating(u_input: InputEntry, e_id: u32) -> Self {
-        Screen::Edit(EditingState::new_updating(u_input, e_id))
+    pub fn new_edit_updating(u_input: InputEntry, e_id: u32, password_reveal: PasswordReveal) -> Self {
+        Screen::Edit(Box::new(EditingState::new_updating(u_input, e_id, password_reveal)))
     }
     /// 新建新建页面
-    pub fn new_edit_creating() -> Self {
-        Screen::Edit(EditingState::new_creating())
+    pub fn new_edit_creating(password_reveal: PasswordReveal) -> Self {
+        Screen::Edit(Box::new(EditingState::new_creating(password_reveal)))
+    }
+
+    /// 每次tick调用之，详情及编辑页面显示的密码超时则重新掩码
+    pub fn tick_password_reveal(&mut self) {
+        match self {
+            Screen::Details(_, _, reveal) => {
+                reveal.tick();
+            }
+            Screen::Edit(state) => state.tick_password_reveal(),
+            Screen::History(state) => state.tick_password_reveal(),
+            _ => {}
+        }
     }
 
     /// 新建help页面
@@ -59,7 +79,7 @@ impl Screen {
 
     /// 新建主页
     pub fn new_home_page1(enc_entries: Vec<EncryptedEntry>) -> Self {
-        Screen::HomePageV1(HomePageV1State::new(enc_entries))
+        Screen::HomePageV1(Box::new(HomePageV1State::new(enc_entries)))
     }
 
     /// 新建输入密码页面
@@ -80,6 +100,20 @@ impl Screen {
     }
     /// 新建输入密码页面
     ///
+    /// 解决主页复制条目字段，而尚未验证主密码
+    ///
+    /// 成功为back screen 后复制，
+    /// 取消为back screen
+    pub fn new_copy_field_verify(e_id: u32, field: CopyField, context: &PntContext) -> anyhow::Result<Self> {
+        VerifyMPHState::new(
+            context,
+            Box::new(move || Action::Actions(vec![Action::BackScreen, Action::CopyEntryField(e_id, field)])),
+            || Action::BackScreen,
+        )
+        .map(Screen::InputMainPwd)
+    }
+    /// 新建输入密码页面
+    ///
     /// 解决 relock 页面覆盖其他页面
     ///
     /// 成功为back screen
@@ -90,6 +124,33 @@ impl Screen {
     }
 }
 
+/// 按键对应的要复制的条目字段，`with_notes_line` 为是否响应数字键以复制 notes 的某行（及 alt+数字键以复制某个自定义字段）
+fn copy_field_of(key_event: KeyEvent, with_notes_line: bool) -> Option<CopyField> {
+    match key_event.code {
+        KeyCode::Char('c') => Some(CopyField::Password),
+        KeyCode::Char('u') => Some(CopyField::Username),
+        KeyCode::Char('n') => Some(CopyField::Notes),
+        KeyCode::Char(n @ '1'..='9') if with_notes_line && key_event.modifiers == KeyModifiers::ALT => {
+            Some(CopyField::Custom(n as u8 - b'0'))
+        }
+        KeyCode::Char(n @ '1'..='9') if with_notes_line => Some(CopyField::NotesLine(n as u8 - b'0')),
+        _ => None,
+    }
+}
+
+/// 复制条目某字段到系统剪贴板并提示，字段为空时仅提示
+pub fn copy_field_actions(entry: &InputEntry, field: CopyField) -> Action {
+    match field.pick(entry) {
+        Some(value) => Action::CopyToSysClipboard(value.to_owned().into(), field.to_string()),
+        None => Action::SetTuiHotMsg(
+            format!("[!] {field} is empty"),
+            Some(3),
+            Some(Alignment::Center),
+            Some(CL_D_YELLOW),
+        ),
+    }
+}
+
 pub trait KeyEventExt {
     /// 判定是否为某char按下
     ///
@@ -181,6 +242,9 @@ impl EventHandler for TUIApp {
                 if state.find_mode() {
                     state.set_find_mode(false);
                     return ok_none();
+                } else if state.group_tree().is_focused() {
+                    state.group_tree_mut().set_focused(false);
+                    return ok_none();
                 } else if !state.current_find_input_is_empty() {
                     state.clear_find_input();
                     return ok_action(Action::FlashHomePageDisplayEncEntries);
@@ -250,6 +314,34 @@ impl EventHandler for Screen {
                     if key_event.is_char('q') {
                         return ok_action(Action::BackScreen);
                     }
+                    // tab 切换焦点至分组树或条目列表
+                    if key_event.is_tab() {
+                        let focused = state.group_tree().is_focused();
+                        state.group_tree_mut().set_focused(!focused);
+                        return ok_none();
+                    }
+                    // 焦点在分组树时，移动光标即选中分组并过滤条目
+                    if state.group_tree().is_focused() {
+                        let tree = state.group_tree_mut();
+                        if key_event.is_char('k') || key_event.is_up() {
+                            tree.cursor_up();
+                        } else if key_event.is_down() || key_event.is_char('j') {
+                            tree.cursor_down();
+                        } else if key_event.is_enter() {
+                            tree.toggle_collapsed();
+                            return ok_none();
+                        } else if key_event.is_char('h') || key_event.code == KeyCode::Left {
+                            // 折叠至上级分组时选中的分组改变
+                            tree.collapse();
+                        } else if key_event.code == KeyCode::Right {
+                            tree.expand();
+                            return ok_none();
+                        } else {
+                            return ok_none();
+                        }
+                        state.cursor_mut_ref().select(Some(0));
+                        return ok_action(Action::FlashHomePageDisplayEncEntries);
+                    }
                     // 可进入 查看，编辑，删除tip，新建 页面
                     // 若当前光标无所指，则只能 创建
                     if let Some(c_ptr) = state.cursor_selected() {
@@ -270,6 +362,10 @@ impl EventHandler for Screen {
                                 curr_ptr_e_id,
                             )));
                         }
+                        // 复制 password / username / notes
+                        if let Some(field) = copy_field_of(key_event, false) {
+                            return ok_action(Action::CopyEntryField(curr_ptr_e_id, field));
+                        }
                         // 上移
                         if key_event.is_char('k') || key_event.is_up() {
                             state.cursor_up();
@@ -295,6 +391,14 @@ impl EventHandler for Screen {
                     if key_event.is_char('a') {
                         return ok_action(Action::ScreenIntent(ToEditing(None)));
                     }
+                    // 回收站
+                    if key_event.is_char('t') {
+                        return ok_action(Action::ScreenIntent(ToTrash));
+                    }
+                    // 撤销刚刚的删除
+                    if key_event.is_char('z') {
+                        return ok_action(Action::UndoEntryRemove);
+                    }
                     ok_none()
                 } else {
                     match key_event.code {
@@ -311,7 +415,7 @@ impl EventHandler for Screen {
                 }
             }
             // 详情页
-            Screen::Details(e, e_id) => {
+            Screen::Details(e, e_id, reveal) => {
                 // f1 按下 进入 帮助页面
                 if key_event.is_f1() {
                     return ok_action(Action::ScreenIntent(ToHelp));
@@ -332,17 +436,100 @@ impl EventHandler for Screen {
                         Action::Actions(vec![Action::BackScreen, Action::ScreenIntent(ToEditing(Some(*e_id)))]);
                     return ok_action(back_and_enter_editing_actions);
                 }
-                if key_event.is_char('c') {
-                    let copy_pwd_and_hot_msg_actions = Action::Actions(vec![
-                        Action::CopyToSysClipboard(e.password.clone()),
-                        Action::SetTuiHotMsg(
-                            "[󰅉] Password has been copied to the system clipboard".into(),
-                            Some(5),
-                            None,
-                            Some(CL_AK),
-                        ),
-                    ]);
-                    return ok_action(copy_pwd_and_hot_msg_actions);
+                if let Some(field) = copy_field_of(key_event, true) {
+                    return ok_action(copy_field_actions(e, field));
+                }
+                // 掩码/显示密码
+                if key_event.is_char('r') {
+                    reveal.toggle();
+                    return ok_none();
+                }
+                if key_event.is_char('h') {
+                    return ok_action(Action::ScreenIntent(ToHistory(*e_id)));
+                }
+                ok_none()
+            }
+            // 历史版本页
+            Screen::History(state) => {
+                // f1 按下 进入 帮助页面
+                if key_event.is_f1() {
+                    return ok_action(Action::ScreenIntent(ToHelp));
+                }
+                if key_event.is_char('q') {
+                    return ok_action(Action::BackScreen);
+                }
+                if key_event.is_char('l') {
+                    return ok_action(Action::Relock);
+                }
+                if key_event.is_char('k') || key_event.is_up() {
+                    state.cursor_up();
+                    return ok_none();
+                }
+                if key_event.is_char('j') || key_event.is_down() {
+                    state.cursor_down();
+                    return ok_none();
+                }
+                if let KeyCode::Char('g') | KeyCode::Home = key_event.code {
+                    state.cursor_first();
+                    return ok_none();
+                }
+                if let KeyCode::Char('G') | KeyCode::End = key_event.code {
+                    state.cursor_last();
+                    return ok_none();
+                }
+                if key_event.is_char('r') {
+                    state.toggle_password_reveal();
+                    return ok_none();
+                }
+                let Some(version) = state.selected() else {
+                    return ok_none();
+                };
+                if let Some(field) = copy_field_of(key_event, false) {
+                    return ok_action(copy_field_actions(&version.entry, field));
+                }
+                if key_event.is_enter() {
+                    return ok_action(Action::ScreenIntent(ToRestoreYNOption(state.e_id(), version.h_id)));
+                }
+                ok_none()
+            }
+            // 回收站页
+            Screen::Trash(state) => {
+                // f1 按下 进入 帮助页面
+                if key_event.is_f1() {
+                    return ok_action(Action::ScreenIntent(ToHelp));
+                }
+                if key_event.is_char('q') {
+                    return ok_action(Action::BackScreen);
+                }
+                if key_event.is_char('l') {
+                    return ok_action(Action::Relock);
+                }
+                if key_event.is_char('k') || key_event.is_up() {
+                    state.cursor_up();
+                    return ok_none();
+                }
+                if key_event.is_char('j') || key_event.is_down() {
+                    state.cursor_down();
+                    return ok_none();
+                }
+                if let KeyCode::Char('g') | KeyCode::Home = key_event.code {
+                    state.cursor_first();
+                    return ok_none();
+                }
+                if let KeyCode::Char('G') | KeyCode::End = key_event.code {
+                    state.cursor_last();
+                    return ok_none();
+                }
+                let Some(trashed) = state.selected() else {
+                    return ok_none();
+                };
+                let e_id = trashed.entry.id;
+                // 恢复无需确认，永久删除需确认
+                if key_event.is_char('r') || key_event.is_enter() {
+                    return ok_action(Action::EntryRestore(e_id));
+                }
+                if key_event.is_char('d') {
+                    return ok_action(Action::ScreenIntent(ToPurgeYNOption(e_id)));
                 }
                 ok_none()
             }
@@ -384,7 +571,7 @@ impl EventHandler for Screen {
                 }
 
                 // 如果当前不为 notes编辑，则可响应 up/ down 按键上下
-                if state.current_editing_type() != Editing::Notes {
+                if !state.is_editing_notes() {
                     // 上移
                     if key_event.is_up() {
                         state.cursor_up();
@@ -402,7 +589,7 @@ impl EventHandler for Screen {
                     }
                 }
                 // 若当前为编辑notes，且notes内光标在第一行，且按了上键，则选中上方的输入框
-                if Editing::Notes == state.current_editing_type()
+                if state.is_editing_notes()
                     && key_event.is_up()
                     && state.current_editing_string_mut().cursor().0 == 0
                 {
@@ -415,6 +602,37 @@ impl EventHandler for Screen {
                     state.cursor_down();
                     return ok_none();
                 }
+                // 生成密码并填入 password 输入框
+                if key_event.is_ctrl_char('g') {
+                    return ok_action(Action::GeneratePassword);
+                }
+                // 掩码/显示 password 及秘密字段值输入框
+                if key_event.is_ctrl_char('r') {
+                    state.toggle_password_reveal();
+                    return ok_none();
+                }
+                // 新增自定义字段
+                if key_event.is_ctrl_char('n') {
+                    state.add_field();
+                    return ok_none();
+                }
+                // 删除焦点所在的自定义字段，焦点不在自定义字段上时作为输入框的按键
+                if key_event.is_ctrl_char('d') && state.remove_field() {
+                    return ok_none();
+                }
+                // 切换焦点所在的自定义字段是否为秘密字段
+                if key_event.is_ctrl_char('t') {
+                    if let Some(secret) = state.toggle_field_secret() {
+                        let msg = if secret { "Field is secret (encrypted, masked)" } else { "Field is plain" };
+                        return ok_action(Action::SetTuiHotMsg(
+                            msg.into(),
+                            Some(3),
+                            Some(Alignment::Center),
+                            Some(CL_D_YELLOW),
+                        ));
+                    }
+                    return ok_none();
+                }
                 // 保存
                 if key_event.is_ctrl_char('s') {
                     return if state.current_input_validate() {
@@ -439,11 +657,19 @@ impl EventHandler for Screen {
             // 需要主密码
             Screen::InputMainPwd(state) => {
                 if key_event.is_enter() {
+                    // 失败次数过多时须等待，等待期间不校验
+                    if let Some(secs) = state.retry_wait_secs() {
+                        return ok_action(Action::SetTuiHotMsg(
+                            format!("[!] Too many failed attempts, retry in {secs}s"),
+                            Some(3),
+                            Some(Alignment::Center),
+                            Some(CL_D_YELLOW),
+                        ));
+                    }
                     return if let Some(security_context) = state.try_build_security_context()? {
                         ok_action(Action::MainPwdVerifySuccess(security_context))
                     } else {
-                        state.increment_retry_count()?;
-                        ok_none()
+                        ok_action(Action::MainPwdVerifyFailed)
                     };
                 }
                 // 密码编辑窗口变化
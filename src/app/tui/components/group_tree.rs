@@ -0,0 +1,232 @@
+//! 主页左侧的分组树，选中的分组过滤主页显示的条目
+
+use crate::app::entry::{EncryptedEntry, GROUP_SEPARATOR};
+use std::collections::HashSet;
+
+/// 分组树中可见的一个分组
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct GroupNode<'a> {
+    /// 完整路径，如 `work/aws`
+    pub path: &'a str,
+    /// 末级分组名，如 `aws`
+    pub name: &'a str,
+    /// 层级，顶级分组为 0
+    pub depth: usize,
+    pub has_children: bool,
+    pub collapsed: bool,
+}
+
+/// 分组树状态，分组由条目的分组路径（及其各级上级分组）得出
+#[derive(Debug, Clone, Default)]
+pub struct GroupTreeState {
+    /// 所有分组路径，按各级分组名（不区分大小写）排序，遂下级分组紧随其上级分组
+    paths: Vec<String>,
+    /// 折叠的分组路径
+    collapsed: HashSet<String>,
+    /// 光标在可见节点中的位置，0 为全部条目，其后依次为 [`GroupTreeState::visible`] 中的分组
+    cursor: usize,
+    /// 焦点是否在分组树上
+    focused: bool,
+}
+
+impl GroupTreeState {
+    /// 以给定条目的分组重设分组树，光标所指的分组仍存在时保持之，否则回到全部条目
+    pub fn reset<'a>(&mut self, entries: impl Iterator<Item = &'a EncryptedEntry>) {
+        let selected = self.selected().map(str::to_owned);
+        let mut paths = HashSet::new();
+        for group in entries.filter_map(|e| e.group.as_deref()) {
+            // 各级上级分组
+            for (i, _) in group.match_indices(GROUP_SEPARATOR) {
+                paths.insert(&group[..i]);
+            }
+            paths.insert(group);
+        }
+        let mut paths: Vec<String> = paths.into_iter().map(str::to_owned).collect();
+        paths.sort_by_cached_key(|p| {
+            p.split(GROUP_SEPARATOR)
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+        });
+        self.collapsed.retain(|p| paths.contains(p));
+        self.paths = paths;
+        self.cursor = selected
+            .and_then(|s| self.visible().iter().position(|n| n.path == s))
+            .map_or(0, |i| i + 1);
+        if self.paths.is_empty() {
+            self.focused = false;
+        }
+    }
+
+    /// 是否没有任何分组，此时不显示分组树
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// 可见的分组，即其各级上级分组均未折叠
+    pub fn visible(&self) -> Vec<GroupNode<'_>> {
+        let mut nodes = Vec::with_capacity(self.paths.len());
+        // 被折叠的分组路径，其下级分组不可见
+        let mut hidden_under: Option<&str> = None;
+        for (i, path) in self.paths.iter().enumerate() {
+            if let Some(hidden) = hidden_under {
+                if is_descendant(path, hidden) {
+                    continue;
+                }
+                hidden_under = None;
+            }
+            let collapsed = self.collapsed.contains(path);
+            if collapsed {
+                hidden_under = Some(path);
+            }
+            let (depth, name) = match path.rsplit_once(GROUP_SEPARATOR) {
+                Some((_, name)) => (path.matches(GROUP_SEPARATOR).count(), name),
+                None => (0, path.as_str()),
+            };
+            nodes.push(GroupNode {
+                path,
+                name,
+                depth,
+                has_children: self.paths.get(i + 1).is_some_and(|next| is_descendant(next, path)),
+                collapsed,
+            });
+        }
+        nodes
+    }
+
+    /// 光标在可见节点中的位置，0 为全部条目
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// 光标所指的分组路径，None 即全部条目
+    pub fn selected(&self) -> Option<&str> {
+        let i = self.cursor.checked_sub(1)?;
+        self.visible().get(i).map(|n| n.path)
+    }
+
+    /// 条目是否在光标所指的分组（或其下级分组）中，光标指向全部条目时总是
+    pub fn contains(&self, entry: &EncryptedEntry) -> bool {
+        match self.selected() {
+            Some(path) => entry.in_group(path),
+            None => true,
+        }
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused && !self.is_empty();
+    }
+
+    pub fn cursor_down(&mut self) {
+        let len = self.visible().len() + 1;
+        self.cursor = (self.cursor + 1) % len;
+    }
+
+    pub fn cursor_up(&mut self) {
+        let len = self.visible().len() + 1;
+        self.cursor = (self.cursor + len - 1) % len;
+    }
+
+    /// 折叠光标所指的分组，其已折叠或没有下级分组时光标移至其上级分组
+    pub fn collapse(&mut self) {
+        let visible = self.visible();
+        let Some(node) = self.cursor.checked_sub(1).and_then(|i| visible.get(i)) else {
+            return;
+        };
+        if node.has_children && !node.collapsed {
+            let path = node.path.to_owned();
+            self.collapsed.insert(path);
+        } else if let Some((parent, _)) = node.path.rsplit_once(GROUP_SEPARATOR) {
+            self.cursor = visible.iter().position(|n| n.path == parent).map_or(0, |i| i + 1);
+        }
+    }
+
+    /// 展开光标所指的分组
+    pub fn expand(&mut self) {
+        if let Some(path) = self.selected().map(str::to_owned) {
+            self.collapsed.remove(&path);
+        }
+    }
+
+    /// 折叠或展开光标所指的分组
+    pub fn toggle_collapsed(&mut self) {
+        if let Some(path) = self.selected().map(str::to_owned)
+            && !self.collapsed.remove(&path)
+        {
+            self.collapsed.insert(path);
+        }
+    }
+}
+
+/// path 是否为 ancestor 的下级分组
+fn is_descendant(path: &str, ancestor: &str) -> bool {
+    path.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.starts_with(GROUP_SEPARATOR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u32, group: Option<&str>) -> EncryptedEntry {
+        EncryptedEntry {
+            id,
+            about: String::new(),
+            notes: None,
+            encrypted_username: String::new(),
+            encrypted_password: String::new(),
+            created_time: Default::default(),
+            updated_time: Default::default(),
+            tags: Vec::new(),
+            group: group.map(str::to_owned),
+            fields: None,
+        }
+    }
+
+    #[test]
+    fn test_group_tree() {
+        let entries = [
+            entry(1, Some("work/aws/prod")),
+            entry(2, Some("Home")),
+            entry(3, Some("work/mail")),
+            entry(4, None),
+        ];
+        let mut tree = GroupTreeState::default();
+        tree.reset(entries.iter());
+        let paths: Vec<_> = tree.visible().iter().map(|n| n.path).collect();
+        assert_eq!(paths, ["Home", "work", "work/aws", "work/aws/prod", "work/mail"]);
+        assert!(entries.iter().all(|e| tree.contains(e)));
+
+        // 选中 work，包括下级分组的条目
+        tree.cursor_down();
+        tree.cursor_down();
+        assert_eq!(tree.selected(), Some("work"));
+        let ids: Vec<_> = entries.iter().filter(|e| tree.contains(e)).map(|e| e.id).collect();
+        assert_eq!(ids, [1, 3]);
+
+        // 折叠 work 后其下级分组不可见，再次折叠无变化
+        tree.collapse();
+        assert_eq!(tree.visible().len(), 2);
+        tree.toggle_collapsed();
+        assert_eq!(tree.visible().len(), 5);
+        // 在没有下级分组的分组上折叠，光标移至其上级分组
+        tree.cursor_down();
+        tree.cursor_down();
+        assert_eq!(tree.selected(), Some("work/aws/prod"));
+        tree.collapse();
+        assert_eq!(tree.selected(), Some("work/aws"));
+        tree.collapse();
+        assert_eq!(tree.visible().len(), 4);
+
+        // 重设后保持光标所指的分组，其不再存在则回到全部条目
+        tree.reset(entries.iter());
+        assert_eq!(tree.selected(), Some("work/aws"));
+        tree.reset(entries[1..].iter());
+        assert_eq!(tree.selected(), None);
+        tree.cursor_up();
+        assert_eq!(tree.selected(), Some("work/mail"));
+    }
+}
@@ -1,26 +1,134 @@
-use crate::app::consts::ALLOC_INVALID_MAIN_PASS_MAX;
+use crate::app::cfg::InnerCfg;
+use crate::app::consts::{ALLOC_INVALID_MAIN_PASS_MAX, PASSWORD_MASK_CHAR};
 use crate::app::context::{PntContext, SecurityContext};
 use crate::app::crypto::{Encrypter, MainPwdVerifier};
-use crate::app::entry::{EncryptedEntry, InputEntry, ValidEntry};
+use crate::app::entry::{CustomField, EncryptedEntry, InputEntry, TrashedEntry, ValidEntry, normalize_group_path, parse_tags};
 use crate::app::errors::AppError::InvalidPassword;
-use crate::app::tui::TUIApp;
+use crate::app::failed_unlock::secs_until;
+use crate::app::fuzzy::{self, EntryMatch};
+use crate::app::secret::SecretString;
+use crate::app::tui::components::group_tree::GroupTreeState;
 use crate::app::tui::events::Action;
-use crate::app::tui::intents::ScreenIntent;
-use crate::app::tui::ui::{TextAreaExt, new_input_textarea};
-use anyhow::{Context, anyhow};
+use crate::app::tui::ui::{TextAreaExt, new_input_textarea, tag_chips};
+use anyhow::anyhow;
+use chrono::{DateTime, Local};
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::prelude::Widget;
 use ratatui::widgets::{ScrollbarState, TableState};
 use tui_textarea::TextArea;
+use std::fmt::{Debug, Formatter};
 use unicode_width::UnicodeWidthStr;
+use zeroize::Zeroize;
+
+/// 密码的掩码/显示状态，显示后超过配置的秒数重新掩码
+#[derive(Debug, Clone, Copy)]
+pub struct PasswordReveal {
+    revealed: bool,
+    /// 距重新掩码的剩余秒数，响应tick自减
+    countdown: u32,
+    /// 0表示不因超时重新掩码
+    reveal_sec: u32,
+}
 
-#[derive(Debug, Clone)]
+impl PasswordReveal {
+    /// 按配置的默认状态新建
+    pub fn new(inner_cfg: &InnerCfg) -> Self {
+        let mut reveal = Self {
+            revealed: false,
+            countdown: 0,
+            reveal_sec: inner_cfg.reveal_password_sec.unwrap_or(0),
+        };
+        if inner_cfg.reveal_password {
+            reveal.toggle();
+        }
+        reveal
+    }
+
+    pub fn is_revealed(&self) -> bool {
+        self.revealed
+    }
+
+    /// 切换掩码/显示，显示时重新开始计时
+    pub fn toggle(&mut self) {
+        self.revealed = !self.revealed;
+        self.countdown = self.reveal_sec;
+    }
+
+    /// 每次tick调用之，返回是否因超时重新掩码
+    pub fn tick(&mut self) -> bool {
+        if !self.revealed || self.reveal_sec == 0 {
+            return false;
+        }
+        self.countdown = self.countdown.saturating_sub(1);
+        if self.countdown == 0 {
+            self.revealed = false;
+        }
+        !self.revealed
+    }
+}
+
+#[derive(Clone)]
 pub struct EditingState {
-    editing: Editing,
-    input_textarea: [TextArea<'static>; 4],
+    editing: EditingFocus,
+    input_textarea: [TextArea<'static>; 6],
+    /// 自定义字段的输入框，位于 tags 与 notes 之间
+    fields: Vec<FieldInput>,
     /// 正在编辑的条目id，若为None，则表示正在编辑的条目为新建条目
     e_id: Option<u32>,
+    /// password 及秘密字段值输入框的掩码状态
+    password_reveal: PasswordReveal,
+}
+
+/// 编辑中的一个自定义字段，名称及值各一个输入框
+#[derive(Clone)]
+pub struct FieldInput {
+    pub name: TextArea<'static>,
+    pub value: TextArea<'static>,
+    pub secret: bool,
+}
+
+impl FieldInput {
+    fn new(secret: bool) -> Self {
+        let mut value = new_input_textarea(None, false);
+        // 字段可随时切换为秘密，遂值均不保留撤销历史，以免其中留有明文副本
+        value.set_max_histories(0);
+        Self {
+            name: new_input_textarea(Some("field name, e.g. api key"), false),
+            value,
+            secret,
+        }
+    }
+}
+
+/// 同 [`EditingState`]，字段值（可随时切换为秘密）在 drop 时擦除
+impl Drop for FieldInput {
+    fn drop(&mut self) {
+        wipe_textarea(&mut self.value);
+    }
+}
+
+/// 擦除输入框的所有行
+fn wipe_textarea(textarea: &mut TextArea<'static>) {
+    std::mem::take(textarea).into_lines().zeroize();
+}
+
+/// 编辑中的条目为明文，Debug 不输出其内容
+impl Debug for EditingState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EditingState")
+            .field("editing", &self.editing)
+            .field("fields", &self.fields.len())
+            .field("e_id", &self.e_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 编辑中的 password 为明文，drop 时擦除其输入框，秘密字段值由 [`FieldInput`] 的 drop 擦除
+impl Drop for EditingState {
+    fn drop(&mut self) {
+        wipe_textarea(&mut self.input_textarea[Editing::Password]);
+    }
 }
 
 impl EditingState {
@@ -30,69 +138,143 @@ impl EditingState {
             username: self.value(Editing::Username),
             password: self.value(Editing::Password),
             notes: self.value(Editing::Notes),
+            tags: parse_tags(&self.value(Editing::Tags)),
+            group: normalize_group_path(&self.value(Editing::Group)),
+            // 名称及值均为空的字段忽略
+            fields: self
+                .fields
+                .iter()
+                .map(|f| CustomField {
+                    name: single_line_value(&f.name).trim().to_owned(),
+                    value: single_line_value(&f.value),
+                    secret: f.secret,
+                })
+                .filter(|f| !f.name.is_empty() || !f.value.is_empty())
+                .collect(),
         }
     }
 
-    /// 返回当前正在编辑的字段是哪一个
-    pub fn current_editing_type(&self) -> Editing {
+    /// 返回当前焦点所在的输入框
+    pub fn current_editing_type(&self) -> EditingFocus {
         self.editing
     }
 
+    /// 当前是否在编辑 notes
+    pub fn is_editing_notes(&self) -> bool {
+        self.editing == EditingFocus::Input(Editing::Notes)
+    }
+
     /// 返回当前正在编辑的字段的可变引用
     pub fn current_editing_string_mut(&mut self) -> &mut TextArea<'static> {
-        &mut self.input_textarea[self.editing]
+        self.textarea_mut(self.editing)
     }
 
-    pub fn new_updating(u_input: InputEntry, e_id: u32) -> Self {
-        let mut new = Self::new_creating();
-        new.input_textarea[0].insert_str(u_input.about);
-        new.input_textarea[1].insert_str(u_input.username);
-        new.input_textarea[2].insert_str(u_input.password);
-        new.input_textarea[3].insert_str(u_input.notes);
+    fn textarea_mut(&mut self, focus: EditingFocus) -> &mut TextArea<'static> {
+        match focus {
+            EditingFocus::Input(editing) => &mut self.input_textarea[editing],
+            EditingFocus::FieldName(i) => &mut self.fields[i].name,
+            EditingFocus::FieldValue(i) => &mut self.fields[i].value,
+        }
+    }
+
+    pub fn new_updating(u_input: InputEntry, e_id: u32, password_reveal: PasswordReveal) -> Self {
+        let mut new = Self::new_creating(password_reveal);
+        new.input_textarea[Editing::About].insert_str(&u_input.about);
+        new.input_textarea[Editing::Username].insert_str(&u_input.username);
+        new.input_textarea[Editing::Password].insert_str(&u_input.password);
+        new.input_textarea[Editing::Group].insert_str(&u_input.group);
+        new.input_textarea[Editing::Tags].insert_str(u_input.tags.join(" "));
+        new.input_textarea[Editing::Notes].insert_str(&u_input.notes);
+        for field in &u_input.fields {
+            let mut input = FieldInput::new(field.secret);
+            input.name.insert_str(&field.name);
+            input.value.insert_str(&field.value);
+            new.fields.push(input);
+        }
         new.e_id = Some(e_id);
+        new.apply_password_mask();
         new
     }
 
-    pub fn new_creating() -> Self {
+    pub fn new_creating(password_reveal: PasswordReveal) -> Self {
         let editing = Editing::default();
-        let mut textarea4 = Self::new4();
-        textarea4[editing].set_activate_state(true); // 光标可见
-        Self {
-            editing,
-            input_textarea: textarea4,
+        let mut textarea6 = Self::new6();
+        textarea6[editing].set_activate_state(true); // 光标可见
+        let mut new = Self {
+            editing: EditingFocus::Input(editing),
+            input_textarea: textarea6,
+            fields: Vec::new(),
             e_id: None,
+            password_reveal,
+        };
+        new.apply_password_mask();
+        new
+    }
+
+    /// 切换 password 及秘密字段值输入框的掩码/显示
+    pub fn toggle_password_reveal(&mut self) {
+        self.password_reveal.toggle();
+        self.apply_password_mask();
+    }
+
+    /// 每次tick调用之，超时则重新掩码 password 及秘密字段值输入框
+    pub fn tick_password_reveal(&mut self) {
+        if self.password_reveal.tick() {
+            self.apply_password_mask();
         }
     }
 
-    /// 输入框4个
-    fn new4() -> [TextArea<'static>; 4] {
+    fn apply_password_mask(&mut self) {
+        let revealed = self.password_reveal.is_revealed();
+        let masked = std::iter::once(&mut self.input_textarea[Editing::Password])
+            .map(|textarea| (textarea, true))
+            .chain(self.fields.iter_mut().map(|f| (&mut f.value, f.secret)));
+        for (textarea, secret) in masked {
+            if secret && !revealed {
+                textarea.set_mask_char(PASSWORD_MASK_CHAR);
+            } else {
+                textarea.clear_mask_char();
+            }
+        }
+    }
+
+    /// 输入框6个
+    fn new6() -> [TextArea<'static>; 6] {
         [
             new_input_textarea(Some("require about"), false),
             new_input_textarea(Some("require username"), false),
-            new_input_textarea(Some("require password"), false),
+            Self::new_password_textarea(false),
+            new_input_textarea(Some("separated by '/', e.g. work/aws"), false),
+            new_input_textarea(Some("separated by spaces, e.g. work mail"), false),
             new_input_textarea(None, false),
         ]
     }
 
+    /// password 输入框，不保留撤销历史，以免其中留有明文副本
+    fn new_password_textarea(activate_state: bool) -> TextArea<'static> {
+        let mut textarea = new_input_textarea(Some("require password"), activate_state);
+        textarea.set_max_histories(0);
+        textarea
+    }
+
     /// 返回指定的输入框
     pub fn textarea(&self, editing: Editing) -> &TextArea<'static> {
         &self.input_textarea[editing]
     }
     /// 返回持有的所有textarea切片引用
-    pub fn all_textarea(&self) -> &[TextArea<'static>; 4] {
+    pub fn all_textarea(&self) -> &[TextArea<'static>; 6] {
         &self.input_textarea
     }
+    /// 返回所有自定义字段的输入框
+    pub fn field_inputs(&self) -> &[FieldInput] {
+        &self.fields
+    }
 
     /// 某个框的内容
     fn value(&self, editing: Editing) -> String {
         match editing {
             Editing::Notes => self.input_textarea[editing].lines().join("\n"),
-            // textarea的lines.last().unwrap一定不会panic，因为其即使空字符串一定有值""...
-            _ => self.input_textarea[editing]
-                .lines()
-                .last()
-                .unwrap()
-                .to_owned(),
+            _ => single_line_value(&self.input_textarea[editing]),
         }
     }
 
@@ -100,29 +282,99 @@ impl EditingState {
         self.e_id
     }
 
-    /// 光标向上移动，若当前光标为Name，则移动到Password
+    /// 焦点的顺序：about username password group tags，各自定义字段的名称及值，notes
+    fn focus_order(&self) -> Vec<EditingFocus> {
+        let mut order: Vec<_> = [Editing::About, Editing::Username, Editing::Password, Editing::Group, Editing::Tags]
+            .into_iter()
+            .map(EditingFocus::Input)
+            .collect();
+        for i in 0..self.fields.len() {
+            order.extend([EditingFocus::FieldName(i), EditingFocus::FieldValue(i)]);
+        }
+        order.push(EditingFocus::Input(Editing::Notes));
+        order
+    }
+
+    /// 将焦点移至给定输入框，隐藏原焦点输入框的光标
+    fn focus(&mut self, focus: EditingFocus) {
+        self.textarea_mut(self.editing).set_activate_state(false);
+        self.editing = focus;
+        self.textarea_mut(self.editing).set_activate_state(true);
+    }
+
+    /// 光标向上移动，若当前光标为about，则移动到notes
     pub fn cursor_up(&mut self) {
-        // 将当前置位光标隐藏，将新的置位光标不隐藏
-        self.input_textarea[self.editing].set_activate_state(false);
-        self.editing = match self.editing {
-            Editing::About => Editing::Notes,
-            Editing::Username => Editing::About,
-            Editing::Password => Editing::Username,
-            Editing::Notes => Editing::Password,
-        };
-        self.input_textarea[self.editing].set_activate_state(true);
+        let order = self.focus_order();
+        let idx = order.iter().position(|f| *f == self.editing).unwrap_or_default();
+        self.focus(order[(idx + order.len() - 1) % order.len()]);
     }
 
-    /// 光标向下移动，若当前光标为Password，则移动到Name
+    /// 光标向下移动，若当前光标为notes，则移动到about
     pub fn cursor_down(&mut self) {
-        self.input_textarea[self.editing].set_activate_state(false);
-        self.editing = match self.editing {
-            Editing::About => Editing::Username,
-            Editing::Username => Editing::Password,
-            Editing::Password => Editing::Notes,
-            Editing::Notes => Editing::About,
+        let order = self.focus_order();
+        let idx = order.iter().position(|f| *f == self.editing).unwrap_or_default();
+        self.focus(order[(idx + 1) % order.len()]);
+    }
+
+    /// 焦点所在的自定义字段的位置
+    fn focused_field(&self) -> Option<usize> {
+        match self.editing {
+            EditingFocus::FieldName(i) | EditingFocus::FieldValue(i) => Some(i),
+            EditingFocus::Input(_) => None,
+        }
+    }
+
+    /// 在焦点所在的自定义字段之后（焦点不在自定义字段上时为末尾）新增一个自定义字段，焦点移至其名称
+    pub fn add_field(&mut self) {
+        let i = self.focused_field().map_or(self.fields.len(), |i| i + 1);
+        self.fields.insert(i, FieldInput::new(false));
+        // 插入位置之后的字段下标后移，焦点随之
+        if let Some(j) = self.focused_field()
+            && j >= i
+        {
+            self.editing = match self.editing {
+                EditingFocus::FieldName(_) => EditingFocus::FieldName(j + 1),
+                _ => EditingFocus::FieldValue(j + 1),
+            };
+        }
+        self.focus(EditingFocus::FieldName(i));
+    }
+
+    /// 删除焦点所在的自定义字段，焦点移至其后的字段，没有则其前的字段或 tags，
+    /// 返回是否有字段被删除
+    pub fn remove_field(&mut self) -> bool {
+        let Some(i) = self.focused_field() else {
+            return false;
+        };
+        self.fields.remove(i);
+        let next = if i < self.fields.len() {
+            EditingFocus::FieldName(i)
+        } else if i > 0 {
+            EditingFocus::FieldName(i - 1)
+        } else {
+            EditingFocus::Input(Editing::Tags)
         };
-        self.input_textarea[self.editing].set_activate_state(true);
+        // 被删除的输入框无需隐藏光标
+        self.editing = next;
+        self.textarea_mut(next).set_activate_state(true);
+        true
+    }
+
+    /// 切换焦点所在的自定义字段是否为秘密字段，返回切换后的状态，焦点不在自定义字段上则 None
+    pub fn toggle_field_secret(&mut self) -> Option<bool> {
+        let i = self.focused_field()?;
+        self.fields[i].secret = !self.fields[i].secret;
+        self.apply_password_mask();
+        Some(self.fields[i].secret)
+    }
+
+    /// 以给定密码替换 password 输入框的内容，焦点移至 password 输入框
+    pub fn fill_password(&mut self, password: &str) {
+        self.focus(EditingFocus::Input(Editing::Password));
+        let mut textarea = Self::new_password_textarea(true);
+        textarea.insert_str(password);
+        wipe_textarea(&mut std::mem::replace(&mut self.input_textarea[Editing::Password], textarea));
+        self.apply_password_mask();
     }
 
     /// 当前输入是否有效
@@ -131,11 +383,12 @@ impl EditingState {
     /// * about 不为空
     /// * username 不为空
     /// * password 不为空
+    /// * 有值的自定义字段的名称不为空
     ///
     /// # Panics
     /// 当 about username password中任意一个有多行内容时
     pub fn current_input_validate(&self) -> bool {
-        // 0,1,2 notes 不校验
+        // 0,1,2 group tags notes 不校验
         for idx in 0..3usize {
             let text_area = &self.input_textarea[idx];
             // 不得为空
@@ -149,23 +402,30 @@ impl EditingState {
                 panic!("Invalid input");
             }
         }
-        true
+        self.current_input_entry().validate()
     }
 
     /// 尝试加密 UserInputEntry 为 ValidInsertEntry
     /// 当 UserInputEntry 不合法时，该方法会返回错误
-    /// 当 UserInputEntry 合法时, 该方法会返回 ValidInsertEntry 和 可能的 条目id
-    /// 当条目id为None时，表示该条目为新建条目, 反之则为更新条目
-    pub fn try_encrypt<Enc>(&self, encrypter: &Enc) -> anyhow::Result<ValidEntry>
+    /// 当 UserInputEntry 合法时, 该方法会返回 ValidInsertEntry，
+    /// 密文与给定的条目id绑定，新建条目应先分配id
+    pub fn try_encrypt<Enc>(&self, e_id: u32, encrypter: &Enc) -> anyhow::Result<ValidEntry>
     where
-        Enc: for<'a> Encrypter<&'a InputEntry, ValidEntry>,
+        Enc: for<'a> Encrypter<(u32, &'a InputEntry), ValidEntry>,
     {
         if !self.current_input_validate() {
             return Err(anyhow!("input not validate"));
         }
-        Ok(encrypter.encrypt(&self.current_input_entry())?)
+        Ok(encrypter.encrypt((e_id, &self.current_input_entry()))?)
     }
 }
+
+/// 单行输入框的内容
+fn single_line_value(textarea: &TextArea<'static>) -> String {
+    // textarea的lines.last().unwrap一定不会panic，因为其即使空字符串一定有值""...
+    textarea.lines().last().unwrap().to_owned()
+}
+
 /// 表示正在编辑 UserInputEntry的 哪一个
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
 #[repr(u8)]
@@ -174,16 +434,29 @@ pub enum Editing {
     About = 0_u8,
     Username = 1_u8,
     Password = 2_u8,
-    Notes = 3_u8,
+    Group = 3_u8,
+    Tags = 4_u8,
+    Notes = 5_u8,
 }
 
-impl<T> std::ops::Index<Editing> for [T; 4] {
+/// 编辑页面焦点所在的输入框
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EditingFocus {
+    /// 固定的输入框
+    Input(Editing),
+    /// 第 n 个自定义字段的名称
+    FieldName(usize),
+    /// 第 n 个自定义字段的值
+    FieldValue(usize),
+}
+
+impl<T> std::ops::Index<Editing> for [T; 6] {
     type Output = T;
     fn index(&self, editing: Editing) -> &T {
         &self[editing as usize]
     }
 }
-impl<T> std::ops::IndexMut<Editing> for [T; 4] {
+impl<T> std::ops::IndexMut<Editing> for [T; 6] {
     fn index_mut(&mut self, editing: Editing) -> &mut T {
         &mut self[editing as usize]
     }
@@ -197,12 +470,18 @@ pub struct HomePageV1State {
     find_input: TextArea<'static>,
     /// 显示的 entry，该中应是已排序且被find过滤的
     display_entries: Vec<EncryptedEntry>,
+    /// 显示的 entry 各自与 find 输入匹配的字符位置，与 display_entries 一一对应，无 find 输入时为空匹配
+    display_matches: Vec<EntryMatch>,
     /// 添加ListState来控制滚动
     cursor: TableState,
     /// 垂直滚动条样式
     scrollbar_state: ScrollbarState,
     /// 存储的 entries 中 about 内容 最长的占用的字符个数（中文会占用多个字符宽度）
     max_entry_about_width: u16,
+    /// 同上，显示的 entries 中 tags 显示内容最长的宽度
+    max_entry_tags_width: u16,
+    /// 左侧的分组树，其选中的分组过滤显示的 entry
+    group_tree: GroupTreeState,
 }
 
 impl HomePageV1State {
@@ -214,9 +493,12 @@ impl HomePageV1State {
             find_mode: false,
             find_input: new_input_textarea(Some("find"), false),
             display_entries: Vec::with_capacity(0),
+            display_matches: Vec::with_capacity(0),
             cursor: TableState::default(),
             scrollbar_state: ScrollbarState::default(),
             max_entry_about_width: 0,
+            max_entry_tags_width: 0,
+            group_tree: GroupTreeState::default(),
         };
         state.reset_display_entries(entries.iter());
         state
@@ -263,6 +545,19 @@ impl HomePageV1State {
         &self.display_entries
     }
 
+    pub fn group_tree(&self) -> &GroupTreeState {
+        &self.group_tree
+    }
+
+    pub fn group_tree_mut(&mut self) -> &mut GroupTreeState {
+        &mut self.group_tree
+    }
+
+    /// 显示的 entry 各自与 find 输入匹配的字符位置，见 [`HomePageV1State::display_entries`]
+    pub fn display_matches(&self) -> &[EntryMatch] {
+        &self.display_matches
+    }
+
     pub fn cursor_down(&mut self) {
         if let Some(p) = self.cursor_selected() {
             if p >= self.display_entries.len() - 1 {
@@ -294,37 +589,58 @@ impl HomePageV1State {
     }
 
     /// 根据给定entries重设homePage显示的entry，
-    /// 该方法内会进行 find过滤、entries 的 sort，滚动条及光标行位置等的重设
+    /// 该方法内会进行 分组树的重设、分组及find过滤、entries 的 sort，滚动条及光标行位置等的重设
+    ///
+    /// 有 find 输入时以模糊匹配过滤（`#` 前缀的词匹配标签），按匹配得分排序（得分相同按更新时间），
+    /// 否则按更新时间排序
     pub fn reset_display_entries<'a>(&mut self, entries: impl Iterator<Item = &'a EncryptedEntry>) {
         // 因为Iter终止不定，遂对其sort不可行，这里要创建vec，排序其，遂有一定开销，
         // 后续或应优化其使其不创建中间vec
         // 还有引用的clone到创建一个EncEntry是否能优化？
 
+        // 分组树由所有 entries 得出，再以其选中的分组过滤
+        let entries: Vec<_> = entries.collect();
+        self.group_tree.reset(entries.iter().copied());
+        let entries = entries.into_iter().filter(|e| self.group_tree.contains(e));
         // 判定是否find filter
-        let mut enc_entries: Vec<_> = if self.find_input.is_empty() {
-            entries.cloned().collect()
+        let mut matched: Vec<_> = if self.find_input.is_empty() {
+            entries.map(|e| (e.clone(), EntryMatch::default())).collect()
         } else {
-            let lower_find_input = self.current_find_input().to_ascii_lowercase();
-            let it = entries
-                .into_iter()
-                .filter(move |&e| e.about.to_ascii_lowercase().contains(&lower_find_input));
-            it.cloned().collect()
+            let query = self.current_find_input();
+            entries
+                .filter_map(|e| fuzzy::match_entry(query, e).map(|m| (e.clone(), m)))
+                .collect()
         };
-        // 根据update time 排序之
-        enc_entries.sort_unstable_by(EncryptedEntry::sort_by_update_time_desc);
+        // 根据匹配得分及update time 排序之
+        matched.sort_unstable_by(|(a, a_match), (b, b_match)| {
+            b_match
+                .score
+                .cmp(&a_match.score)
+                .then_with(|| EncryptedEntry::sort_by_update_time_desc(a, b))
+        });
+        let (enc_entries, matches): (Vec<_>, Vec<_>) = matched.into_iter().unzip();
+        self.display_matches = matches;
         // about 最长的
         self.max_entry_about_width = enc_entries
             .iter()
             .map(|e| e.about.width_cjk())
             .max()
             .unwrap_or(0) as u16;
+        self.max_entry_tags_width = enc_entries
+            .iter()
+            .map(|e| tag_chips(&e.tags).width_cjk())
+            .max()
+            .unwrap_or(0) as u16;
         // 滚动条
         self.scrollbar_state = self.scrollbar_state.content_length(enc_entries.len());
         self.display_entries = enc_entries;
         // table光标
         if !self.display_entries().is_empty() {
-            if self.cursor_selected().is_none() {
-                self.cursor.select(Some(0))
+            match self.cursor_selected() {
+                None => self.cursor.select(Some(0)),
+                // 过滤后条目变少时光标不超出之
+                Some(p) if p >= self.display_entries.len() => self.cursor.select(Some(self.display_entries.len() - 1)),
+                Some(_) => {}
             }
         } else {
             self.cursor.select(None);
@@ -337,12 +653,187 @@ impl HomePageV1State {
     pub fn max_about_width(&self) -> u16 {
         self.max_entry_about_width
     }
+
+    /// 返回当中的一系列entry的tags显示内容的最大显示字符宽度，均无标签则为0
+    pub fn max_tags_width(&self) -> u16 {
+        self.max_entry_tags_width
+    }
+}
+
+/// 历史版本页面中的一个版本，entry 为其解密后的明文
+#[derive(Debug, Clone)]
+pub struct HistoryVersion {
+    /// 历史版本的id
+    pub h_id: u32,
+    /// 该版本保存的时间
+    pub saved_time: DateTime<Local>,
+    /// 该版本被替换的时间
+    pub archived_time: DateTime<Local>,
+    pub entry: InputEntry,
+}
+
+/// 条目历史版本页面的状态，版本新的在前
+#[derive(Debug, Clone)]
+pub struct HistoryState {
+    e_id: u32,
+    /// 条目当前的 about，用于标题
+    about: String,
+    versions: Vec<HistoryVersion>,
+    cursor: TableState,
+    /// 光标所指版本的 password 的掩码状态，其余版本总是掩码
+    password_reveal: PasswordReveal,
+}
+
+impl HistoryState {
+    pub fn new(e_id: u32, about: String, versions: Vec<HistoryVersion>, password_reveal: PasswordReveal) -> Self {
+        let cursor = TableState::default().with_selected((!versions.is_empty()).then_some(0));
+        Self {
+            e_id,
+            about,
+            versions,
+            cursor,
+            password_reveal,
+        }
+    }
+
+    pub fn e_id(&self) -> u32 {
+        self.e_id
+    }
+
+    pub fn about(&self) -> &str {
+        &self.about
+    }
+
+    pub fn versions(&self) -> &[HistoryVersion] {
+        &self.versions
+    }
+
+    /// 光标所指的版本
+    pub fn selected(&self) -> Option<&HistoryVersion> {
+        self.cursor.selected().and_then(|i| self.versions.get(i))
+    }
+
+    pub fn cursor_mut_ref(&mut self) -> &mut TableState {
+        &mut self.cursor
+    }
+
+    pub fn cursor_down(&mut self) {
+        if let Some(i) = self.cursor.selected() {
+            self.cursor.select(Some((i + 1).min(self.versions.len() - 1)));
+        }
+    }
+
+    pub fn cursor_up(&mut self) {
+        if let Some(i) = self.cursor.selected() {
+            self.cursor.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    pub fn cursor_first(&mut self) {
+        if !self.versions.is_empty() {
+            self.cursor.select(Some(0));
+        }
+    }
+
+    /// ratatui 的 select_last 将光标置为 usize::MAX，遂显式给定位置
+    pub fn cursor_last(&mut self) {
+        if !self.versions.is_empty() {
+            self.cursor.select(Some(self.versions.len() - 1));
+        }
+    }
+
+    pub fn is_password_revealed(&self) -> bool {
+        self.password_reveal.is_revealed()
+    }
+
+    pub fn toggle_password_reveal(&mut self) {
+        self.password_reveal.toggle();
+    }
+
+    /// 每次tick调用之，显示的密码超时则重新掩码
+    pub fn tick_password_reveal(&mut self) {
+        self.password_reveal.tick();
+    }
+}
+
+/// 回收站页面的状态，条目最近删除的在前，其 about 及 notes 为明文
+#[derive(Debug, Clone)]
+pub struct TrashState {
+    entries: Vec<TrashedEntry>,
+    cursor: TableState,
+    /// 回收站中的条目在多少天后永久删除，0 即不自动删除
+    purge_days: u32,
+}
+
+impl TrashState {
+    pub fn new(entries: Vec<TrashedEntry>, purge_days: u32) -> Self {
+        let mut state = Self {
+            entries: Vec::new(),
+            cursor: TableState::default(),
+            purge_days,
+        };
+        state.reset_entries(entries);
+        state
+    }
+
+    /// 重设显示的条目（如恢复或永久删除后），光标位置保持但不越界
+    pub fn reset_entries(&mut self, entries: Vec<TrashedEntry>) {
+        self.entries = entries;
+        let selected = match self.entries.len() {
+            0 => None,
+            len => Some(self.cursor.selected().unwrap_or(0).min(len - 1)),
+        };
+        self.cursor.select(selected);
+    }
+
+    pub fn entries(&self) -> &[TrashedEntry] {
+        &self.entries
+    }
+
+    pub fn purge_days(&self) -> u32 {
+        self.purge_days
+    }
+
+    /// 光标所指的条目
+    pub fn selected(&self) -> Option<&TrashedEntry> {
+        self.cursor.selected().and_then(|i| self.entries.get(i))
+    }
+
+    pub fn cursor_mut_ref(&mut self) -> &mut TableState {
+        &mut self.cursor
+    }
+
+    pub fn cursor_down(&mut self) {
+        if let Some(i) = self.cursor.selected() {
+            self.cursor.select(Some((i + 1).min(self.entries.len() - 1)));
+        }
+    }
+
+    pub fn cursor_up(&mut self) {
+        if let Some(i) = self.cursor.selected() {
+            self.cursor.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    pub fn cursor_first(&mut self) {
+        if !self.entries.is_empty() {
+            self.cursor.select(Some(0));
+        }
+    }
+
+    pub fn cursor_last(&mut self) {
+        if !self.entries.is_empty() {
+            self.cursor.select(Some(self.entries.len() - 1));
+        }
+    }
 }
 
 /// 主密码输入界面状态
 pub struct VerifyMPHState {
-    pub mp_input: String,
+    pub mp_input: SecretString,
     retry_count: u8,
+    /// 允许下次尝试的时间，见 [`PntContext::unlock_retry_after`]
+    retry_after: Option<DateTime<Local>>,
     verifier: MainPwdVerifier,
     /// 验证成功的回调函数
     verified_callback: Option<Box<dyn FnOnce() -> Action>>,
@@ -356,27 +847,23 @@ impl VerifyMPHState {
     ) -> anyhow::Result<Self> {
         let r = Self {
             verifier: context.mpv()?,
-            mp_input: String::new(),
+            mp_input: SecretString::default(),
             retry_count: 0,
+            retry_after: context.unlock_retry_after()?,
             cancel_callback,
             verified_callback: Some(verified_callback),
         };
         Ok(r)
     }
 
-    /// 调用 verified_callback
+    /// 调用 verified_callback，
+    /// 该方法应当在 try_build_security_context 返回 Some 之后调用，即一定验证了主密码，
+    /// 遂不再校验（校验需一次 KDF）
     ///
     /// # Panics
-    /// 当前状态不是校验成功状态或闭包fnOnce已被消费
+    /// 闭包fnOnce已被消费
     pub fn call_verified(&mut self) -> Action {
-        let imp = &self.mp_input;
-        if self.verifier.verify(imp).unwrap() {
-            self.verified_callback.take().unwrap()()
-        } else {
-            // verify 不应当返回false，因为该方法应当在try_build_security_context之后调用
-            // 即一定验证了主密码
-            panic!("not verified main password");
-        }
+        self.verified_callback.take().expect("verified callback already called")()
     }
     pub fn call_cancel(&self) -> Action {
         (self.cancel_callback)()
@@ -387,18 +874,22 @@ impl VerifyMPHState {
     ///
     /// 若为 Err 表示在校验主密码过程或构建security_context过程发生错误
     pub fn try_build_security_context(&self) -> anyhow::Result<Option<SecurityContext>> {
-        let imp = &self.mp_input;
-        if self.verifier.verify(imp)? {
-            Ok(Some(self.verifier.load_security_context(imp)?))
-        } else {
-            Ok(None)
-        }
+        self.verifier.try_load_security_context(&self.mp_input)
     }
 
     pub fn retry_count(&self) -> u8 {
         self.retry_count
     }
 
+    /// 距允许下次尝试的剩余秒数，无需等待则 None
+    pub fn retry_wait_secs(&self) -> Option<u64> {
+        self.retry_after.and_then(secs_until)
+    }
+
+    pub fn set_retry_after(&mut self, retry_after: Option<DateTime<Local>>) {
+        self.retry_after = retry_after;
+    }
+
     /// 尝试自增重试次数，若重试次数到顶 ([`ALLOC_INVALID_MAIN_PASS_MAX`])
     /// 则返回 Err
     pub fn increment_retry_count(&mut self) -> anyhow::Result<()> {
@@ -411,3 +902,83 @@ impl VerifyMPHState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_reveal_remask() {
+        let mut inner_cfg = InnerCfg::default();
+        let mut reveal = PasswordReveal::new(&inner_cfg);
+        assert!(!reveal.is_revealed());
+        reveal.toggle();
+        // 未配置超时，不重新掩码
+        assert!(!(0..100).any(|_| reveal.tick()));
+        assert!(reveal.is_revealed());
+
+        inner_cfg.reveal_password = true;
+        inner_cfg.reveal_password_sec = Some(2);
+        let mut reveal = PasswordReveal::new(&inner_cfg);
+        assert!(reveal.is_revealed());
+        assert!(!reveal.tick());
+        assert!(reveal.tick());
+        assert!(!reveal.is_revealed());
+        // 已掩码则不再计时
+        assert!(!reveal.tick());
+    }
+
+    #[test]
+    fn test_editing_password_no_history() {
+        let mut state = EditingState::new_creating(PasswordReveal::new(&InnerCfg::default()));
+        state.fill_password("generated");
+        let password = state.current_editing_string_mut();
+        password.insert_str("-typed");
+        // 不保留撤销历史，撤销不会恢复之前的内容
+        assert!(!password.undo());
+        assert_eq!(password.lines(), ["generated-typed"]);
+        state.add_field();
+        state.cursor_down();
+        state.current_editing_string_mut().insert_str("1234");
+        assert!(!state.current_editing_string_mut().undo());
+    }
+
+    #[test]
+    fn test_editing_fields() {
+        let mut input = InputEntry::default();
+        input.about.push('a');
+        input.username.push('u');
+        input.password.push('p');
+        input.set_field("host", "example.com", false);
+        let mut state = EditingState::new_updating(input, 1, PasswordReveal::new(&InnerCfg::default()));
+        // 焦点顺序：tags 之后为自定义字段，其后为 notes
+        (0..5).for_each(|_| state.cursor_down());
+        assert_eq!(state.current_editing_type(), EditingFocus::FieldName(0));
+        state.cursor_down();
+        state.cursor_down();
+        assert!(state.is_editing_notes());
+
+        // 在第一个字段之后新增，有值却无名称的字段不可保存，名称及值均为空的字段忽略
+        state.cursor_up();
+        state.add_field();
+        assert_eq!(state.current_editing_type(), EditingFocus::FieldName(1));
+        assert!(state.current_input_validate());
+        state.cursor_down();
+        state.current_editing_string_mut().insert_str("1234");
+        assert!(!state.current_input_validate());
+        state.cursor_up();
+        state.current_editing_string_mut().insert_str("pin");
+        assert_eq!(state.toggle_field_secret(), Some(true));
+        let fields = state.current_input_entry().fields.clone();
+        assert_eq!(fields.len(), 2);
+        assert!(fields[1].secret && fields[1].name == "pin" && fields[1].value == "1234");
+
+        // 删除后焦点移至其前的字段，焦点不在字段上时不可删除
+        assert!(state.remove_field());
+        assert_eq!(state.current_editing_type(), EditingFocus::FieldName(0));
+        assert!(state.remove_field());
+        assert_eq!(state.current_editing_type(), EditingFocus::Input(Editing::Tags));
+        assert!(!state.remove_field());
+        assert!(state.current_input_entry().fields.is_empty());
+    }
+}
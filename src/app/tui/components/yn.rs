@@ -3,6 +3,8 @@ use crate::app::entry::{EncryptedEntry, InputEntry};
 use crate::app::tui::TUIApp;
 use crate::app::tui::colors::{CL_BLACK, CL_D_RED, CL_DD_RED, CL_DDD_RED, CL_L_BLACK, CL_WHITE};
 use crate::app::tui::events::Action;
+use crate::app::tui::ui::tag_chips;
+use chrono::{DateTime, Local};
 use ratatui::prelude::Color;
 
 /// 二分类枚举
@@ -157,20 +159,40 @@ impl YNState {
         } else {
             format!(" [!] SAVE CHANGE '{}' ? ", ie.about)
         };
+        let fields: String = ie
+            .fields
+            .iter()
+            .map(|f| {
+                let icon = if f.secret { "󰌿" } else { "󰦨" };
+                format!("[{icon} {}]: {}\n", f.name, f.value)
+            })
+            .collect();
         let tip_desc = format!(
             "[󰦨 about]:    {}\n\
              [󰌿 username]: {}\n\
              [󰌿 password]: {}\n\
+             [󰉋 group]:    {}\n\
+             [󰓹 tags]:     {}\n\
+             {}\
              -󰦨 notes-----\n{}",
-            &ie.about, &ie.username, &ie.password, e_notes_dots
+            &ie.about,
+            &ie.username,
+            &ie.password,
+            &ie.group,
+            tag_chips(&ie.tags),
+            fields,
+            e_notes_dots
         );
         let mut yn = Self::new(tip_title, tip_desc, Theme::THEME_SAVE);
         yn.set_y_call(Box::new(move |tui| {
-            let valid = tui.context.try_encrypter()?.encrypt(&ie)?;
             if let Some(e_id) = e_id {
-                tui.send_action(Action::EntryUpdate(valid, e_id))
+                let valid = tui.context.try_encrypter()?.encrypt((e_id, &ie))?;
+                tui.send_action(Action::EntryUpdate(valid, ie.tags.clone(), ie.group.clone()))
             } else {
-                tui.send_action(Action::EntryInsert(valid));
+                // 密文与条目id绑定，新建条目须先分配id
+                let e_id = tui.context.storage.next_entry_id();
+                let valid = tui.context.try_encrypter()?.encrypt((e_id, &ie))?;
+                tui.send_action(Action::EntryInsert(valid, ie.tags.clone(), ie.group.clone()))
             }
             // 响应该事件时 ，当前页面一定为 tips，所以回退到上一级页面（即召唤delete tips页面的页面)
             while !tui.screen.is_home_page() {
@@ -182,6 +204,57 @@ impl YNState {
         yn
     }
 
+    /// 永久删除回收站中条目页面用的
+    pub fn new_purge_tip(encrypted_entry: EncryptedEntry) -> Self {
+        let e_name = &encrypted_entry.about;
+        let e_desc = encrypted_entry.notes.as_ref().map_or("", |v| v);
+        let tip_title = format!(" [!] PERMANENTLY DELETE '{}' ? ", e_name);
+        let tip_desc = format!(
+            "[󰦨 about]: {}\n\
+             -󰦨 notes-----\n{}",
+            e_name, e_desc
+        );
+        let e_id = encrypted_entry.id;
+        let mut yn = Self::new(tip_title, tip_desc, Theme::THEME_DELETE);
+        yn.set_y_call(Box::new(move |tui| {
+            tui.send_action(Action::EntryPurge(e_id));
+            // 回退到召唤该页面的回收站页面
+            tui.back_screen();
+            Ok(())
+        }));
+        yn.set_n_call_back_screen();
+        yn
+    }
+
+    /// 恢复历史版本页面用的，encrypted_entry 为该历史版本（其 about 及 notes 应已为明文）
+    pub fn new_restore_tip(encrypted_entry: EncryptedEntry, h_id: u32, saved_time: DateTime<Local>) -> Self {
+        let e_name = &encrypted_entry.about;
+        let e_desc = encrypted_entry.notes.as_ref().map_or("", |v| v);
+        let tip_title = format!(" [!] RESTORE '{}' ? ", e_name);
+        let tip_desc = format!(
+            "[󰦨 about]: {}\n\
+             [󰥔 saved]: {}\n\
+             -󰦨 notes-----\n{}",
+            e_name,
+            saved_time.format("%Y-%m-%d %H:%M:%S"),
+            e_desc
+        );
+        let mut yn = Self::new(tip_title, tip_desc, Theme::THEME_SAVE);
+        yn.set_y_call(Box::new(move |tui| {
+            // 当前版本亦归档为历史版本，遂恢复可再次恢复以撤销
+            let keep = tui.context.cfg.inner_cfg.history_keep;
+            tui.context.storage.restore_entry_version(h_id, keep)?;
+            tui.send_action(Action::FlashTUIAppEncEntries);
+            tui.send_action(Action::FlashHomePageDisplayEncEntries);
+            while !tui.screen.is_home_page() {
+                tui.back_screen();
+            }
+            Ok(())
+        }));
+        yn.set_n_call_back_screen();
+        yn
+    }
+
     fn set_n_call_back_screen(&mut self) {
         self.set_n_call(Box::new(move |tui| {
             tui.back_screen();
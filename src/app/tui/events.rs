@@ -1,9 +1,11 @@
 use crate::app::context::SecurityContext;
-use crate::app::entry::ValidEntry;
+use crate::app::entry::{InputEntry, ValidEntry};
+use crate::app::secret::SecretString;
 use crate::app::tui::intents::ScreenIntent;
 use anyhow::Result;
 use ratatui::crossterm::event::{self, Event as CEvent};
 use ratatui::prelude::{Alignment, Color};
+use std::fmt::{Display, Formatter};
 use std::{
     sync::mpsc,
     thread,
@@ -38,24 +40,76 @@ pub enum Action {
     OptionYNTuiCallback(crate::app::tui::components::yn::FnCallYN),
     /// 设定TUI hot msg, 该结构内包含信息，持续时间，位置
     SetTuiHotMsg(String, Option<u8>, Option<Alignment>, Option<Color>),
-    /// 新的加密实体插入，插入必要全局刷新 vec，因为插入到库前还不知道id
-    EntryInsert(ValidEntry),
-    /// 更新加密实体，u32为id
-    EntryUpdate(ValidEntry, u32),
-    /// 删除加密实体，u32为id
+    /// 新的加密实体插入，连同其标签名及分组路径，插入必要全局刷新 vec
+    EntryInsert(ValidEntry, Vec<String>, String),
+    /// 更新加密实体，连同其标签名及分组路径
+    EntryUpdate(ValidEntry, Vec<String>, String),
+    /// 删除加密实体（移入回收站，可短时撤销），u32为id
     EntryRemove(u32),
+    /// 撤销最近的删除，仅在删除后的短时间内有效
+    UndoEntryRemove,
+    /// 将回收站中的实体恢复，u32为id
+    EntryRestore(u32),
+    /// 永久删除回收站中的实体，u32为id
+    EntryPurge(u32),
+    /// 刷新回收站页面显示的 entries
+    FlashTrashEntries,
     /// 刷寻 tui-app 载荷的 enc_entries
     FlashTUIAppEncEntries,
     /// 刷新 home-page 显示的 entries
     FlashHomePageDisplayEncEntries,
     /// 主密码校验成功时会载荷 securityContext
     MainPwdVerifySuccess(SecurityContext),
-    /// 复制内容到系统剪贴板
-    CopyToSysClipboard(String),
+    /// 主密码校验失败
+    MainPwdVerifyFailed,
+    /// 按默认密码生成策略生成密码，填入编辑页面的 password 输入框
+    GeneratePassword,
+    /// 复制内容到系统剪贴板，String 为其名称，用于提示
+    CopyToSysClipboard(SecretString, String),
+    /// 复制某条目的某字段到系统剪贴板，未验证主密码时先要求验证，u32为id
+    CopyEntryField(u32, CopyField),
     /// tui程序退出
     Quit,
 }
 
+/// 可复制到系统剪贴板的条目字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyField {
+    Username,
+    Password,
+    Notes,
+    /// notes 的某行，从 1 开始
+    NotesLine(u8),
+    /// 第 n 个自定义字段的值，从 1 开始
+    Custom(u8),
+}
+
+impl CopyField {
+    /// 取条目中该字段的值，为空或 notes 无该行时 None
+    pub fn pick(self, entry: &InputEntry) -> Option<&str> {
+        let value = match self {
+            CopyField::Username => entry.username.as_str(),
+            CopyField::Password => entry.password.as_str(),
+            CopyField::Notes => entry.notes.as_str(),
+            CopyField::NotesLine(n) => entry.notes.lines().nth(n.checked_sub(1)? as usize)?,
+            CopyField::Custom(n) => entry.fields.get(n.checked_sub(1)? as usize)?.value.as_str(),
+        };
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+impl Display for CopyField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CopyField::Username => write!(f, "Username"),
+            CopyField::Password => write!(f, "Password"),
+            CopyField::Notes => write!(f, "Notes"),
+            CopyField::NotesLine(n) => write!(f, "Notes line {n}"),
+            CopyField::Custom(n) => write!(f, "Field {n}"),
+        }
+    }
+}
+
 /// Terminal event handler.
 #[derive(Debug)]
 pub struct EventQueue {
@@ -154,3 +208,30 @@ impl EventThread {
         let _ = self.sender.send(event);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::entry::CustomField;
+
+    #[test]
+    fn test_copy_field_pick() {
+        let entry = InputEntry {
+            about: "about".into(),
+            notes: "first\n\nthird".into(),
+            username: "user".into(),
+            password: String::new(),
+            tags: Vec::new(),
+            group: String::new(),
+            fields: vec![CustomField { name: "host".into(), value: "example.com".into(), secret: false }],
+        };
+        assert_eq!(CopyField::Username.pick(&entry), Some("user"));
+        assert_eq!(CopyField::Password.pick(&entry), None);
+        assert_eq!(CopyField::NotesLine(1).pick(&entry), Some("first"));
+        assert_eq!(CopyField::NotesLine(2).pick(&entry), None);
+        assert_eq!(CopyField::NotesLine(3).pick(&entry), Some("third"));
+        assert_eq!(CopyField::NotesLine(4).pick(&entry), None);
+        assert_eq!(CopyField::Custom(1).pick(&entry), Some("example.com"));
+        assert_eq!(CopyField::Custom(2).pick(&entry), None);
+    }
+}
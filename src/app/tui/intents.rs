@@ -1,8 +1,8 @@
 use crate::app::entry::InputEntry;
 use crate::app::tui::TUIApp;
 use crate::app::tui::components::Screen;
-use crate::app::tui::components::Screen::{Details, Edit, YNOption};
-use crate::app::tui::components::states::EditingState;
+use crate::app::tui::components::Screen::{Details, YNOption};
+use crate::app::tui::components::states::{HistoryState, HistoryVersion, PasswordReveal, TrashState};
 use crate::app::tui::components::yn::YNState;
 use anyhow::Context;
 
@@ -17,16 +17,17 @@ pub enum ScreenIntent {
     ToDetail(u32),
     ToEditing(Option<u32>), // 有id为更新，无id为编辑
     ToDeleteYNOption(u32),
+    ToHistory(u32),
+    ToRestoreYNOption(u32, u32), // 条目id，要恢复的历史版本id
+    ToTrash,
+    ToPurgeYNOption(u32),
     ToSaveYNOption(InputEntry, Option<u32>), // 保存提示页面
 }
 
 impl ScreenIntent {
     /// 表达该 屏幕 在进入前是否需要 主密码
     pub fn is_before_enter_need_main_pwd(&self) -> bool {
-        match self {
-            ScreenIntent::ToHelp | ScreenIntent::ToHomePageV1 => false,
-            _ => true,
-        }
+        !matches!(self, ScreenIntent::ToHelp | ScreenIntent::ToHomePageV1)
     }
 }
 
@@ -44,8 +45,9 @@ impl ScreenIntent {
                         .storage
                         .select_entry_by_id(*e_id)
                         .context("not found entry")?;
+                    let encrypted_entry = tui.context.with_tags_and_group(encrypted_entry)?;
                     let entry = encrypted_entry.decrypt(tui.context.try_encrypter()?)?;
-                    Ok(Details(entry, *e_id))
+                    Ok(Details(entry, *e_id, PasswordReveal::new(&tui.context.cfg.inner_cfg)))
                 }
                 // 有id为编辑页面
                 ScreenIntent::ToEditing(Some(e_id)) => {
@@ -54,18 +56,78 @@ impl ScreenIntent {
                         .storage
                         .select_entry_by_id(*e_id)
                         .context("not found entry")?;
+                    let encrypted_entry = tui.context.with_tags_and_group(encrypted_entry)?;
                     let entry = encrypted_entry.decrypt(tui.context.try_encrypter()?)?;
-                    Ok(Edit(EditingState::new_updating(entry, *e_id)))
+                    Ok(Screen::new_edit_updating(
+                        entry,
+                        *e_id,
+                        PasswordReveal::new(&tui.context.cfg.inner_cfg),
+                    ))
                 }
-                ScreenIntent::ToEditing(None) => Ok(Edit(EditingState::new_creating())),
+                ScreenIntent::ToEditing(None) => Ok(Screen::new_edit_creating(PasswordReveal::new(
+                    &tui.context.cfg.inner_cfg,
+                ))),
                 ScreenIntent::ToDeleteYNOption(e_id) => {
                     let encrypted_entry = tui
                         .context
                         .storage
                         .select_entry_by_id(*e_id)
-                        .context("not found entry")?;
+                        .context("not found entry")?
+                        .decrypt_about_notes(tui.context.try_encrypter()?)?;
                     Ok(YNOption(YNState::new_delete_tip(encrypted_entry)))
                 }
+                ScreenIntent::ToHistory(e_id) => {
+                    let encrypter = tui.context.try_encrypter()?;
+                    let about = tui
+                        .context
+                        .storage
+                        .select_entry_by_id(*e_id)
+                        .context("not found entry")?
+                        .decrypt_about_notes(encrypter)?
+                        .about;
+                    let versions = tui
+                        .context
+                        .storage
+                        .select_entry_history(*e_id)
+                        .into_iter()
+                        .map(|v| {
+                            Ok(HistoryVersion {
+                                h_id: v.id,
+                                saved_time: v.entry.updated_time,
+                                archived_time: v.archived_time,
+                                entry: v.entry.decrypt(encrypter)?,
+                            })
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    let reveal = PasswordReveal::new(&tui.context.cfg.inner_cfg);
+                    Ok(Screen::History(Box::new(HistoryState::new(*e_id, about, versions, reveal))))
+                }
+                ScreenIntent::ToRestoreYNOption(e_id, h_id) => {
+                    let version = tui
+                        .context
+                        .storage
+                        .select_entry_history(*e_id)
+                        .into_iter()
+                        .find(|v| v.id == *h_id)
+                        .context("not found entry version")?;
+                    let saved_time = version.entry.updated_time;
+                    let encrypted_entry = version.entry.decrypt_about_notes(tui.context.try_encrypter()?)?;
+                    Ok(YNOption(YNState::new_restore_tip(encrypted_entry, *h_id, saved_time)))
+                }
+                ScreenIntent::ToTrash => {
+                    let entries = tui.context.select_all_trashed_listing_entry()?;
+                    let purge_days = tui.context.cfg.inner_cfg.trash_purge_days;
+                    Ok(Screen::Trash(Box::new(TrashState::new(entries, purge_days))))
+                }
+                ScreenIntent::ToPurgeYNOption(e_id) => {
+                    let trashed = tui
+                        .context
+                        .storage
+                        .select_trashed_entry_by_id(*e_id)
+                        .context("not found entry")?;
+                    let encrypted_entry = trashed.entry.decrypt_about_notes(tui.context.try_encrypter()?)?;
+                    Ok(YNOption(YNState::new_purge_tip(encrypted_entry)))
+                }
                 ScreenIntent::ToSaveYNOption(ve, e_id) => Ok(YNOption(YNState::new_save_tip(ve.clone(), *e_id))),
                 ScreenIntent::ToHomePageV1 => {
                     let all_enc: Vec<_> = tui.enc_entries.values().cloned().collect();
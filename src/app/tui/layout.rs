@@ -56,6 +56,7 @@ pub fn v_centered_percent(rect: Rect, centered_percent: u16) -> Rect {
 /// 水平分为n份，每份平均
 #[inline]
 #[cfg(test)]
+#[allow(dead_code)]
 pub fn horizontal_split<const N: usize>(rect: Rect) -> [Rect; N] {
     Layout::horizontal(Constraint::from_ratios([(1, N as u32); N])).areas(rect)
 }
@@ -63,6 +64,7 @@ pub fn horizontal_split<const N: usize>(rect: Rect) -> [Rect; N] {
 /// 返回 rect 的 底栏
 #[inline]
 #[cfg(test)]
+#[allow(dead_code)]
 pub fn bottom_rect(rect: Rect) -> Rect {
     Layout::vertical([Constraint::Fill(0), Constraint::Length(1)]).areas::<2>(rect)[1]
 }
@@ -72,10 +74,12 @@ where
     Self: Into<Rect>,
 {
     #[cfg(test)]
+    #[allow(dead_code)]
     fn h_centered_fixed(self, width: u16) -> Rect {
         h_centered_fixed(width, self.into())
     }
     #[cfg(test)]
+    #[allow(dead_code)]
     fn v_centered_fixed(self, height: u16) -> Rect {
         v_centered_fixed(height, self.into())
     }
@@ -84,20 +88,24 @@ where
         h_centered_percent(self.into(), centered_percent)
     }
     #[cfg(test)]
+    #[allow(dead_code)]
     fn v_centered_percent(self, centered_percent: u16) -> Rect {
         v_centered_percent(self.into(), centered_percent)
     }
     #[cfg(test)]
+    #[allow(dead_code)]
     fn centered_percent(self, percent_width: u16, percent_height: u16) -> Rect {
         centered_percent(percent_width, percent_height, self.into())
     }
 
     #[cfg(test)]
+    #[allow(dead_code)]
     fn bottom_rect(self) -> Rect {
         bottom_rect(self.into())
     }
 
     #[cfg(test)]
+    #[allow(dead_code)]
     fn horizontal_split<const N: usize>(self) -> [Rect; N] {
         horizontal_split(self.into())
     }
@@ -2,16 +2,18 @@
 //!
 //! 处理 事件循环主要模块
 
-use super::events::{Action, Event};
-use crate::app::consts::APP_NAME_AND_VERSION;
+use super::events::{Action, CopyField, Event};
+use crate::app::clipboard;
+use crate::app::consts::{APP_NAME_AND_VERSION, TRASH_UNDO_SEC};
 use crate::app::context::SecurityContext;
 use crate::app::entry::ValidEntry;
+use crate::app::secret::SecretString;
 use crate::app::tui::TUIApp;
-use crate::app::tui::components::Screen::{HomePageV1, InputMainPwd};
-use crate::app::tui::components::{EventHandler, Screen};
+use crate::app::tui::colors::{CL_AK, CL_D_YELLOW};
+use crate::app::tui::components::Screen::{HomePageV1, InputMainPwd, Trash};
+use crate::app::tui::components::{EventHandler, Screen, copy_field_actions};
 use crate::app::tui::intents::ScreenIntent;
-use anyhow::{Context, Result, anyhow};
-use arboard::Clipboard;
+use anyhow::{Context, Result};
 use crossterm::event::Event as CEvent;
 use ratatui::crossterm;
 use ratatui::crossterm::event::KeyEventKind;
@@ -92,18 +94,25 @@ impl TUIApp {
     fn handle_action(&mut self, action: Action) -> Result<()> {
         match action {
             Action::ScreenIntent(intent) => self.enter_screen_indent(intent)?,
-            Action::EntryInsert(v_e) => self.insert_entry(&v_e),
-            Action::EntryUpdate(v_e, e_id) => self.update_entry(&v_e, e_id),
+            Action::EntryInsert(v_e, tags, group) => self.insert_entry(&v_e, &tags, &group)?,
+            Action::EntryUpdate(v_e, tags, group) => self.update_entry(&v_e, &tags, &group)?,
             Action::EntryRemove(e_id) => self.remove_entry(e_id),
+            Action::UndoEntryRemove => self.undo_remove_entry(),
+            Action::EntryRestore(e_id) => self.restore_entry(e_id),
+            Action::EntryPurge(e_id) => self.purge_entry(e_id),
+            Action::FlashTrashEntries => self.flash_trash_vec()?,
             Action::FlashTUIAppEncEntries => self.flash_tui_vec()?,
             Action::FlashHomePageDisplayEncEntries => self.flash_home_page_vec()?,
             Action::MainPwdVerifySuccess(sec_context) => self.hold_security_context(sec_context)?,
+            Action::MainPwdVerifyFailed => self.main_pwd_verify_failed()?,
             Action::Quit => self.quit_tui_app(),
             Action::BackScreen => self.back_screen(),
             Action::Relock => self.relock()?,
             Action::Actions(actions) => self.handle_actions(actions)?,
             Action::OptionYNTuiCallback(callback) => callback(self)?,
-            Action::CopyToSysClipboard(info) => self.copy_to_sys_clip(info)?,
+            Action::CopyToSysClipboard(info, what) => self.copy_to_sys_clip(info, &what),
+            Action::CopyEntryField(e_id, field) => self.copy_entry_field(e_id, field)?,
+            Action::GeneratePassword => self.generate_password()?,
             Action::SetTuiHotMsg(msg, live_time, ali, color) => {
                 self.hot_msg.set_msg(&msg, live_time, ali, color)
             }
@@ -119,13 +128,55 @@ impl TUIApp {
         Ok(())
     }
 
-    /// 将给定内容复制到系统剪贴板
-    ///
-    /// https://crates.io/crates/arboard
-    pub fn copy_to_sys_clip(&self, info: String) -> Result<()> {
-        Clipboard::new()?
-            .set_text(info)
-            .context("Failed to set clipboard contents")
+    /// 按 data file 的默认密码生成策略生成密码，填入当前编辑页面的 password 输入框
+    fn generate_password(&mut self) -> Result<()> {
+        let policy = self.context.gen_policy(None)?;
+        if let Screen::Edit(state) = &mut self.screen {
+            state.fill_password(&policy.generate()?);
+            self.hot_msg
+                .set_msg(&format!("Generated password ({policy})"), Some(3), Some(Alignment::Center), None);
+        }
+        Ok(())
+    }
+
+    /// 将给定内容复制到系统剪贴板并提示，记录之以便超时等情况下清除，
+    /// 无可用的剪贴板后端时仅提示，不中断TUI
+    pub fn copy_to_sys_clip(&mut self, info: SecretString, what: &str) {
+        match clipboard::copy(self.context.cfg.clipboard, &info) {
+            Ok(copied) => {
+                self.hot_msg.set_msg(
+                    &format!("[󰅉] {what} has been copied to the clipboard ({})", copied.backend()),
+                    Some(5),
+                    None,
+                    Some(CL_AK),
+                );
+                self.clipboard_clear.hold(copied);
+            }
+            Err(e) => self.hot_msg.set_msg(
+                &format!("[!] {what} was not copied: {e}"),
+                Some(8),
+                Some(Alignment::Center),
+                Some(CL_D_YELLOW),
+            ),
+        }
+    }
+
+    /// 复制给定条目的某字段到系统剪贴板，
+    /// 未验证主密码时先进入要求主密码的页面，验证通过后回退该页面再复制
+    fn copy_entry_field(&mut self, e_id: u32, field: CopyField) -> Result<()> {
+        if !self.context.is_verified() {
+            let verify = Screen::new_copy_field_verify(e_id, field, &self.context)?;
+            let old_scr = std::mem::replace(&mut self.screen, verify);
+            self.back_screen.push(old_scr);
+            return Ok(());
+        }
+        let entry = self
+            .context
+            .storage
+            .select_entry_by_id(e_id)
+            .context("not found entry")?
+            .decrypt(self.context.try_encrypter()?)?;
+        self.handle_action(copy_field_actions(&entry, field))
     }
 
     /// Handles the tick event of the terminal.
@@ -142,6 +193,15 @@ impl TUIApp {
             self.quit_tui_app();
         }
         self.hot_msg.tick();
+        self.trash_undo.tick();
+        // 回退栈中的详情及编辑页面亦计时
+        for screen in std::iter::once(&mut self.screen).chain(self.back_screen.iter_mut()) {
+            screen.tick_password_reveal();
+        }
+        if self.clipboard_clear.tick() && self.clipboard_clear.clear() {
+            self.hot_msg
+                .set_msg("Clipboard cleared", Some(3), Some(Alignment::Center), None);
+        }
         Ok(())
     }
 
@@ -176,6 +236,14 @@ impl TUIApp {
         if is_do_relock {
             // 丢弃security上下文
             self.context.security_context = None;
+            self.clipboard_clear.clear();
+            // 锁定后不可再撤销删除，恢复条目同其他修改一样要求主密码
+            self.trash_undo.take();
+            if self.context.is_full_encrypt() {
+                // 全加密模式下，锁定后 about 及 notes 不再可见
+                self.flash_tui_vec()?;
+                self.flash_home_page_vec()?;
+            }
             if self.idle_tick.need_relock() {
                 self.hot_msg.set_msg(
                     "[!] AUTO RELOCK (idle)",
@@ -190,33 +258,69 @@ impl TUIApp {
 
     pub fn quit_tui_app(&mut self) {
         self.running = false;
+        // 退出时清除剪贴板
+        self.clipboard_clear.clear();
     }
 
-    /// 向 db 删除一个 entry，并更新 store_entry_count - 1
+    /// 将一个 entry 移入回收站，短时间内可撤销之
     fn remove_entry(&mut self, e_id: u32) {
-        self.context.storage.delete_entry(e_id);
+        let about = self.enc_entries.get(&e_id).map(|e| e.about.clone()).unwrap_or_default();
+        self.context.storage.trash_entry(e_id);
+        self.trash_undo.hold(e_id);
+        self.hot_msg.set_msg(
+            &format!("Moved '{about}' to the trash, <Z> undo"),
+            Some(TRASH_UNDO_SEC),
+            Some(Alignment::Center),
+            Some(CL_AK),
+        );
         self.send_action(Action::FlashTUIAppEncEntries);
         self.send_action(Action::FlashHomePageDisplayEncEntries);
     }
-    /// 向 db 添加一个 entry，并更新 store_entry_count + 1
-    fn insert_entry(&mut self, e: &ValidEntry) {
-        self.context.storage.insert_entry(e);
+    /// 恢复最近移入回收站的 entry，超过可撤销的时间则仅提示
+    fn undo_remove_entry(&mut self) {
+        match self.trash_undo.take() {
+            Some(e_id) => self.restore_entry(e_id),
+            None => self
+                .hot_msg
+                .set_msg("[!] Nothing to undo", Some(3), Some(Alignment::Center), Some(CL_D_YELLOW)),
+        }
+    }
+    /// 将回收站中的一个 entry 恢复
+    fn restore_entry(&mut self, e_id: u32) {
+        if self.context.storage.restore_trashed_entry(e_id) {
+            self.hot_msg
+                .set_msg("Entry restored", Some(3), Some(Alignment::Center), Some(CL_AK));
+        }
         self.send_action(Action::FlashTUIAppEncEntries);
         self.send_action(Action::FlashHomePageDisplayEncEntries);
+        self.send_action(Action::FlashTrashEntries);
+    }
+    /// 永久删除回收站中的一个 entry
+    fn purge_entry(&mut self, e_id: u32) {
+        self.context.storage.delete_entry(e_id);
+        self.send_action(Action::FlashTrashEntries);
+    }
+    /// 向 db 添加一个 entry 及其标签和分组，并更新 store_entry_count + 1
+    fn insert_entry(&mut self, e: &ValidEntry, tags: &[String], group: &str) -> Result<()> {
+        self.context.insert_entry(e, tags, group)?;
+        self.send_action(Action::FlashTUIAppEncEntries);
+        self.send_action(Action::FlashHomePageDisplayEncEntries);
+        Ok(())
     }
 
-    fn update_entry(&mut self, e: &ValidEntry, e_id: u32) {
-        self.context.storage.update_entry(e, e_id);
+    /// 向 db 更新一个 entry 及其标签和分组，其更新前的版本归档为历史版本
+    fn update_entry(&mut self, e: &ValidEntry, tags: &[String], group: &str) -> Result<()> {
+        self.context.update_entry(e, tags, group)?;
         self.send_action(Action::FlashTUIAppEncEntries);
         self.send_action(Action::FlashHomePageDisplayEncEntries);
+        Ok(())
     }
 
     /// 通过从db文件中重新查询以更新 tui-app hashmap中载荷的加密实体
     fn flash_tui_vec(&mut self) -> Result<()> {
         let enc_entries: HashMap<_, _> = self
             .context
-            .storage
-            .select_all_entry()
+            .select_all_listing_entry()?
             .into_iter()
             .map(|e| (e.id, e))
             .collect();
@@ -224,13 +328,27 @@ impl TUIApp {
         Ok(())
     }
 
+    /// 刷新回收站页面显示的 entries，回收站页面可能为当前屏幕，也可能在回退栈中
+    fn flash_trash_vec(&mut self) -> Result<()> {
+        let trash = std::iter::once(&mut self.screen)
+            .chain(self.back_screen.iter_mut())
+            .find_map(|s| if let Trash(state) = s { Some(state) } else { None });
+        if let Some(state) = trash {
+            state.reset_entries(self.context.select_all_trashed_listing_entry()?);
+        }
+        Ok(())
+    }
+
+    /// 刷新 home-page 显示的 entries，
+    /// home-page 可能为当前屏幕，也可能在回退栈中（如在其上层屏幕解锁或锁定时）
     fn flash_home_page_vec(&mut self) -> Result<()> {
-        if let HomePageV1(state) = &mut self.screen {
+        let home_page = std::iter::once(&mut self.screen)
+            .chain(self.back_screen.iter_mut())
+            .find_map(|s| if let HomePageV1(state) = s { Some(state) } else { None });
+        if let Some(state) = home_page {
             state.reset_display_entries(self.enc_entries.values());
-            Ok(())
-        } else {
-            unreachable!("flash_home_page_vec")
         }
+        Ok(())
     }
 
     /// 这是验证通过的事件处理终端方法
@@ -239,11 +357,40 @@ impl TUIApp {
     /// 并将当前屏幕切换为目标屏幕
     fn hold_security_context(&mut self, security_context: SecurityContext) -> Result<()> {
         if let InputMainPwd(state) = &mut self.screen {
-            self.context.security_context = Some(security_context);
+            let failed = self.context.hold_security_context(security_context)?;
             let intent = state.call_verified();
-            self.handle_action(intent)
+            if self.context.is_full_encrypt() {
+                // 全加密模式下，解锁后方可显示 about 及 notes
+                self.flash_tui_vec()?;
+                self.flash_home_page_vec()?;
+            }
+            self.handle_action(intent)?;
+            // 目标屏幕已进入，再显示失败记录，以免被其 hot msg 覆盖
+            if let Some(failed) = failed {
+                self.hot_msg.set_msg(
+                    &format!("[!] {}", failed.summary()),
+                    Some(8),
+                    Some(Alignment::Center),
+                    Some(CL_D_YELLOW),
+                );
+            }
+            Ok(())
         } else {
             unreachable!("hold_security_context")
         }
     }
+
+    /// 主密码校验失败的事件处理终端方法
+    ///
+    /// 该方法内将记录失败（跨进程累计），并自增当前屏幕的重试次数，
+    /// 失败次数达到上限时 Err
+    fn main_pwd_verify_failed(&mut self) -> Result<()> {
+        if let InputMainPwd(state) = &mut self.screen {
+            let retry_after = self.context.record_failed_unlock()?;
+            state.set_retry_after(retry_after);
+            state.increment_retry_count()
+        } else {
+            unreachable!("main_pwd_verify_failed")
+        }
+    }
 }
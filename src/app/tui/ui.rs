@@ -1,4 +1,4 @@
-use crate::app::consts::{ALLOC_INVALID_MAIN_PASS_MAX, KEY_LEFT_ICON};
+use crate::app::consts::{ALLOC_INVALID_MAIN_PASS_MAX, KEY_LEFT_ICON, PASSWORD_MASK_CHAR};
 use crate::app::entry::InputEntry;
 use crate::app::tui::colors::{CL_AK, CL_BLACK, CL_BLUE, CL_DD_WHITE, CL_D_RED, CL_D_YELLOW, CL_LL_BLACK, CL_L_BLACK, CL_RED, CL_WHITE, CL_YELLOW};
 use crate::app::tui::components::states::VerifyMPHState;
@@ -8,7 +8,7 @@ use crate::app::tui::ui::home_page::HomePageV1Widget;
 use crate::app::tui::{layout, TUIApp};
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Alignment, Constraint, Offset, Rect};
-use ratatui::prelude::{Layout, Line, Modifier, Style, Stylize, Widget};
+use ratatui::prelude::{Layout, Line, Modifier, Span, Style, Stylize, Widget};
 use ratatui::prelude::{StatefulWidget, Text};
 use ratatui::widgets::{Block, BorderType, Borders, Padding};
 use ratatui::widgets::{Clear, Paragraph, Wrap};
@@ -16,7 +16,9 @@ use tui_textarea::TextArea;
 
 mod editing;
 pub mod help;
+mod history;
 pub mod home_page;
+mod trash;
 
 impl Widget for &mut TUIApp {
     /// 渲染函数入口
@@ -66,15 +68,33 @@ impl Widget for &mut TUIApp {
                 match help_who {
                     Screen::HomePageV1(..) => help::HelpPage::home_page().render(rect, buf, list_cursor),
                     Screen::Details(..) => help::HelpPage::detail().render(rect, buf, list_cursor),
+                    Screen::History(..) => help::HelpPage::history().render(rect, buf, list_cursor),
+                    Screen::Trash(..) => help::HelpPage::trash().render(rect, buf, list_cursor),
                     Screen::Edit(..) => help::HelpPage::editing().render(rect, buf, list_cursor),
                     _ => (),
                 }
             }
-            Screen::Details(entry, _) => {
+            Screen::Details(entry, _, reveal) => {
                 self.hot_msg
-                    .set_always_if_none("󰌌 <ESC>|<Q> back, <E> edit, <C> CP, <D> delete, <L> relock");
+                    .set_always_if_none("󰌌 <ESC>|<Q> back, <E> edit, <C>|<U>|<N> CP, <R> reveal, <H> history, <D> delete, <L> relock");
                 let rect = layout::centered_percent(90, 90, middle);
-                entry.render(rect, buf);
+                EntryDetails {
+                    entry,
+                    reveal_password: reveal.is_revealed(),
+                }
+                .render(rect, buf);
+            }
+            Screen::History(state) => {
+                self.hot_msg
+                    .set_always_if_none("󰌌 <ESC>|<Q> back, ↓↑jk move, <C>|<U> CP, <R> reveal, <ENTER> restore");
+                let rect = layout::centered_percent(90, 90, middle);
+                state.render(rect, buf);
+            }
+            Screen::Trash(state) => {
+                self.hot_msg
+                    .set_always_if_none("󰌌 <ESC>|<Q> back, ↓↑jk move, <R>|<ENTER> restore, <D> delete permanently");
+                let rect = layout::centered_percent(90, 90, middle);
+                state.render(rect, buf);
             }
             Screen::Edit(state) => {
                 self.hot_msg
@@ -258,20 +278,62 @@ pub fn new_input_textarea(place_holder_text: Option<&str>, activate_state: bool)
     textarea
 }
 
-/// inputEntry直接的 渲染逻辑
-impl Widget for &InputEntry {
+/// 标签以 `#` 前缀、空格分隔显示，与 find 中标签的写法一致
+pub fn tag_chips(tags: &[String]) -> String {
+    tags.iter().map(|t| format!("#{t}")).collect::<Vec<_>>().join(" ")
+}
+
+/// 详情页面显示的条目，密码未显示时以掩码代替
+struct EntryDetails<'a> {
+    entry: &'a InputEntry,
+    reveal_password: bool,
+}
+
+/// 详情页面的 渲染逻辑
+impl Widget for EntryDetails<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let block = Block::bordered().border_type(BorderType::Plain);
         block.render(area, buf);
         Clear.render(area, buf);
-        let name = self.about.as_str();
-        let desc = self.notes.as_str();
-        let identity = self.username.as_str();
-        let password = self.password.as_str();
+        let name = self.entry.about.as_str();
+        let desc = self.entry.notes.as_str();
+        let tags = tag_chips(&self.entry.tags);
+        let group = self.entry.group.as_str();
+        let identity = self.entry.username.as_str();
+        let masked: String;
+        let password = if self.reveal_password {
+            self.entry.password.as_str()
+        } else {
+            masked = std::iter::repeat_n(PASSWORD_MASK_CHAR, 8).collect();
+            masked.as_str()
+        };
+        // 自定义字段，秘密字段的值同密码掩码显示
+        let fields: Vec<Line> = self
+            .entry
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let value = if field.secret && !self.reveal_password {
+                    Span::raw(std::iter::repeat_n(PASSWORD_MASK_CHAR, 8).collect::<String>())
+                } else {
+                    Span::raw(field.value.as_str())
+                };
+                let icon = if field.secret { "󰌿" } else { "󰦨" };
+                Line::from(vec![
+                    Span::raw(format!("{} {icon} ", i + 1)).fg(CL_D_YELLOW),
+                    Span::raw(format!("{}: ", field.name)).fg(CL_AK),
+                    value,
+                ])
+            })
+            .collect();
+        let fields_height = if fields.is_empty() { 0 } else { fields.len() as u16 + 2 };
         let rc = Layout::vertical([
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(fields_height),
             Constraint::Fill(0),
         ])
         .split(area);
@@ -279,17 +341,28 @@ impl Widget for &InputEntry {
         let b_name = Block::bordered().title(" 󰦨 about ").fg(CL_WHITE);
         let b_ident = Block::bordered().title(" 󰌿 username ").fg(CL_AK);
         let b_password = Block::bordered().title(" 󰌿 password ").fg(CL_AK);
+        let b_group = Block::bordered().title(" 󰉋 group ").fg(CL_WHITE);
+        let b_tags = Block::bordered().title(" 󰓹 tags ").fg(CL_WHITE);
         let b_description = Block::bordered().title(" 󰦨 notes ").fg(CL_WHITE);
 
         Paragraph::new(name).block(b_name).render(rc[0], buf);
         Paragraph::new(identity).block(b_ident).render(rc[1], buf);
         Paragraph::new(password).block(b_password).render(rc[2], buf);
+        // 分组与标签同一行
+        let [group_area, tags_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(rc[3]);
+        Paragraph::new(group).block(b_group).render(group_area, buf);
+        Paragraph::new(tags).fg(CL_D_YELLOW).block(b_tags).render(tags_area, buf);
+        if !fields.is_empty() {
+            let b_fields = Block::bordered().title(" 󰌆 fields ").fg(CL_WHITE);
+            Paragraph::new(fields).block(b_fields).render(rc[4], buf);
+        }
         Paragraph::new(desc)
             // 虽然 detail直接切换到 edit notes显示过长的行部分会跳变
             // 但为了在detail时的信息完整性，允许跳变
             .wrap(Wrap { trim: false })
             .block(b_description)
-            .render(rc[3], buf);
+            .render(rc[5], buf);
     }
 }
 
@@ -1,21 +1,20 @@
 use crate::app::tui::colors::{CL_WHITE, CL_YELLOW};
-use crate::app::tui::components::states::EditingState;
+use crate::app::tui::components::states::{Editing, EditingFocus, EditingState};
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Layout, Rect};
+use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::prelude::{Stylize, Widget};
 use ratatui::widgets::{Block, Clear};
+use tui_textarea::TextArea;
 
 impl Widget for &EditingState {
     fn render(self, area: Rect, buf: &mut Buffer) {
         Clear.render(area, buf);
 
-        let areas: [Rect; 4] = Layout::vertical([
-            ratatui::layout::Constraint::Length(3),
-            ratatui::layout::Constraint::Length(3),
-            ratatui::layout::Constraint::Length(3),
-            ratatui::layout::Constraint::Fill(0), // notes
-        ])
-        .areas(area);
+        let fields = self.field_inputs();
+        // about username password group tags，各自定义字段一行，notes
+        let constraints = std::iter::repeat_n(Constraint::Length(3), 5 + fields.len())
+            .chain(std::iter::once(Constraint::Fill(0)));
+        let areas = Layout::vertical(constraints).split(area);
 
         let curr_editing = self.current_editing_type();
         let all_textarea = self.all_textarea();
@@ -37,37 +36,53 @@ impl Widget for &EditingState {
             " 󰌿 password "
         };
 
-        let b_about = Block::bordered().title(title_name).fg(CL_WHITE);
-        let b_username = Block::bordered().title(title_ident).fg(CL_WHITE);
-        let b_password = Block::bordered().title(title_password).fg(CL_WHITE);
-        let b_notes = Block::bordered().title(" 󰦨 notes ").fg(CL_WHITE);
-
-        let mut blocks = [
-            Some(b_about),
-            Some(b_username),
-            Some(b_password),
-            Some(b_notes),
+        let inputs = [
+            (Editing::About, title_name, areas[0]),
+            (Editing::Username, title_ident, areas[1]),
+            (Editing::Password, title_password, areas[2]),
+            (Editing::Group, " 󰉋 group ", areas[3]),
+            (Editing::Tags, " 󰓹 tags ", areas[4]),
+            (Editing::Notes, " 󰦨 notes ", areas[areas.len() - 1]),
         ];
+        for (editing, title, curr_area) in inputs {
+            let active = curr_editing == EditingFocus::Input(editing);
+            render_input(self.textarea(editing), title, active, curr_area, buf);
+        }
 
-        for idx in 0..4_usize {
-            let blc = blocks[idx].take().unwrap();
-            let curr_area = areas[idx];
-            let n_blc = if idx == curr_editing as usize {
-                // is_active
-                // 正在编辑的，fg yellow，光标显示
-                blc.fg(CL_YELLOW)
+        for (i, field) in fields.iter().enumerate() {
+            let [name_area, value_area] =
+                Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(areas[5 + i]);
+            // 有值却无名称的字段不可保存
+            let title_name = if field.name.is_empty() && !field.value.is_empty() {
+                " (*) 󰌆 field "
             } else {
-                // 非正在编辑的...
-                // fixed 修复因 notes太多时，因焦点切换，
-                // notes内容使用Paragraph渲染时与textarea渲染在光标在下方行时显示内容不一致问题
-                // 都统一使用textarea渲染... textarea光标的显示与否可通过
-                // textarea.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED));
-                // 进行切换... 参考自：https://github.com/rhysd/tui-textarea/blob/HEAD/examples/split.rs
-                blc
+                " 󰌆 field "
             };
-            let inner = n_blc.inner(curr_area);
-            n_blc.render(curr_area, buf);
-            all_textarea[idx].render(inner, buf);
+            let title_value = if field.secret { " 󰌿 secret value " } else { " 󰦨 value " };
+            let active_name = curr_editing == EditingFocus::FieldName(i);
+            let active_value = curr_editing == EditingFocus::FieldValue(i);
+            render_input(&field.name, title_name, active_name, name_area, buf);
+            render_input(&field.value, title_value, active_value, value_area, buf);
         }
     }
 }
+
+/// 渲染一个带标题的输入框，正在编辑的 fg yellow
+fn render_input(textarea: &TextArea<'static>, title: &str, active: bool, area: Rect, buf: &mut Buffer) {
+    let block = Block::bordered().title(title).fg(CL_WHITE);
+    let block = if active {
+        // 正在编辑的，fg yellow，光标显示
+        block.fg(CL_YELLOW)
+    } else {
+        // 非正在编辑的...
+        // fixed 修复因 notes太多时，因焦点切换，
+        // notes内容使用Paragraph渲染时与textarea渲染在光标在下方行时显示内容不一致问题
+        // 都统一使用textarea渲染... textarea光标的显示与否可通过
+        // textarea.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED));
+        // 进行切换... 参考自：https://github.com/rhysd/tui-textarea/blob/HEAD/examples/split.rs
+        block
+    };
+    let inner = block.inner(area);
+    block.render(area, buf);
+    textarea.render(inner, buf);
+}
@@ -51,7 +51,7 @@ impl<'a, const N: usize> StatefulWidget for &HelpPage<'a, N> {
     }
 }
 
-impl HelpPage<'static, 9> {
+impl HelpPage<'static, 14> {
     pub const fn editing() -> Self {
         Self {
             key_maps: [
@@ -91,12 +91,32 @@ impl HelpPage<'static, 9> {
                     key_map: "<CTRL+S>",
                     note: "save (create) | overwrite (update)",
                 },
+                KeyMapInfo {
+                    key_map: "<CTRL+G>",
+                    note: "generate password (policy 'default')",
+                },
+                KeyMapInfo {
+                    key_map: "<CTRL+R>",
+                    note: "reveal | mask password and secret field values",
+                },
+                KeyMapInfo {
+                    key_map: "<CTRL+N>",
+                    note: "add custom field (after the selected one)",
+                },
+                KeyMapInfo {
+                    key_map: "<CTRL+D>",
+                    note: "[field] remove selected custom field",
+                },
+                KeyMapInfo {
+                    key_map: "<CTRL+T>",
+                    note: "[field] toggle secret (encrypted, masked) | plain",
+                },
             ],
         }
     }
 }
 
-impl HelpPage<'static, 6> {
+impl HelpPage<'static, 12> {
     pub const fn detail() -> Self {
         Self {
             key_maps: [
@@ -106,7 +126,7 @@ impl HelpPage<'static, 6> {
                 },
                 KeyMapInfo {
                     key_map: "<D>",
-                    note: "delete current entry",
+                    note: "delete current entry (move to trash)",
                 },
                 KeyMapInfo {
                     key_map: "<L>",
@@ -116,10 +136,120 @@ impl HelpPage<'static, 6> {
                     key_map: "<E>",
                     note: "edit current entry",
                 },
+                KeyMapInfo {
+                    key_map: "<R>",
+                    note: "reveal | mask password and secret field values",
+                },
                 KeyMapInfo {
                     key_map: "<C>",
                     note: "copy password to system clipboard",
                 },
+                KeyMapInfo {
+                    key_map: "<U>",
+                    note: "copy username to system clipboard",
+                },
+                KeyMapInfo {
+                    key_map: "<N>",
+                    note: "copy notes to system clipboard",
+                },
+                KeyMapInfo {
+                    key_map: "<1>..<9>",
+                    note: "copy the n-th line of notes to system clipboard",
+                },
+                KeyMapInfo {
+                    key_map: "<ALT+1>..<ALT+9>",
+                    note: "copy the n-th custom field to system clipboard",
+                },
+                KeyMapInfo {
+                    key_map: "<H>",
+                    note: "previous versions of current entry",
+                },
+                KeyMapInfo {
+                    key_map: "<CTRL+C>",
+                    note: "quit app",
+                },
+            ],
+        }
+    }
+}
+
+impl HelpPage<'static, 9> {
+    pub const fn history() -> Self {
+        Self {
+            key_maps: [
+                KeyMapInfo {
+                    key_map: "<ESC>|<Q>",
+                    note: "back",
+                },
+                KeyMapInfo {
+                    key_map: "<↓>|<J>",
+                    note: "down",
+                },
+                KeyMapInfo {
+                    key_map: "<↑>|<K>",
+                    note: "up",
+                },
+                KeyMapInfo {
+                    key_map: "<g>|<G>",
+                    note: "newest | oldest version",
+                },
+                KeyMapInfo {
+                    key_map: "<R>",
+                    note: "reveal | mask password of selected version",
+                },
+                KeyMapInfo {
+                    key_map: "<C>",
+                    note: "copy password of selected version to system clipboard",
+                },
+                KeyMapInfo {
+                    key_map: "<U>",
+                    note: "copy username of selected version to system clipboard",
+                },
+                KeyMapInfo {
+                    key_map: "<ENTER>",
+                    note: "restore selected version (current version is kept in history)",
+                },
+                KeyMapInfo {
+                    key_map: "<L>",
+                    note: "back and relock",
+                },
+            ],
+        }
+    }
+}
+
+impl HelpPage<'static, 8> {
+    pub const fn trash() -> Self {
+        Self {
+            key_maps: [
+                KeyMapInfo {
+                    key_map: "<ESC>|<Q>",
+                    note: "back",
+                },
+                KeyMapInfo {
+                    key_map: "<↓>|<J>",
+                    note: "down",
+                },
+                KeyMapInfo {
+                    key_map: "<↑>|<K>",
+                    note: "up",
+                },
+                KeyMapInfo {
+                    key_map: "<g>|<G>",
+                    note: "first | last",
+                },
+                KeyMapInfo {
+                    key_map: "<R>|<ENTER>",
+                    note: "restore selected entry",
+                },
+                KeyMapInfo {
+                    key_map: "<D>",
+                    note: "delete selected entry permanently",
+                },
+                KeyMapInfo {
+                    key_map: "<L>",
+                    note: "back and relock",
+                },
                 KeyMapInfo {
                     key_map: "<CTRL+C>",
                     note: "quit app",
@@ -129,13 +259,13 @@ impl HelpPage<'static, 6> {
     }
 }
 
-impl HelpPage<'static, 13> {
+impl HelpPage<'static, 21> {
     pub const fn home_page() -> Self {
         Self {
             key_maps: [
                 KeyMapInfo {
                     key_map: "<F>",
-                    note: "find",
+                    note: "find (#tag matches tags)",
                 },
                 KeyMapInfo {
                     key_map: "<↓>|<J>",
@@ -159,11 +289,23 @@ impl HelpPage<'static, 13> {
                 },
                 KeyMapInfo {
                     key_map: "<ESC>",
-                    note: "quit app | [find] find | quit find",
+                    note: "quit app | [find] find | quit find | [groups] focus entries",
                 },
                 KeyMapInfo {
                     key_map: "<ENTER>",
-                    note: "current entry detail | [find] find",
+                    note: "current entry detail | [find] find | [groups] collapse | expand group",
+                },
+                KeyMapInfo {
+                    key_map: "<TAB>",
+                    note: "focus groups (up | down selects a group) | focus entries",
+                },
+                KeyMapInfo {
+                    key_map: "<H>|<←>",
+                    note: "[groups] collapse group | select parent group",
+                },
+                KeyMapInfo {
+                    key_map: "<→>",
+                    note: "[groups] expand group",
                 },
                 KeyMapInfo {
                     key_map: "<O>",
@@ -179,7 +321,27 @@ impl HelpPage<'static, 13> {
                 },
                 KeyMapInfo {
                     key_map: "<D>",
-                    note: "delete current entry",
+                    note: "delete current entry (move to trash)",
+                },
+                KeyMapInfo {
+                    key_map: "<C>",
+                    note: "copy current entry password",
+                },
+                KeyMapInfo {
+                    key_map: "<U>",
+                    note: "copy current entry username",
+                },
+                KeyMapInfo {
+                    key_map: "<N>",
+                    note: "copy current entry notes",
+                },
+                KeyMapInfo {
+                    key_map: "<Z>",
+                    note: "undo delete (shortly after deleting)",
+                },
+                KeyMapInfo {
+                    key_map: "<T>",
+                    note: "trash (restore | permanently delete entries)",
                 },
                 KeyMapInfo {
                     key_map: "<L>",
@@ -0,0 +1,81 @@
+use crate::app::consts::PASSWORD_MASK_CHAR;
+use crate::app::tui::colors::{CL_AK, CL_DD_WHITE, CL_DDD_WHITE, CL_LL_BLACK, CL_WHITE};
+use crate::app::tui::components::states::HistoryState;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Constraint, Rect};
+use ratatui::prelude::{Modifier, StatefulWidget, Style, Stylize, Widget};
+use ratatui::widgets::{Block, BorderType, Clear, HighlightSpacing, Paragraph, Row, Table};
+
+/// 时间列的格式
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 历史版本页面的渲染逻辑，仅光标所指版本的 password 可显示明文
+impl Widget for &mut HistoryState {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+        let block = Block::bordered()
+            .border_type(BorderType::Plain)
+            .title(format!(" 󰋚 history '{}' ", self.about()))
+            .fg(CL_WHITE);
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        if self.versions().is_empty() {
+            Paragraph::new("no previous versions")
+                .fg(CL_DDD_WHITE)
+                .alignment(Alignment::Center)
+                .render(inner_area, buf);
+            return;
+        }
+
+        let selected = self.cursor_mut_ref().selected();
+        let masked: String = std::iter::repeat_n(PASSWORD_MASK_CHAR, 8).collect();
+        let rows = self
+            .versions()
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let password = if selected == Some(i) && self.is_password_revealed() {
+                    v.entry.password.clone()
+                } else {
+                    masked.clone()
+                };
+                Row::new([
+                    v.saved_time.format(TIME_FORMAT).to_string(),
+                    v.archived_time.format(TIME_FORMAT).to_string(),
+                    v.entry.about.clone(),
+                    v.entry.username.clone(),
+                    password,
+                ])
+                .fg(CL_WHITE)
+            })
+            .collect::<Vec<_>>();
+
+        let header_style = Style::default().fg(CL_WHITE).bg(CL_LL_BLACK);
+        let header = Row::new(["Saved", "Replaced", "About", "Username", "Password"]).style(header_style);
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(20),
+                Constraint::Length(20),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header)
+        .highlight_symbol(" ")
+        .highlight_spacing(HighlightSpacing::Always)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED).fg(CL_DD_WHITE));
+        StatefulWidget::render(table, inner_area, buf, self.cursor_mut_ref());
+
+        // 显示明文的 password 以醒目颜色标记之
+        if self.is_password_revealed() {
+            Block::new()
+                .title_bottom(" 󰌿 password revealed ")
+                .title_alignment(Alignment::Right)
+                .fg(CL_AK)
+                .render(area, buf);
+        }
+    }
+}
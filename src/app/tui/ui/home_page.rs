@@ -1,14 +1,121 @@
-use crate::app::tui::colors::{CL_D_WHITE, CL_DD_WHITE, CL_LL_BLACK, CL_WHITE, CL_D_YELLOW};
+use crate::app::tui::colors::{CL_D_WHITE, CL_DD_WHITE, CL_LL_BLACK, CL_WHITE, CL_D_YELLOW, CL_YELLOW};
+use crate::app::tui::components::group_tree::GroupTreeState;
 use crate::app::tui::components::states::HomePageV1State;
 use crate::app::tui::layout::RectExt;
 use ratatui::layout::{Constraint, Layout, Rect};
-use ratatui::prelude::{Buffer, Margin, Modifier, StatefulWidget, Style, Stylize, Text, Widget};
+use ratatui::prelude::{Buffer, Line, Margin, Modifier, Span, StatefulWidget, Style, Stylize, Text, Widget};
 use ratatui::widgets::{
-    Block, BorderType, Borders, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation, Table,
+    Block, BorderType, Borders, HighlightSpacing, List, ListState, Paragraph, Row, Scrollbar, ScrollbarOrientation,
+    Table,
 };
+use unicode_width::UnicodeWidthStr;
 
 pub struct HomePageV1Widget;
 
+/// find 匹配的字符的样式
+const MATCHED_STYLE: Style = Style::new().fg(CL_YELLOW).add_modifier(Modifier::BOLD);
+
+/// tags 列的最大宽度
+const MAX_TAGS_WIDTH: u16 = 32;
+
+/// 分组树的最大宽度
+const MAX_GROUP_TREE_WIDTH: u16 = 28;
+
+/// 分组树，每个分组按层级缩进，有下级分组的以箭头表示其折叠与否，
+/// 光标所指的分组即过滤条目的分组，焦点在分组树时以反色显示之
+fn render_group_tree(tree: &GroupTreeState, area: Rect, buf: &mut Buffer) {
+    let block = Block::new()
+        .borders(Borders::RIGHT)
+        .border_type(BorderType::Plain)
+        .fg(if tree.is_focused() { CL_D_YELLOW } else { CL_DD_WHITE });
+    let inner = block.inner(area);
+    block.render(area, buf);
+    // 表头与条目表格的表头对齐
+    let [area_header, area_list] = Layout::vertical([Constraint::Length(1), Constraint::Fill(0)]).areas(inner);
+    Paragraph::new(" Groups")
+        .style(Style::default().fg(CL_WHITE).bg(CL_LL_BLACK))
+        .render(area_header, buf);
+
+    let mut items = vec![Line::from(" 󰉋 All")];
+    for node in tree.visible() {
+        let arrow = match (node.has_children, node.collapsed) {
+            (false, _) => " ",
+            (true, false) => "▾",
+            (true, true) => "▸",
+        };
+        items.push(Line::from(format!(" {}{} {}", "  ".repeat(node.depth), arrow, node.name)));
+    }
+    let highlight_style = if tree.is_focused() {
+        Style::new().add_modifier(Modifier::REVERSED).fg(CL_D_YELLOW)
+    } else {
+        MATCHED_STYLE
+    };
+    let list = List::new(items).fg(CL_WHITE).highlight_style(highlight_style);
+    StatefulWidget::render(list, area_list, buf, &mut ListState::default().with_selected(Some(tree.cursor())));
+}
+
+/// 分组树的显示宽度，含缩进、箭头及右边框
+fn group_tree_width(tree: &GroupTreeState) -> u16 {
+    let max = tree
+        .visible()
+        .iter()
+        .map(|n| n.depth * 2 + n.name.width_cjk() + 4)
+        .max()
+        .unwrap_or_default();
+    (max as u16 + 1).clamp(10, MAX_GROUP_TREE_WIDTH)
+}
+
+/// 标签以 `#` 前缀显示，其中 find 匹配的标签（`matched` 为其位置，升序）高亮
+fn tag_chips(tags: &[String], matched: &[usize]) -> Text<'static> {
+    let mut spans = Vec::with_capacity(tags.len() * 2);
+    for (i, tag) in tags.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" "));
+        }
+        let style = if matched.binary_search(&i).is_ok() {
+            MATCHED_STYLE
+        } else {
+            Style::new().fg(CL_DD_WHITE)
+        };
+        spans.push(Span::styled(format!("#{tag}"), style));
+    }
+    Text::from(Line::from(spans))
+}
+
+/// 文本，其中 find 匹配的字符（`indices` 为其位置，以 char 计，升序）高亮
+fn highlighted(text: &str, indices: &[usize]) -> Text<'static> {
+    if indices.is_empty() {
+        return Text::from(text.to_owned());
+    }
+    let mut lines = Vec::new();
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    let flush = |run: &mut String, matched: bool, spans: &mut Vec<Span<'static>>| {
+        if !run.is_empty() {
+            let run = std::mem::take(run);
+            spans.push(if matched { Span::styled(run, MATCHED_STYLE) } else { Span::raw(run) });
+        }
+    };
+    let mut indices = indices.iter().peekable();
+    for (i, c) in text.chars().enumerate() {
+        let matched = indices.next_if_eq(&&i).is_some();
+        if c == '\n' {
+            flush(&mut run, run_matched, &mut spans);
+            lines.push(Line::from(std::mem::take(&mut spans)));
+            continue;
+        }
+        if matched != run_matched {
+            flush(&mut run, run_matched, &mut spans);
+            run_matched = matched;
+        }
+        run.push(c);
+    }
+    flush(&mut run, run_matched, &mut spans);
+    lines.push(Line::from(spans));
+    Text::from(lines)
+}
+
 impl StatefulWidget for HomePageV1Widget {
     type State = HomePageV1State;
 
@@ -27,6 +134,18 @@ impl StatefulWidget for HomePageV1Widget {
 
         // 搜索框， list 区域， 底部
         let [area_find, area_table] = layout_v.areas(center);
+        // 有分组时 list 区域左侧为分组树
+        let area_table = if state.group_tree().is_empty() {
+            area_table
+        } else {
+            let [area_tree, area_table] = Layout::horizontal([
+                Constraint::Length(group_tree_width(state.group_tree())),
+                Constraint::Fill(0),
+            ])
+            .areas(area_table);
+            render_group_tree(state.group_tree(), area_tree, buf);
+            area_table
+        };
 
         // find 查找的字符渲染
         let mut find_input_block = Block::bordered().border_type(BorderType::Plain);
@@ -54,18 +173,19 @@ impl StatefulWidget for HomePageV1Widget {
         let rows = state
             .display_entries()
             .iter()
-            .enumerate()
-            .map(|(_, enc_entry)| {
+            .zip(state.display_matches())
+            .map(|(enc_entry, entry_match)| {
                 // fix 这里得用 clone，否则引用一直持续到调用 render，但是那里又需要可变引用，遂不行
                 // 这里只能clone获取所有权，但是有string的clone开销，后续得想办法不用clone开销...
-                let about = Text::from(enc_entry.about.clone());
-                let notes = enc_entry.notes.as_ref().map(|s| s.to_owned()).unwrap_or("".to_owned());
-                let notes = Text::from(notes);
-                Row::new([about, notes]).fg(CL_WHITE)
-            });
+                let about = highlighted(&enc_entry.about, &entry_match.about);
+                let tags = tag_chips(&enc_entry.tags, &entry_match.tags);
+                let notes = highlighted(enc_entry.notes.as_deref().unwrap_or(""), &entry_match.notes);
+                Row::new([about, tags, notes]).fg(CL_WHITE)
+            })
+            .collect::<Vec<_>>();
 
         let header_style = Style::default().fg(CL_WHITE).bg(CL_LL_BLACK);
-        let header = Row::new(["About", "Notes"]).style(header_style);
+        let header = Row::new(["About", "Tags", "Notes"]).style(header_style);
 
         let table = Table::new(
             rows,
@@ -73,6 +193,8 @@ impl StatefulWidget for HomePageV1Widget {
                 // 最小20，最大根据about长度计算之，最小20为防止about都短时过于靠左边...
                 // + 1 is for padding.
                 Constraint::Length(20.max(state.max_about_width() + 1)),
+                // 均无标签时为0，即不显示该列，标签过多时截断以免挤占 notes
+                Constraint::Length(state.max_tags_width().min(MAX_TAGS_WIDTH)),
                 Constraint::Fill(0),
             ],
         )
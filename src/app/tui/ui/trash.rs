@@ -0,0 +1,71 @@
+use crate::app::tui::colors::{CL_DD_WHITE, CL_DDD_WHITE, CL_LL_BLACK, CL_WHITE};
+use crate::app::tui::components::states::TrashState;
+use chrono::Days;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Constraint, Rect};
+use ratatui::prelude::{Modifier, StatefulWidget, Style, Stylize, Widget};
+use ratatui::widgets::{Block, BorderType, Clear, HighlightSpacing, Paragraph, Row, Table};
+
+/// 时间列的格式
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 回收站页面的渲染逻辑
+impl Widget for &mut TrashState {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+        let block = Block::bordered()
+            .border_type(BorderType::Plain)
+            .title(" 󰩹 trash ")
+            .fg(CL_WHITE);
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        if self.entries().is_empty() {
+            Paragraph::new("trash is empty")
+                .fg(CL_DDD_WHITE)
+                .alignment(Alignment::Center)
+                .render(inner_area, buf);
+            return;
+        }
+
+        let purge_days = self.purge_days();
+        let rows = self
+            .entries()
+            .iter()
+            .map(|t| {
+                // 不自动删除时无删除时间
+                let purge_time = match purge_days {
+                    0 => String::from("-"),
+                    days => t
+                        .deleted_time
+                        .checked_add_days(Days::new(days as u64))
+                        .map_or_else(|| String::from("-"), |t| t.format(TIME_FORMAT).to_string()),
+                };
+                Row::new([
+                    t.entry.about.clone(),
+                    t.entry.notes.clone().unwrap_or_default(),
+                    t.deleted_time.format(TIME_FORMAT).to_string(),
+                    purge_time,
+                ])
+                .fg(CL_WHITE)
+            })
+            .collect::<Vec<_>>();
+
+        let header_style = Style::default().fg(CL_WHITE).bg(CL_LL_BLACK);
+        let header = Row::new(["About", "Notes", "Deleted", "Purged"]).style(header_style);
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Fill(2),
+                Constraint::Length(20),
+                Constraint::Length(20),
+            ],
+        )
+        .header(header)
+        .highlight_symbol(" ")
+        .highlight_spacing(HighlightSpacing::Always)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED).fg(CL_DD_WHITE));
+        StatefulWidget::render(table, inner_area, buf, self.cursor_mut_ref());
+    }
+}